@@ -0,0 +1,9 @@
+//! The shared library of the C interface: `libsha_calc.so` (`.dylib`,
+//! `sha_calc.dll`), exporting the functions of [`rustedbytes_sha::capi`]
+//! declared in `include/sha_calc.h`.
+//!
+//! It is a separate package because a `cdylib` needs a panic handler, so
+//! the main crate could not list that crate type and still build without
+//! `std`.
+
+pub use rustedbytes_sha::capi::*;
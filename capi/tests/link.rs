@@ -0,0 +1,75 @@
+//! Builds a C program against `include/sha_calc.h` and the shared library,
+//! the way a C user would, and runs it.
+
+#![cfg(unix)]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const PROGRAM: &str = r#"
+#include <stdio.h>
+#include <string.h>
+#include "sha_calc.h"
+
+static void print_hex(const uint8_t *digest, int len) {
+    for (int i = 0; i < len; i++) {
+        printf("%02x", digest[i]);
+    }
+    printf("\n");
+}
+
+int main(int argc, char **argv) {
+    uint8_t digest[SHA_CALC_MAX_DIGEST_SIZE];
+    const char *text = "hello world";
+    int len = sha_calc_hash_bytes("sha256", (const uint8_t *)text, strlen(text), digest, sizeof digest);
+    if (len != sha_calc_digest_size("sha256")) {
+        return 1;
+    }
+    print_hex(digest, len);
+    print_hex(digest, sha_calc_hash_file("sha1", argv[argc - 1], digest, sizeof digest));
+    printf("%d %d\n", sha_calc_hash_bytes("md5", NULL, 0, digest, sizeof digest) == SHA_CALC_ERR_ALGORITHM,
+           sha_calc_hash_file("sha256", "/nonexistent", digest, sizeof digest) == SHA_CALC_ERR_IO);
+    return 0;
+}
+"#;
+
+#[test]
+fn test_c_program_links_against_the_library() {
+    let include = Path::new(env!("CARGO_MANIFEST_DIR")).join("../include");
+    // Cargo does not build a cdylib for the tests of its package
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut build = Command::new(cargo);
+    build.args(["build", "--quiet", "--package", env!("CARGO_PKG_NAME")]);
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    }
+    let status = build.status()
+        .unwrap();
+    assert!(status.success());
+    // The test runs from target/<profile>/deps; the library is one level up
+    let exe = std::env::current_exe().unwrap();
+    let library_dir = exe.parent().unwrap().parent().unwrap();
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi-link");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.c"), PROGRAM).unwrap();
+    fs::write(dir.join("input.txt"), "abc").unwrap();
+
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg("-I").arg(&include)
+        .arg(dir.join("main.c"))
+        .arg("-L").arg(library_dir)
+        .arg("-lsha_calc")
+        .arg(format!("-Wl,-rpath,{}", library_dir.display()))
+        .arg("-o").arg(dir.join("main"))
+        .status()
+        .expect("a C compiler (cc, or $CC) is needed to test the C interface");
+    assert!(status.success());
+
+    let output = Command::new(dir.join("main")).arg(dir.join("input.txt")).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\na9993e364706816aba3e25717850c26c9cd0d89d\n1 1\n",
+    );
+}
@@ -0,0 +1,48 @@
+/*
+ * C interface of rustedbytes-sha, built with `cargo build --release
+ * --package sha-calc-capi` (libsha_calc.so / libsha_calc.dylib / sha_calc.dll).
+ *
+ * Algorithms are named as for `sha-calc -a` ("sha256", "sha3-512",
+ * "blake2b", "crc32", ...), case-insensitively. The hashing functions write
+ * the raw digest to `out` and return its length, or a negative
+ * SHA_CALC_ERR_* code. Keep in sync with src/capi.rs.
+ */
+
+#ifndef SHA_CALC_H
+#define SHA_CALC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The algorithm name is unknown, or its support was not compiled in. */
+#define SHA_CALC_ERR_ALGORITHM (-1)
+/* A required pointer is null, or the path is not valid for the platform. */
+#define SHA_CALC_ERR_ARGUMENT (-2)
+/* out_len is smaller than the digest. */
+#define SHA_CALC_ERR_BUFFER (-3)
+/* The file could not be opened or read. */
+#define SHA_CALC_ERR_IO (-4)
+
+/* Largest digest of any algorithm, in bytes. */
+#define SHA_CALC_MAX_DIGEST_SIZE 64
+
+/* Digest length in bytes of `algorithm`. */
+int sha_calc_digest_size(const char *algorithm);
+
+/* Hashes `len` bytes at `data` (which may be NULL if len is 0). */
+int sha_calc_hash_bytes(const char *algorithm, const uint8_t *data, size_t len,
+                        uint8_t *out, size_t out_len);
+
+/* Hashes the file at `path`. */
+int sha_calc_hash_file(const char *algorithm, const char *path,
+                       uint8_t *out, size_t out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SHA_CALC_H */
@@ -0,0 +1,60 @@
+// JavaScript API of the WebAssembly build of rustedbytes-sha (`wasm`
+// feature, see src/wasm.rs and the README). Keep in sync with src/wasm.rs.
+//
+//   import { loadShaCalc } from "./sha_calc.js";
+//   const sha = await loadShaCalc(fetch("rustedbytes_sha.wasm"));
+//   sha.hashBytes("sha256", new TextEncoder().encode("hello world"));
+//   // "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+
+const ERRORS = {
+  [-1]: "unknown algorithm, or its support was not compiled in",
+  [-2]: "invalid argument or unknown encoding",
+  [-3]: "output buffer too small",
+};
+
+// Longest encoded digest of any algorithm and encoding
+const MAX_TEXT_LEN = 256;
+
+// Instantiates the module from a fetch() response (or a promise of one).
+export async function loadShaCalc(response) {
+  const { instance } = await WebAssembly.instantiateStreaming(response);
+  return wrap(instance.exports);
+}
+
+// The API over the exports of an already instantiated module.
+export function wrap(exports) {
+  const encoder = new TextEncoder();
+  const decoder = new TextDecoder();
+
+  // Views are made after each allocation: growing memory detaches old ones
+  function copyIn(bytes) {
+    const buffer = exports.sha_calc_alloc(bytes.length);
+    new Uint8Array(exports.memory.buffer, buffer, bytes.length).set(bytes);
+    return buffer;
+  }
+
+  return {
+    // Digest of `data` (a Uint8Array) with the algorithm named as for
+    // `sha-calc -a`, encoded as hex, base64, base32, zbase32 or proquint.
+    hashBytes(algorithm, data, encoding = "hex") {
+      const inputs = [encoder.encode(algorithm), encoder.encode(encoding), data];
+      const buffers = inputs.map(copyIn);
+      const out = exports.sha_calc_alloc(MAX_TEXT_LEN);
+      try {
+        const len = exports.sha_calc_hash(
+          buffers[0], inputs[0].length,
+          buffers[1], inputs[1].length,
+          buffers[2], inputs[2].length,
+          out, MAX_TEXT_LEN,
+        );
+        if (len < 0) {
+          throw new Error(`hashBytes: ${ERRORS[len] ?? `error ${len}`}`);
+        }
+        return decoder.decode(new Uint8Array(exports.memory.buffer, out, len));
+      } finally {
+        inputs.forEach((bytes, i) => exports.sha_calc_free(buffers[i], bytes.length));
+        exports.sha_calc_free(out, MAX_TEXT_LEN);
+      }
+    },
+  };
+}
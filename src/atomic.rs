@@ -0,0 +1,117 @@
+//! Atomic output files: data is written to a temporary sibling file which
+//! only replaces the destination once everything has been written.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub struct AtomicFile {
+    path: PathBuf,
+    temp_path: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// Starts writing `path`. With `append`, the current contents of `path`
+    /// (if it exists) are kept and new data is added after them.
+    pub fn create(path: &Path, append: bool) -> io::Result<AtomicFile> {
+        let file_name = path.file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output path has no file name"))?;
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+
+        let file = if append && path.exists() {
+            fs::copy(path, &temp_path)?;
+            OpenOptions::new().append(true).open(&temp_path)?
+        } else {
+            File::create(&temp_path)?
+        };
+
+        Ok(AtomicFile {
+            path: path.to_path_buf(),
+            temp_path,
+            writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Flushes the data to disk and moves it over the destination.
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            let file = writer.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+        }
+        fs::rename(&self.temp_path, &self.path)
+    }
+
+    fn writer(&mut self) -> &mut BufWriter<File> {
+        self.writer.as_mut().expect("AtomicFile used after commit")
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // Not committed (or the rename failed): discard the partial output
+        if self.temp_path.exists() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_replaces_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sha256");
+        fs::write(&path, "old\n").unwrap();
+
+        let mut file = AtomicFile::create(&path, false).unwrap();
+        file.write_all(b"new\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\n");
+
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_append_keeps_existing_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sha256");
+        fs::write(&path, "first\n").unwrap();
+
+        let mut file = AtomicFile::create(&path, true).unwrap();
+        file.write_all(b"second\n").unwrap();
+        file.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn test_drop_discards_uncommitted_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sha256");
+
+        let mut file = AtomicFile::create(&path, false).unwrap();
+        file.write_all(b"partial").unwrap();
+        drop(file);
+
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,159 @@
+//! Size detection for block devices (`/dev/sdb`, `\\.\PhysicalDrive1`),
+//! whose metadata reports a length of zero, so progress totals stay right
+//! when hashing whole disks; and recognition of streams, which have no size.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// The size in bytes of the regular file or block device opened as `file`.
+pub fn size(file: &File) -> io::Result<u64> {
+    if is_block_device(file)? {
+        device_size(file)
+    } else {
+        Ok(file.metadata()?.len())
+    }
+}
+
+/// Whether `path` is a FIFO, socket or character device, such as the
+/// `/dev/fd/63` of `<(command)`: read once until it ends, never probed for
+/// a size beforehand, since opening a FIFO consumes it.
+#[cfg(unix)]
+pub fn is_stream(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|meta| {
+        let kind = meta.file_type();
+        kind.is_fifo() || kind.is_socket() || kind.is_char_device()
+    })
+}
+
+#[cfg(not(unix))]
+pub fn is_stream(_path: &Path) -> bool {
+    false
+}
+
+/// Opens a stream for reading; Unix sockets are connected to, as they
+/// cannot be opened like files.
+pub fn open_stream(path: &Path) -> io::Result<Box<dyn Read>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if fs::metadata(path)?.file_type().is_socket() {
+            return Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?));
+        }
+    }
+    Ok(Box::new(File::open(path)?))
+}
+
+#[cfg(unix)]
+fn is_block_device(file: &File) -> io::Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+    Ok(file.metadata()?.file_type().is_block_device())
+}
+
+#[cfg(windows)]
+fn is_block_device(file: &File) -> io::Result<bool> {
+    // Volumes and physical drives are neither files nor directories
+    Ok(file.metadata().map_or(true, |meta| !meta.is_file() && !meta.is_dir()))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_block_device(_file: &File) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// `BLKGETSIZE64`: the device size in bytes.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn device_size(file: &File) -> io::Result<u64> {
+    use std::os::unix::io::AsRawFd;
+    // _IOR(0x12, 114, size_t)
+    const BLKGETSIZE64: u32 = 0x8000_1272 | ((std::mem::size_of::<usize>() as u32) << 16);
+
+    let mut size = 0u64;
+    // SAFETY: BLKGETSIZE64 writes a single u64 through the pointer
+    if unsafe { libc::ioctl(file.as_raw_fd(), BLKGETSIZE64 as libc::Ioctl, &mut size) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(size)
+}
+
+/// `DKIOCGETBLOCKCOUNT` times `DKIOCGETBLOCKSIZE`.
+#[cfg(target_os = "macos")]
+fn device_size(file: &File) -> io::Result<u64> {
+    use std::os::unix::io::AsRawFd;
+    // _IOR('d', 24, uint32_t) and _IOR('d', 25, uint64_t)
+    const DKIOCGETBLOCKSIZE: libc::c_ulong = 0x4004_6418;
+    const DKIOCGETBLOCKCOUNT: libc::c_ulong = 0x4008_6419;
+
+    let mut block_size = 0u32;
+    let mut block_count = 0u64;
+    // SAFETY: each ioctl writes a single integer of the given type through the pointer
+    unsafe {
+        if libc::ioctl(file.as_raw_fd(), DKIOCGETBLOCKSIZE, &mut block_size) < 0
+            || libc::ioctl(file.as_raw_fd(), DKIOCGETBLOCKCOUNT, &mut block_count) < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(u64::from(block_size) * block_count)
+}
+
+/// Other Unix systems: the offset of the end of the device.
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android", target_os = "macos"))))]
+fn device_size(mut file: &File) -> io::Result<u64> {
+    use std::io::{Seek, SeekFrom};
+    let size = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(size)
+}
+
+/// `IOCTL_DISK_GET_LENGTH_INFO`.
+#[cfg(windows)]
+fn device_size(file: &File) -> io::Result<u64> {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+    const IOCTL_DISK_GET_LENGTH_INFO: u32 = 0x0007_405C;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn DeviceIoControl(
+            device: *mut c_void, code: u32,
+            in_buffer: *const c_void, in_size: u32,
+            out_buffer: *mut c_void, out_size: u32,
+            returned: *mut u32, overlapped: *mut c_void,
+        ) -> i32;
+    }
+
+    let mut length = 0i64;
+    let mut returned = 0u32;
+    // SAFETY: the output buffer is a GET_LENGTH_INFORMATION (a single i64)
+    let ok = unsafe {
+        DeviceIoControl(
+            file.as_raw_handle(), IOCTL_DISK_GET_LENGTH_INFO,
+            std::ptr::null(), 0,
+            (&mut length as *mut i64).cast(), std::mem::size_of::<i64>() as u32,
+            &mut returned, std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(length as u64)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn device_size(file: &File) -> io::Result<u64> {
+    Ok(file.metadata()?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regular_file_size() {
+        let mut file = tempfile::tempfile().unwrap();
+        std::io::Write::write_all(&mut file, &[0; 1234]).unwrap();
+        assert_eq!(size(&file).unwrap(), 1234);
+    }
+}
@@ -0,0 +1,200 @@
+//! C interface to the library, exported with the `capi` feature by the
+//! `sha-calc-capi` package in `capi/` as `libsha_calc`; `include/sha_calc.h`
+//! declares it.
+//!
+//! Functions return the digest length on success and a negative
+//! `SHA_CALC_ERR_*` code on failure, writing raw digest bytes to `out`.
+
+use std::ffi::{CStr, c_char, c_int};
+use std::path::PathBuf;
+use std::slice;
+
+use crate::hasher::HashAlgorithm;
+use crate::{Digest, hash_bytes, hash_file};
+
+/// The algorithm name is unknown, or its support was not compiled in.
+pub const SHA_CALC_ERR_ALGORITHM: c_int = -1;
+/// A required pointer is null, or the path is not valid for the platform.
+pub const SHA_CALC_ERR_ARGUMENT: c_int = -2;
+/// `out_len` is smaller than the digest.
+pub const SHA_CALC_ERR_BUFFER: c_int = -3;
+/// The file could not be opened or read.
+pub const SHA_CALC_ERR_IO: c_int = -4;
+
+/// Digest length in bytes of the algorithm named `algorithm` (as for `-a`).
+///
+/// # Safety
+///
+/// `algorithm` must be null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha_calc_digest_size(algorithm: *const c_char) -> c_int {
+    // SAFETY: forwarded from the caller
+    match unsafe { parse_algorithm(algorithm) } {
+        Ok(algorithm) => algorithm.digest_size() as c_int,
+        Err(code) => code,
+    }
+}
+
+/// Hashes `len` bytes at `data` into `out`.
+///
+/// # Safety
+///
+/// `algorithm` must be null or a NUL-terminated string, `data` must be
+/// valid for `len` bytes (it may be null if `len` is 0) and `out` for
+/// `out_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha_calc_hash_bytes(
+    algorithm: *const c_char,
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    // SAFETY: forwarded from the caller
+    let algorithm = match unsafe { parse_algorithm(algorithm) } {
+        Ok(algorithm) => algorithm,
+        Err(code) => return code,
+    };
+    let data: &[u8] = if len == 0 {
+        &[]
+    } else if data.is_null() {
+        return SHA_CALC_ERR_ARGUMENT;
+    } else {
+        // SAFETY: the caller guarantees `data` is valid for `len` bytes
+        unsafe { slice::from_raw_parts(data, len) }
+    };
+    // SAFETY: forwarded from the caller
+    unsafe { write_digest(&hash_bytes(data, algorithm), out, out_len) }
+}
+
+/// Hashes the file at `path` into `out`.
+///
+/// # Safety
+///
+/// `algorithm` and `path` must be null or NUL-terminated strings and `out`
+/// must be valid for `out_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha_calc_hash_file(
+    algorithm: *const c_char,
+    path: *const c_char,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    // SAFETY: forwarded from the caller
+    let algorithm = match unsafe { parse_algorithm(algorithm) } {
+        Ok(algorithm) => algorithm,
+        Err(code) => return code,
+    };
+    if path.is_null() {
+        return SHA_CALC_ERR_ARGUMENT;
+    }
+    // SAFETY: the caller guarantees `path` is NUL-terminated
+    let Some(path) = path_from_c(unsafe { CStr::from_ptr(path) }) else {
+        return SHA_CALC_ERR_ARGUMENT;
+    };
+    match hash_file(path, algorithm) {
+        // SAFETY: forwarded from the caller
+        Ok(digest) => unsafe { write_digest(&digest, out, out_len) },
+        Err(_) => SHA_CALC_ERR_IO,
+    }
+}
+
+/// # Safety
+///
+/// `algorithm` must be null or a NUL-terminated string.
+unsafe fn parse_algorithm(algorithm: *const c_char) -> Result<HashAlgorithm, c_int> {
+    if algorithm.is_null() {
+        return Err(SHA_CALC_ERR_ARGUMENT);
+    }
+    // SAFETY: the caller guarantees `algorithm` is NUL-terminated
+    let name = unsafe { CStr::from_ptr(algorithm) };
+    name.to_str().ok().and_then(|name| name.parse().ok()).ok_or(SHA_CALC_ERR_ALGORITHM)
+}
+
+/// # Safety
+///
+/// `out` must be null or valid for `out_len` bytes.
+unsafe fn write_digest(digest: &Digest, out: *mut u8, out_len: usize) -> c_int {
+    let bytes = digest.as_bytes();
+    if out.is_null() {
+        return SHA_CALC_ERR_ARGUMENT;
+    }
+    if out_len < bytes.len() {
+        return SHA_CALC_ERR_BUFFER;
+    }
+    // SAFETY: `out` holds at least `bytes.len()` bytes and cannot overlap a
+    // digest this function owns
+    unsafe { out.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len()) };
+    bytes.len() as c_int
+}
+
+/// Paths are raw bytes on Unix and must be UTF-8 elsewhere.
+#[cfg(unix)]
+fn path_from_c(path: &CStr) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes())))
+}
+
+#[cfg(not(unix))]
+fn path_from_c(path: &CStr) -> Option<PathBuf> {
+    path.to_str().ok().map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::*;
+
+    #[test]
+    fn test_hash_bytes() {
+        let mut out = [0u8; 64];
+        let len = unsafe { sha_calc_hash_bytes(c"sha1".as_ptr(), b"abc".as_ptr(), 3, out.as_mut_ptr(), out.len()) };
+        assert_eq!(len, 20);
+        assert_eq!(hex::encode(&out[..20]), "a9993e364706816aba3e25717850c26c9cd0d89d");
+
+        let len = unsafe { sha_calc_hash_bytes(c"crc32".as_ptr(), std::ptr::null(), 0, out.as_mut_ptr(), 4) };
+        assert_eq!((len, &out[..4]), (4, &[0u8; 4][..]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut out = [0u8; 64];
+        unsafe {
+            assert_eq!(sha_calc_hash_bytes(c"md5".as_ptr(), b"".as_ptr(), 0, out.as_mut_ptr(), 64), SHA_CALC_ERR_ALGORITHM);
+            assert_eq!(sha_calc_hash_bytes(c"sha256".as_ptr(), std::ptr::null(), 1, out.as_mut_ptr(), 64), SHA_CALC_ERR_ARGUMENT);
+            assert_eq!(sha_calc_hash_bytes(c"sha256".as_ptr(), b"".as_ptr(), 0, out.as_mut_ptr(), 31), SHA_CALC_ERR_BUFFER);
+            assert_eq!(sha_calc_hash_file(c"sha256".as_ptr(), c"/nonexistent/file".as_ptr(), out.as_mut_ptr(), 64), SHA_CALC_ERR_IO);
+            assert_eq!(sha_calc_digest_size(c"SHA3-384".as_ptr()), 48);
+        }
+    }
+
+    #[test]
+    fn test_header_matches() {
+        let header = include_str!("../include/sha_calc.h");
+        for (name, value) in [
+            ("SHA_CALC_ERR_ALGORITHM", SHA_CALC_ERR_ALGORITHM),
+            ("SHA_CALC_ERR_ARGUMENT", SHA_CALC_ERR_ARGUMENT),
+            ("SHA_CALC_ERR_BUFFER", SHA_CALC_ERR_BUFFER),
+            ("SHA_CALC_ERR_IO", SHA_CALC_ERR_IO),
+        ] {
+            assert!(header.contains(&format!("#define {} ({})", name, value)), "{}", name);
+        }
+        let max = HashAlgorithm::value_variants().iter().map(|alg| alg.digest_size()).max().unwrap();
+        assert!(header.contains(&format!("#define SHA_CALC_MAX_DIGEST_SIZE {}\n", max)));
+        for function in ["sha_calc_digest_size(", "sha_calc_hash_bytes(", "sha_calc_hash_file("] {
+            assert!(header.contains(function), "{}", function);
+        }
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"hello world").unwrap();
+        let path = std::ffi::CString::new(file.path().to_str().unwrap()).unwrap();
+        let mut out = [0u8; 32];
+        let len = unsafe { sha_calc_hash_file(c"sha256".as_ptr(), path.as_ptr(), out.as_mut_ptr(), out.len()) };
+        assert_eq!(len, 32);
+        assert_eq!(hex::encode(out), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+    }
+}
@@ -0,0 +1,185 @@
+//! Object storage inputs (`s3://`, `gs://`, `az://`), read through the
+//! providers' own command-line tools so their usual credential chains
+//! (environment, profiles, instance metadata, `az login`) apply unchanged.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::{Context, Result};
+
+/// Whether `source` names an object in S3, Google Cloud Storage or Azure Blob Storage.
+pub fn is_object_url(source: &str) -> bool {
+    ["s3://", "gs://", "az://"].iter().any(|scheme| source.starts_with(scheme))
+}
+
+/// Streams the object at `url` to `consume` piece by piece, and returns its
+/// size. Only `az://` objects off Unix are stored, in a temporary file.
+pub fn stream(url: &str, consume: &mut dyn FnMut(&[u8])) -> Result<u64> {
+    let mut command = download_command(url)?;
+    if scheme(url) != "az" {
+        return run(command, url, consume);
+    }
+    if cfg!(unix) {
+        command.args(["--file", "/dev/stdout"]);
+        return run(command, url, consume);
+    }
+
+    // Elsewhere az has no stdout path to write to: the blob goes to a
+    // temporary file, hashed once the download is complete
+    let temp = TempPath::new();
+    command.arg("--file").arg(&temp.0);
+    run(command, url, &mut |_| {})?;
+    let mut file = File::open(&temp.0)
+        .with_context(|| format!("Failed to open the download of {}", url))?;
+    pump(&mut file, consume).with_context(|| format!("Failed to read the download of {}", url))
+}
+
+/// Runs the download `command`, streaming its stdout to `consume`.
+fn run(mut command: Command, url: &str, consume: &mut dyn FnMut(&[u8])) -> Result<u64> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}, which is required to read {} objects", program, scheme(url)))?;
+
+    let size = pump(&mut child.stdout.take().unwrap(), consume)
+        .with_context(|| format!("Failed to read from {}", program))?;
+
+    let mut message = String::new();
+    child.stderr.take().unwrap().read_to_string(&mut message).ok();
+    let status = child.wait().with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        anyhow::bail!("Failed to download {}: {}", url, message.trim());
+    }
+    Ok(size)
+}
+
+/// Copies `reader` to `consume` piece by piece, returning the size.
+fn pump(reader: &mut dyn Read, consume: &mut dyn FnMut(&[u8])) -> std::io::Result<u64> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(size);
+        }
+        consume(&buffer[..n]);
+        size += n as u64;
+    }
+}
+
+/// A fresh path in the temporary directory, removed with whatever was
+/// written there when dropped.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new() -> TempPath {
+        // Downloads may run on several threads at once
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        TempPath(std::env::temp_dir().join(format!("sha-calc-{}-{}.download", std::process::id(), n)))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn scheme(url: &str) -> &str {
+    url.split_once("://").map_or(url, |(scheme, _)| scheme)
+}
+
+/// The command downloading the object at `url`:
+///
+/// - `s3://bucket/key` with `aws s3 cp`, to stdout
+/// - `gs://bucket/object` with `gcloud storage cat`, to stdout
+/// - `az://account/container/blob` with `az storage blob download`, which
+///   still needs the `--file` to write to
+fn download_command(url: &str) -> Result<Command> {
+    let (scheme, rest) = url.split_once("://").context("Not an object storage URL")?;
+    let mut command;
+    match scheme {
+        "s3" | "gs" => {
+            let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() || key.is_empty() {
+                anyhow::bail!("Expected {}://<bucket>/<object>, found {}", scheme, url);
+            }
+            if scheme == "s3" {
+                command = Command::new("aws");
+                command.args(["s3", "cp", "--quiet", "--", url, "-"]);
+            } else {
+                command = Command::new("gcloud");
+                command.args(["storage", "cat", "--", url]);
+            }
+        },
+        "az" => {
+            let mut parts = rest.splitn(3, '/');
+            let (Some(account), Some(container), Some(blob)) = (parts.next(), parts.next(), parts.next()) else {
+                anyhow::bail!("Expected az://<account>/<container>/<blob>, found {}", url);
+            };
+            if account.is_empty() || container.is_empty() || blob.is_empty() {
+                anyhow::bail!("Expected az://<account>/<container>/<blob>, found {}", url);
+            }
+            command = Command::new("az");
+            command.args(["storage", "blob", "download", "--no-progress", "--output", "none"])
+                .args(["--account-name", account, "--container-name", container, "--name", blob]);
+        },
+        _ => anyhow::bail!("Unsupported object storage URL: {}", url),
+    }
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(url: &str) -> Vec<String> {
+        let command = download_command(url).unwrap();
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_is_object_url() {
+        assert!(is_object_url("s3://bucket/key"));
+        assert!(is_object_url("gs://bucket/object"));
+        assert!(is_object_url("az://account/container/blob"));
+        assert!(!is_object_url("https://example.com/file"));
+        assert!(!is_object_url("data/s3://bucket"));
+    }
+
+    #[test]
+    fn test_download_commands() {
+        assert_eq!(args("s3://bucket/dir/file.parquet"), ["aws", "s3", "cp", "--quiet", "--", "s3://bucket/dir/file.parquet", "-"]);
+        assert_eq!(args("gs://bucket/file.csv"), ["gcloud", "storage", "cat", "--", "gs://bucket/file.csv"]);
+        assert_eq!(
+            args("az://acct/data/dir/blob.bin"),
+            ["az", "storage", "blob", "download", "--no-progress", "--output", "none",
+             "--account-name", "acct", "--container-name", "data", "--name", "dir/blob.bin"]
+        );
+    }
+
+    #[test]
+    fn test_incomplete_urls_are_rejected() {
+        assert!(download_command("s3://bucket").is_err());
+        assert!(download_command("gs://bucket/").is_err());
+        assert!(download_command("az://acct/container").is_err());
+    }
+
+    #[test]
+    fn test_temp_paths() {
+        let (a, b) = (TempPath::new(), TempPath::new());
+        assert_ne!(a.0, b.0);
+        fs::write(&a.0, b"blob").unwrap();
+        let path = a.0.clone();
+        drop(a);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,659 @@
+//! The hashing itself: algorithm selection, one-shot and incremental
+//! hashing. Only `alloc` is needed, so the module also builds without the
+//! `std` feature, e.g. for firmware.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+
+pub use crate::digest::Digest;
+pub use crate::encoding::{Encoding, encode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum HashAlgorithm {
+    /// SHA-1 (160-bit) - Legacy, not recommended for security
+    #[cfg_attr(feature = "std", value(name = "sha1"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha1")), value(skip))]
+    Sha1,
+    /// SHA-224 (224-bit)
+    #[cfg_attr(feature = "std", value(name = "sha224"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha2")), value(skip))]
+    Sha224,
+    /// SHA-256 (256-bit) - Most common
+    #[cfg_attr(feature = "std", value(name = "sha256"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha2")), value(skip))]
+    Sha256,
+    /// SHA-384 (384-bit)
+    #[cfg_attr(feature = "std", value(name = "sha384"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha2")), value(skip))]
+    Sha384,
+    /// SHA-512 (512-bit)
+    #[cfg_attr(feature = "std", value(name = "sha512"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha2")), value(skip))]
+    Sha512,
+    /// SHA3-224 (224-bit)
+    #[cfg_attr(feature = "std", value(name = "sha3-224"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha3")), value(skip))]
+    Sha3_224,
+    /// SHA3-256 (256-bit)
+    #[cfg_attr(feature = "std", value(name = "sha3-256"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha3")), value(skip))]
+    Sha3_256,
+    /// SHA3-384 (384-bit)
+    #[cfg_attr(feature = "std", value(name = "sha3-384"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha3")), value(skip))]
+    Sha3_384,
+    /// SHA3-512 (512-bit)
+    #[cfg_attr(feature = "std", value(name = "sha3-512"))]
+    #[cfg_attr(all(feature = "std", not(feature = "sha3")), value(skip))]
+    Sha3_512,
+    /// BLAKE2b-512 (512-bit) - High performance
+    #[cfg_attr(feature = "std", value(name = "blake2b"))]
+    #[cfg_attr(all(feature = "std", not(feature = "blake2")), value(skip))]
+    Blake2b,
+    /// BLAKE2s-256 (256-bit) - High performance, smaller output
+    #[cfg_attr(feature = "std", value(name = "blake2s"))]
+    #[cfg_attr(all(feature = "std", not(feature = "blake2")), value(skip))]
+    Blake2s,
+    /// CRC-32 (32-bit) - Non-cryptographic checksum, used by SFV files
+    #[cfg_attr(feature = "std", value(name = "crc32"))]
+    Crc32,
+}
+
+impl HashAlgorithm {
+    /// Every algorithm, in `--list-algorithms` order, including those whose
+    /// support was not compiled in.
+    pub const ALL: [HashAlgorithm; 12] = [
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha224,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha384,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Sha3_224,
+        HashAlgorithm::Sha3_256,
+        HashAlgorithm::Sha3_384,
+        HashAlgorithm::Sha3_512,
+        HashAlgorithm::Blake2b,
+        HashAlgorithm::Blake2s,
+        HashAlgorithm::Crc32,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "SHA-1",
+            HashAlgorithm::Sha224 => "SHA-224",
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Sha384 => "SHA-384",
+            HashAlgorithm::Sha512 => "SHA-512",
+            HashAlgorithm::Sha3_224 => "SHA3-224",
+            HashAlgorithm::Sha3_256 => "SHA3-256",
+            HashAlgorithm::Sha3_384 => "SHA3-384",
+            HashAlgorithm::Sha3_512 => "SHA3-512",
+            HashAlgorithm::Blake2b => "BLAKE2b-512",
+            HashAlgorithm::Blake2s => "BLAKE2s-256",
+            HashAlgorithm::Crc32 => "CRC-32",
+        }
+    }
+
+    /// Name accepted by `-a` (the clap value name).
+    fn cli_name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha224 => "sha224",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Sha3_224 => "sha3-224",
+            HashAlgorithm::Sha3_256 => "sha3-256",
+            HashAlgorithm::Sha3_384 => "sha3-384",
+            HashAlgorithm::Sha3_512 => "sha3-512",
+            HashAlgorithm::Blake2b => "blake2b",
+            HashAlgorithm::Blake2s => "blake2s",
+            HashAlgorithm::Crc32 => "crc32",
+        }
+    }
+
+    /// Tag used by BSD-style (`--tag`) output, as emitted by GNU/BSD sum tools.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "SHA1",
+            HashAlgorithm::Sha224 => "SHA224",
+            HashAlgorithm::Sha256 => "SHA256",
+            HashAlgorithm::Sha384 => "SHA384",
+            HashAlgorithm::Sha512 => "SHA512",
+            HashAlgorithm::Sha3_224 => "SHA3-224",
+            HashAlgorithm::Sha3_256 => "SHA3-256",
+            HashAlgorithm::Sha3_384 => "SHA3-384",
+            HashAlgorithm::Sha3_512 => "SHA3-512",
+            HashAlgorithm::Blake2b => "BLAKE2b",
+            HashAlgorithm::Blake2s => "BLAKE2s",
+            HashAlgorithm::Crc32 => "CRC32",
+        }
+    }
+
+    /// Name registered for RFC 6920 named-information (`ni://`) URIs, if any.
+    pub fn ni_name(&self) -> Option<&'static str> {
+        match self {
+            HashAlgorithm::Sha256 => Some("sha-256"),
+            HashAlgorithm::Sha384 => Some("sha-384"),
+            HashAlgorithm::Sha512 => Some("sha-512"),
+            HashAlgorithm::Sha3_224 => Some("sha3-224"),
+            HashAlgorithm::Sha3_256 => Some("sha3-256"),
+            HashAlgorithm::Sha3_384 => Some("sha3-384"),
+            HashAlgorithm::Sha3_512 => Some("sha3-512"),
+            _ => None,
+        }
+    }
+
+    /// Whether support for the algorithm was compiled in. Each family has a
+    /// cargo feature (`sha1`, `sha2`, `sha3`, `blake2`); CRC-32 is always
+    /// available. Parsing a name only succeeds for available algorithms, and
+    /// hashing with an unavailable one panics.
+    pub fn is_available(&self) -> bool {
+        match self {
+            HashAlgorithm::Sha1 => cfg!(feature = "sha1"),
+            HashAlgorithm::Sha224 | HashAlgorithm::Sha256 | HashAlgorithm::Sha384 | HashAlgorithm::Sha512 => cfg!(feature = "sha2"),
+            HashAlgorithm::Sha3_224 | HashAlgorithm::Sha3_256 | HashAlgorithm::Sha3_384 | HashAlgorithm::Sha3_512 => cfg!(feature = "sha3"),
+            HashAlgorithm::Blake2b | HashAlgorithm::Blake2s => cfg!(feature = "blake2"),
+            HashAlgorithm::Crc32 => true,
+        }
+    }
+
+    /// The cargo feature that compiles the algorithm in, if it needs one.
+    fn feature(&self) -> Option<&'static str> {
+        match self {
+            HashAlgorithm::Sha1 => Some("sha1"),
+            HashAlgorithm::Sha224 | HashAlgorithm::Sha256 | HashAlgorithm::Sha384 | HashAlgorithm::Sha512 => Some("sha2"),
+            HashAlgorithm::Sha3_224 | HashAlgorithm::Sha3_256 | HashAlgorithm::Sha3_384 | HashAlgorithm::Sha3_512 => Some("sha3"),
+            HashAlgorithm::Blake2b | HashAlgorithm::Blake2s => Some("blake2"),
+            HashAlgorithm::Crc32 => None,
+        }
+    }
+
+    /// Length of the digest in bytes.
+    pub fn digest_size(&self) -> usize {
+        match self {
+            HashAlgorithm::Crc32 => 4,
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha224 | HashAlgorithm::Sha3_224 => 28,
+            HashAlgorithm::Sha256 | HashAlgorithm::Sha3_256 | HashAlgorithm::Blake2s => 32,
+            HashAlgorithm::Sha384 | HashAlgorithm::Sha3_384 => 48,
+            HashAlgorithm::Sha512 | HashAlgorithm::Sha3_512 | HashAlgorithm::Blake2b => 64,
+        }
+    }
+
+    /// Size in bytes of the blocks the algorithm consumes (the rate, for
+    /// SHA-3), as needed for HMAC; CRC-32 works a byte at a time.
+    pub fn block_size(&self) -> usize {
+        match self {
+            HashAlgorithm::Crc32 => 1,
+            HashAlgorithm::Sha1 | HashAlgorithm::Sha224 | HashAlgorithm::Sha256 | HashAlgorithm::Blake2s => 64,
+            HashAlgorithm::Sha384 | HashAlgorithm::Sha512 | HashAlgorithm::Blake2b => 128,
+            HashAlgorithm::Sha3_224 => 144,
+            HashAlgorithm::Sha3_256 => 136,
+            HashAlgorithm::Sha3_384 => 104,
+            HashAlgorithm::Sha3_512 => 72,
+        }
+    }
+
+    /// Whether the algorithm is a cryptographic hash function, designed to
+    /// resist deliberate tampering; see [`HashAlgorithm::security_note`] for
+    /// ones that no longer do.
+    pub fn is_cryptographic(&self) -> bool {
+        *self != HashAlgorithm::Crc32
+    }
+
+    /// A warning for algorithms unfit for security uses, if any.
+    pub fn security_note(&self) -> Option<&'static str> {
+        match self {
+            HashAlgorithm::Sha1 => Some("broken: practical collisions (SHAttered), do not use for signatures"),
+            HashAlgorithm::Crc32 => Some("not cryptographic: detects accidental corruption only"),
+            _ => None,
+        }
+    }
+
+    /// Guesses the algorithm that produced a hex digest of `hex_len` characters.
+    ///
+    /// Several algorithms share a digest length (SHA-256, SHA3-256 and
+    /// BLAKE2s, for instance); the first of `preferred` with a matching length
+    /// wins, falling back to the SHA-1/SHA-2 family like the coreutils tools.
+    pub fn from_hex_len(hex_len: usize, preferred: &[HashAlgorithm]) -> Option<HashAlgorithm> {
+        let matches = |alg: &HashAlgorithm| alg.digest_size() * 2 == hex_len;
+        preferred
+            .iter()
+            .chain(&HashAlgorithm::ALL)
+            .copied()
+            .find(matches)
+    }
+
+    /// Looks up the algorithm for a BSD-style tag (the inverse of [`HashAlgorithm::tag`]).
+    pub fn from_tag(tag: &str) -> Option<HashAlgorithm> {
+        HashAlgorithm::ALL
+            .iter()
+            .copied()
+            .find(|alg| alg.tag() == tag)
+    }
+}
+
+/// Formats the algorithm under its command-line name (e.g. `sha3-256`),
+/// which [`HashAlgorithm::from_str`] parses back.
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.cli_name())
+    }
+}
+
+/// SHA-256, or if SHA-2 was left out the first compiled-in of SHA3-256,
+/// BLAKE2b, SHA-1 and CRC-32.
+impl Default for HashAlgorithm {
+    fn default() -> HashAlgorithm {
+        [HashAlgorithm::Sha256, HashAlgorithm::Sha3_256, HashAlgorithm::Blake2b, HashAlgorithm::Sha1]
+            .into_iter()
+            .find(HashAlgorithm::is_available)
+            .unwrap_or(HashAlgorithm::Crc32)
+    }
+}
+
+/// Parses a command-line algorithm name, ignoring case. Only algorithms
+/// whose support was compiled in are accepted.
+impl FromStr for HashAlgorithm {
+    type Err = UnknownAlgorithm;
+
+    fn from_str(s: &str) -> Result<HashAlgorithm, UnknownAlgorithm> {
+        HashAlgorithm::ALL
+            .into_iter()
+            .find(|alg| alg.cli_name().eq_ignore_ascii_case(s) && alg.is_available())
+            .ok_or_else(|| UnknownAlgorithm(s.to_string()))
+    }
+}
+
+/// Error returned when parsing an algorithm name that is not supported, or
+/// whose support was not compiled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownAlgorithm(pub String);
+
+impl fmt::Display for UnknownAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let feature = HashAlgorithm::ALL
+            .into_iter()
+            .find(|alg| alg.cli_name().eq_ignore_ascii_case(&self.0) && !alg.is_available())
+            .and_then(|alg| alg.feature());
+        match feature {
+            Some(feature) => write!(f, "hash algorithm '{}' is not compiled in (cargo feature `{}`)", self.0, feature),
+            None => write!(f, "unknown hash algorithm '{}'", self.0),
+        }
+    }
+}
+
+impl core::error::Error for UnknownAlgorithm {}
+
+/// Computes the lowercase hex digest of `data`.
+pub fn calculate_hash(data: &[u8], algorithm: HashAlgorithm, is_empty: bool) -> String {
+    hex::encode(calculate_digest(data, algorithm, is_empty))
+}
+
+/// Computes the raw digest bytes of `data`.
+pub fn calculate_digest(data: &[u8], algorithm: HashAlgorithm, is_empty: bool) -> Vec<u8> {
+    let mut hasher = StreamingHasher::new(algorithm);
+    if !is_empty {
+        hasher.update(data);
+    }
+    hasher.finalize().into_bytes()
+}
+
+/// Incremental hasher, for data that arrives in pieces, such as a download,
+/// network frames or the parts of a chunked upload: feeding the pieces to
+/// [`StreamingHasher::update`] in order gives the digest of the whole.
+///
+#[cfg_attr(feature = "sha2", doc = "```")]
+#[cfg_attr(not(feature = "sha2"), doc = "```ignore")]
+/// use rustedbytes_sha::{HashAlgorithm, StreamingHasher};
+///
+/// let mut hasher = StreamingHasher::new(HashAlgorithm::Sha256);
+/// hasher.update(b"hello ");
+/// hasher.update(b"world");
+/// assert_eq!(
+///     hasher.finalize().to_hex(),
+///     "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+/// );
+/// ```
+pub struct StreamingHasher {
+    algorithm: HashAlgorithm,
+    state: HasherState,
+}
+
+enum HasherState {
+    #[cfg_attr(not(any(feature = "sha1", feature = "sha2", feature = "sha3", feature = "blake2")), allow(dead_code))]
+    Digest(Box<dyn digest::DynDigest>),
+    /// The running CRC-32 register, before the final inversion.
+    Crc32(u32),
+}
+
+impl StreamingHasher {
+    /// A hasher that has seen no data yet.
+    ///
+    /// # Panics
+    ///
+    /// If support for `algorithm` was not compiled in; see
+    /// [`HashAlgorithm::is_available`].
+    pub fn new(algorithm: HashAlgorithm) -> StreamingHasher {
+        let state = match algorithm {
+            #[cfg(feature = "sha1")]
+            HashAlgorithm::Sha1 => HasherState::Digest(Box::new(sha1::Sha1::default())),
+            #[cfg(feature = "sha2")]
+            HashAlgorithm::Sha224 => HasherState::Digest(Box::new(sha2::Sha224::default())),
+            #[cfg(feature = "sha2")]
+            HashAlgorithm::Sha256 => HasherState::Digest(Box::new(sha2::Sha256::default())),
+            #[cfg(feature = "sha2")]
+            HashAlgorithm::Sha384 => HasherState::Digest(Box::new(sha2::Sha384::default())),
+            #[cfg(feature = "sha2")]
+            HashAlgorithm::Sha512 => HasherState::Digest(Box::new(sha2::Sha512::default())),
+            #[cfg(feature = "sha3")]
+            HashAlgorithm::Sha3_224 => HasherState::Digest(Box::new(sha3::Sha3_224::default())),
+            #[cfg(feature = "sha3")]
+            HashAlgorithm::Sha3_256 => HasherState::Digest(Box::new(sha3::Sha3_256::default())),
+            #[cfg(feature = "sha3")]
+            HashAlgorithm::Sha3_384 => HasherState::Digest(Box::new(sha3::Sha3_384::default())),
+            #[cfg(feature = "sha3")]
+            HashAlgorithm::Sha3_512 => HasherState::Digest(Box::new(sha3::Sha3_512::default())),
+            #[cfg(feature = "blake2")]
+            HashAlgorithm::Blake2b => HasherState::Digest(Box::new(blake2::Blake2b512::default())),
+            #[cfg(feature = "blake2")]
+            HashAlgorithm::Blake2s => HasherState::Digest(Box::new(blake2::Blake2s256::default())),
+            HashAlgorithm::Crc32 => HasherState::Crc32(!0),
+            #[allow(unreachable_patterns)]
+            _ => panic!("{}", UnknownAlgorithm(algorithm.cli_name().to_string())),
+        };
+        StreamingHasher { algorithm, state }
+    }
+
+    /// Adds `data` to what has been hashed so far.
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Digest(digest) => digest.update(data),
+            HasherState::Crc32(crc) => *crc = crc32_update(*crc, data),
+        }
+    }
+
+    /// Returns the digest of everything passed to [`StreamingHasher::update`].
+    pub fn finalize(self) -> Digest {
+        let bytes = match self.state {
+            HasherState::Digest(digest) => digest.finalize().to_vec(),
+            HasherState::Crc32(crc) => (!crc).to_be_bytes().to_vec(),
+        };
+        Digest::from_finalized(self.algorithm, bytes)
+    }
+}
+
+/// Several incremental hashers fed the same data, so that one pass over an
+/// input gives a digest per algorithm (e.g. SHA-256 for verification and
+/// CRC-32 for a legacy index) without reading it again.
+///
+#[cfg_attr(feature = "sha2", doc = "```")]
+#[cfg_attr(not(feature = "sha2"), doc = "```ignore")]
+/// use rustedbytes_sha::{HashAlgorithm, MultiHasher};
+///
+/// let mut hasher = MultiHasher::new(&[HashAlgorithm::Sha256, HashAlgorithm::Crc32]);
+/// hasher.update(b"hello world");
+/// let digests = hasher.finalize();
+/// assert_eq!(digests[1].to_hex(), "0d4a1185");
+/// ```
+pub struct MultiHasher {
+    hashers: Vec<StreamingHasher>,
+}
+
+impl MultiHasher {
+    /// One hasher per entry of `algorithms`, in that order.
+    pub fn new(algorithms: &[HashAlgorithm]) -> MultiHasher {
+        MultiHasher { hashers: algorithms.iter().map(|&alg| StreamingHasher::new(alg)).collect() }
+    }
+
+    pub fn algorithms(&self) -> impl Iterator<Item = HashAlgorithm> + '_ {
+        self.hashers.iter().map(|hasher| hasher.algorithm)
+    }
+
+    /// Adds `data` to every hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        for hasher in &mut self.hashers {
+            hasher.update(data);
+        }
+    }
+
+    /// The digests, in the order the algorithms were given.
+    pub fn finalize(self) -> Vec<Digest> {
+        self.hashers.into_iter().map(StreamingHasher::finalize).collect()
+    }
+}
+
+/// Lookup table for the reflected CRC-32 polynomial (IEEE 802.3).
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The algorithms compiled into this build.
+    fn available() -> Vec<HashAlgorithm> {
+        HashAlgorithm::ALL.into_iter().filter(HashAlgorithm::is_available).collect()
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_sha256_known_vectors() {
+        // Test vector from NIST
+        let test_cases = vec![
+            ("", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            ("abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            ("message digest", "f7846f55cf23e14eebeab5b4e1550cad5b509e3348fbc4efa3a1413d393cb650"),
+        ];
+
+        for (input, expected) in test_cases {
+            let result = if input.is_empty() {
+                calculate_hash(&[], HashAlgorithm::Sha256, true)
+            } else {
+                calculate_hash(input.as_bytes(), HashAlgorithm::Sha256, false)
+            };
+            assert_eq!(result, expected, "SHA-256 failed for input: '{}'", input);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn test_sha1_known_vectors() {
+        let test_cases = vec![
+            ("", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            ("abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+        ];
+
+        for (input, expected) in test_cases {
+            let result = if input.is_empty() {
+                calculate_hash(&[], HashAlgorithm::Sha1, true)
+            } else {
+                calculate_hash(input.as_bytes(), HashAlgorithm::Sha1, false)
+            };
+            assert_eq!(result, expected, "SHA-1 failed for input: '{}'", input);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_sha512_known_vectors() {
+        let test_cases = vec![
+            ("abc", "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+        ];
+
+        for (input, expected) in test_cases {
+            let result = calculate_hash(input.as_bytes(), HashAlgorithm::Sha512, false);
+            assert_eq!(result, expected, "SHA-512 failed for input: '{}'", input);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "sha1", feature = "sha2", feature = "blake2"))]
+    fn test_different_algorithms_produce_different_hashes() {
+        let input = b"test data";
+        
+        let sha1 = calculate_hash(input, HashAlgorithm::Sha1, false);
+        let sha256 = calculate_hash(input, HashAlgorithm::Sha256, false);
+        let sha512 = calculate_hash(input, HashAlgorithm::Sha512, false);
+        let blake2b = calculate_hash(input, HashAlgorithm::Blake2b, false);
+        
+        // All hashes should be different
+        assert_ne!(sha1, sha256);
+        assert_ne!(sha256, sha512);
+        assert_ne!(sha512, blake2b);
+        assert_ne!(sha1, blake2b);
+        
+        // Check expected lengths (in hex characters)
+        assert_eq!(sha1.len(), 40);    // 160 bits = 40 hex chars
+        assert_eq!(sha256.len(), 64);  // 256 bits = 64 hex chars
+        assert_eq!(sha512.len(), 128); // 512 bits = 128 hex chars
+        assert_eq!(blake2b.len(), 128); // 512 bits = 128 hex chars
+    }
+
+    #[test]
+    fn test_crc32_known_vectors() {
+        assert_eq!(calculate_hash(&[], HashAlgorithm::Crc32, true), "00000000");
+        assert_eq!(calculate_hash(b"123456789", HashAlgorithm::Crc32, false), "cbf43926");
+        assert_eq!(calculate_hash(b"hello world", HashAlgorithm::Crc32, false), "0d4a1185");
+    }
+
+    #[test]
+    fn test_incremental_hasher_matches_one_shot() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        for alg in available() {
+            let mut hasher = StreamingHasher::new(alg);
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().into_bytes(), calculate_digest(data, alg, false), "{}", alg.name());
+            assert_eq!(StreamingHasher::new(alg).finalize().into_bytes(), calculate_digest(b"", alg, true), "{}", alg.name());
+        }
+    }
+
+    #[test]
+    fn test_multi_hasher() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        let algorithms = available();
+        let mut hasher = MultiHasher::new(&algorithms);
+        for chunk in data.chunks(5) {
+            hasher.update(chunk);
+        }
+        assert!(hasher.algorithms().eq(algorithms.iter().copied()));
+        for (digest, alg) in hasher.finalize().into_iter().zip(algorithms) {
+            assert_eq!(digest.algorithm(), alg);
+            assert_eq!(digest.into_bytes(), calculate_digest(data, alg, false));
+        }
+        assert!(MultiHasher::new(&[]).finalize().is_empty());
+    }
+
+    #[test]
+    fn test_hash_algorithm_tags() {
+        assert_eq!(HashAlgorithm::Sha256.tag(), "SHA256");
+        assert_eq!(HashAlgorithm::Sha3_512.tag(), "SHA3-512");
+        for alg in HashAlgorithm::ALL {
+            assert_eq!(HashAlgorithm::from_tag(alg.tag()), Some(alg));
+        }
+        assert_eq!(HashAlgorithm::from_tag("MD5"), None);
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for alg in available() {
+            assert_eq!(alg.to_string().parse::<HashAlgorithm>(), Ok(alg));
+        }
+        assert_eq!(HashAlgorithm::Sha3_256.to_string(), "sha3-256");
+        #[cfg(feature = "blake2")]
+        assert_eq!("BLAKE2b".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Blake2b));
+        assert_eq!("md5".parse::<HashAlgorithm>(), Err(UnknownAlgorithm("md5".to_string())));
+    }
+
+    #[test]
+    fn test_availability() {
+        for alg in HashAlgorithm::ALL {
+            assert_eq!(alg.to_string().parse::<HashAlgorithm>().is_ok(), alg.is_available());
+        }
+        assert!(HashAlgorithm::Crc32.is_available());
+        assert!(HashAlgorithm::default().is_available());
+        assert_eq!(UnknownAlgorithm("md5".to_string()).to_string(), "unknown hash algorithm 'md5'");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_names_match_clap() {
+        for alg in HashAlgorithm::ALL {
+            // Algorithms left out of the build are hidden from clap
+            let name = alg.to_possible_value().map(|value| value.get_name().to_string());
+            assert_eq!(name, alg.is_available().then(|| alg.to_string()));
+        }
+        assert_eq!(HashAlgorithm::value_variants(), available());
+    }
+
+    #[test]
+    fn test_digest_size_matches_output() {
+        for alg in available() {
+            assert_eq!(calculate_digest(b"abc", alg, false).len(), alg.digest_size(), "{:?}", alg);
+        }
+    }
+
+    #[test]
+    fn test_algorithm_metadata() {
+        assert_eq!(HashAlgorithm::Sha256.block_size(), 64);
+        assert_eq!(HashAlgorithm::Sha3_256.block_size(), 136);
+        assert!(HashAlgorithm::Sha1.is_cryptographic());
+        assert!(!HashAlgorithm::Crc32.is_cryptographic());
+        assert!(HashAlgorithm::Sha1.security_note().is_some());
+        assert_eq!(HashAlgorithm::Blake2b.security_note(), None);
+        for alg in HashAlgorithm::ALL {
+            assert_eq!(alg.security_note().is_some(), !alg.is_cryptographic() || alg == HashAlgorithm::Sha1);
+        }
+    }
+
+    #[test]
+    fn test_from_hex_len() {
+        assert_eq!(HashAlgorithm::from_hex_len(40, &[]), Some(HashAlgorithm::Sha1));
+        assert_eq!(HashAlgorithm::from_hex_len(64, &[]), Some(HashAlgorithm::Sha256));
+        assert_eq!(HashAlgorithm::from_hex_len(128, &[]), Some(HashAlgorithm::Sha512));
+        assert_eq!(HashAlgorithm::from_hex_len(8, &[]), Some(HashAlgorithm::Crc32));
+        assert_eq!(HashAlgorithm::from_hex_len(63, &[]), None);
+
+        let preferred = [HashAlgorithm::Sha3_512, HashAlgorithm::Sha3_256];
+        assert_eq!(HashAlgorithm::from_hex_len(64, &preferred), Some(HashAlgorithm::Sha3_256));
+        assert_eq!(HashAlgorithm::from_hex_len(40, &preferred), Some(HashAlgorithm::Sha1));
+    }
+
+    #[test]
+    fn test_hash_algorithm_names() {
+        assert_eq!(HashAlgorithm::Sha256.name(), "SHA-256");
+        assert_eq!(HashAlgorithm::Sha1.name(), "SHA-1");
+        assert_eq!(HashAlgorithm::Blake2b.name(), "BLAKE2b-512");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,104 @@
+//! Computed digests, kept as raw bytes and encoded on demand.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::encoding::{self, Encoding};
+use crate::hasher::HashAlgorithm;
+
+/// The digest of some data: its raw bytes and the algorithm that produced
+/// them. Compare digests directly rather than their encodings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Digest {
+    algorithm: HashAlgorithm,
+    bytes: Vec<u8>,
+}
+
+impl Digest {
+    /// Wraps digest bytes computed elsewhere, e.g. read from a manifest.
+    /// Returns `None` if their length does not match the algorithm.
+    pub fn new(algorithm: HashAlgorithm, bytes: Vec<u8>) -> Option<Digest> {
+        (bytes.len() == algorithm.digest_size()).then_some(Digest { algorithm, bytes })
+    }
+
+    /// Parses a hex digest, in either case.
+    pub fn from_hex(algorithm: HashAlgorithm, hex: &str) -> Option<Digest> {
+        Digest::new(algorithm, hex::decode(hex).ok()?)
+    }
+
+    pub(crate) fn from_finalized(algorithm: HashAlgorithm, bytes: Vec<u8>) -> Digest {
+        Digest { algorithm, bytes }
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Lowercase hexadecimal, as printed by `sha256sum`.
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.bytes)
+    }
+
+    /// Standard base64 with padding (RFC 4648), as in SRI hashes.
+    pub fn to_base64(&self) -> String {
+        self.encode(Encoding::Base64)
+    }
+
+    /// Base32 with padding (RFC 4648).
+    pub fn to_base32(&self) -> String {
+        self.encode(Encoding::Base32)
+    }
+
+    /// The digest in any of the encodings of `--encoding`.
+    pub fn encode(&self, encoding: Encoding) -> String {
+        encoding::encode(&self.bytes, encoding)
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Formats the digest as lowercase hex.
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_bytes;
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn test_encodings() {
+        let digest = hash_bytes(b"hello world", HashAlgorithm::Sha1);
+        assert_eq!(digest.to_hex(), "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed");
+        assert_eq!(digest.to_string(), digest.to_hex());
+        assert_eq!(digest.to_base64(), "Kq5sNclPz7QV2+lfQIuc6R7oRu0=");
+        assert_eq!(digest.to_base32(), "FKXGYNOJJ7H3IFO35FPUBC445EPOQRXN");
+        assert_eq!(digest.algorithm(), HashAlgorithm::Sha1);
+        assert_eq!(digest.as_bytes().len(), 20);
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let digest = hash_bytes(b"abc", HashAlgorithm::Crc32);
+        assert_eq!(Digest::from_hex(HashAlgorithm::Crc32, "352441C2"), Some(digest));
+        assert_eq!(Digest::from_hex(HashAlgorithm::Sha256, "352441c2"), None);
+        assert_eq!(Digest::from_hex(HashAlgorithm::Crc32, "not hex!"), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Ed25519 signature verification (RFC 8032) through the `ed25519-compact`
+//! crate.
+
+use ed25519_compact::{PublicKey, Signature};
+
+/// Checks an Ed25519 `signature` of `message` under `public_key`, with the
+/// strict rules of libsodium: S must be reduced modulo L, points must be
+/// canonically encoded, and small-order keys and R values are rejected, so
+/// that a signature cannot be re-encoded or made to verify several messages.
+pub fn verify(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    PublicKey::new(*public_key).verify(message, &Signature::new(*signature)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode<const N: usize>(text: &str) -> [u8; N] {
+        hex::decode(text).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_rfc8032_vectors() {
+        // RFC 8032 §7.1: TEST 1, 2, 3 and SHA(abc)
+        let vectors = [
+            (
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+            (
+                "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+                "af82",
+                "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+            ),
+            (
+                "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+                "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b58909351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704",
+            ),
+        ];
+        for (key, message, sig) in vectors {
+            assert!(verify(&decode(key), &hex::decode(message).unwrap(), &decode(sig)), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_canonical_s() {
+        // TEST 1 with L added to S, which the verification equation alone accepts
+        let key = decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        let sig = decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901554c8c7872aa064e049dbb3013fbf29380d25bf5f0595bbe24655141438e7a101b");
+        assert!(!verify(&key, b"", &sig));
+    }
+
+    #[test]
+    fn test_rejects_small_order_points() {
+        // The neutral element as key, with R = neutral and S = 0, satisfies
+        // [S]B = R + [h]A for every message
+        let neutral = decode("0100000000000000000000000000000000000000000000000000000000000000");
+        let mut sig = [0u8; 64];
+        sig[0] = 1;
+        assert!(!verify(&neutral, b"any message", &sig));
+
+        // TEST 2's key with R = neutral and S = h * a, which also satisfies the equation
+        let key = decode("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c");
+        let sig = decode("0100000000000000000000000000000000000000000000000000000000000000675d054595fa17cb6a44e3ada6ca385e55667e41a63fdab0ac22d10000354002");
+        assert!(!verify(&key, &[0x72], &sig));
+
+        // Points of order 1, 2, 4 and 8, as key with R = the key and S = 0,
+        // and as R of TEST 2's signature
+        let valid: [u8; 64] = decode("92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00");
+        for point in [
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+            "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a",
+        ] {
+            let point: [u8; 32] = decode(point);
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&point);
+            assert!(!verify(&point, b"any message", &sig), "{}", hex::encode(point));
+
+            let mut sig = valid;
+            sig[..32].copy_from_slice(&point);
+            assert!(!verify(&key, &[0x72], &sig), "{}", hex::encode(point));
+        }
+        assert!(verify(&key, &[0x72], &valid));
+    }
+
+    #[test]
+    fn test_rejects_non_canonical_points() {
+        // y = p + 3 encodes the point y = 3, x = 0 with the sign bit set the
+        // neutral element, and y = 2 is not on the curve
+        let sig = [0u8; 64];
+        for key in [
+            "f0ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "0100000000000000000000000000000000000000000000000000000000000080",
+            "0200000000000000000000000000000000000000000000000000000000000000",
+        ] {
+            assert!(!verify(&decode(key), b"", &sig), "{}", key);
+        }
+
+        // R = neutral encoded as p + 1, with S = h * a for TEST 2's key
+        let key = decode("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c");
+        let sig = decode("eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f0a0403c78756704394a791302d07acbdf846e02e6371010b73f9c021313bde01");
+        assert!(!verify(&key, &[0x72], &sig));
+    }
+
+    #[test]
+    fn test_rejects_tampering() {
+        let key = decode("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c");
+        let mut sig = decode("92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00");
+        assert!(!verify(&key, &[0x73], &sig));
+
+        sig[0] ^= 1;
+        assert!(!verify(&key, &[0x72], &sig));
+
+        let other = decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        assert!(!verify(&other, &[0x72], &sig));
+    }
+}
@@ -0,0 +1,306 @@
+//! Textual encodings for digests, beyond plain hex.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+
+/// Digest encodings selectable with `--encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum Encoding {
+    /// Lowercase hexadecimal (default)
+    Hex,
+    /// Standard base64 with padding (RFC 4648)
+    Base64,
+    /// Base32 with padding (RFC 4648)
+    Base32,
+    /// Human-oriented z-base-32, as used by Tahoe-LAFS (unpadded)
+    Zbase32,
+    /// Pronounceable quintuplets, e.g. `lusab-babad` (one word per 16 bits)
+    Proquint,
+}
+
+/// Encodes `data` with the given encoding.
+pub fn encode(data: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Hex => hex::encode(data),
+        Encoding::Base64 => encode_base64(data, BASE64_ALPHABET, true),
+        Encoding::Base32 => encode_base32(data, BASE32_ALPHABET, true),
+        Encoding::Zbase32 => encode_base32(data, ZBASE32_ALPHABET, false),
+        Encoding::Proquint => proquint(data),
+    }
+}
+
+/// Digest grouping selected with `--group N[:SEP]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grouping {
+    pub size: usize,
+    pub separator: String,
+}
+
+impl Grouping {
+    /// Parses `N` or `N:SEP` (the separator defaults to a space).
+    pub fn parse(spec: &str) -> Result<Grouping, String> {
+        let (size, separator) = match spec.split_once(':') {
+            Some((size, separator)) => (size, separator),
+            None => (spec, " "),
+        };
+
+        let size = size.parse::<usize>()
+            .ok()
+            .filter(|&size| size > 0)
+            .ok_or_else(|| format!("invalid group size '{}' (expected a positive number)", size))?;
+
+        Ok(Grouping { size, separator: separator.to_string() })
+    }
+
+    /// Splits `text` into groups of `size` characters joined by the separator.
+    pub fn apply(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        chars
+            .chunks(self.size)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+/// Textual encodings of input data, selected with `--hex-input` or `--base64-input`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEncoding {
+    Hex,
+    Base64,
+}
+
+impl InputEncoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            InputEncoding::Hex => "hexadecimal",
+            InputEncoding::Base64 => "base64",
+        }
+    }
+}
+
+/// Decodes input data such as a hex dump or a PEM body. ASCII whitespace,
+/// including the line breaks of wrapped dumps, is ignored. Returns `None` on invalid input.
+pub fn decode_input(text: &[u8], encoding: InputEncoding) -> Option<Vec<u8>> {
+    let text: Vec<u8> = text.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    match encoding {
+        InputEncoding::Hex => hex::decode(text).ok(),
+        InputEncoding::Base64 => core::str::from_utf8(&text).ok().and_then(decode_base64),
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// URL-safe base64 (RFC 4648 §5) without padding, as used by RFC 6920.
+pub fn base64url(data: &[u8]) -> String {
+    encode_base64(data, BASE64_URL_ALPHABET, false)
+}
+
+fn encode_base64(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        for i in 0..=chunk.len() {
+            encoded.push(alphabet[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes standard base64, with or without padding. Returns `None` on
+/// invalid input.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    decode_base64_with(text, BASE64_ALPHABET)
+}
+
+/// Decodes URL-safe base64 (RFC 4648 §5), with or without padding.
+/// Returns `None` on invalid input.
+pub fn decode_base64url(text: &str) -> Option<Vec<u8>> {
+    decode_base64_with(text, BASE64_URL_ALPHABET)
+}
+
+fn decode_base64_with(text: &str, alphabet: &[u8; 64]) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in text.bytes() {
+        let value = alphabet.iter().position(|&a| a == c)?;
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+
+    // A single leftover symbol cannot encode a whole byte
+    (bits < 6).then_some(decoded)
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const ZBASE32_ALPHABET: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+fn encode_base32(data: &[u8], alphabet: &[u8; 32], pad: bool) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+    for chunk in data.chunks(5) {
+        let mut block = [0u8; 5];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let n = block.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+
+        // Number of significant 5-bit groups for a partial final block
+        let symbols = (chunk.len() * 8).div_ceil(5);
+        for i in 0..8 {
+            if i < symbols {
+                encoded.push(alphabet[((n >> (35 - 5 * i)) & 0x1F) as usize] as char);
+            } else if pad {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+const PROQUINT_CONSONANTS: &[u8; 16] = b"bdfghjklmnprstvz";
+const PROQUINT_VOWELS: &[u8; 4] = b"aiou";
+
+/// Proquint encoding: every 16-bit big-endian word becomes a five letter
+/// consonant-vowel-consonant-vowel-consonant syllable. An odd trailing byte is
+/// padded with zero.
+fn proquint(data: &[u8]) -> String {
+    let words: Vec<String> = data
+        .chunks(2)
+        .map(|pair| {
+            let word = (u16::from(pair[0]) << 8) | u16::from(*pair.get(1).unwrap_or(&0));
+            [
+                PROQUINT_CONSONANTS[(word >> 12) as usize & 0xF],
+                PROQUINT_VOWELS[(word >> 10) as usize & 0x3],
+                PROQUINT_CONSONANTS[(word >> 6) as usize & 0xF],
+                PROQUINT_VOWELS[(word >> 4) as usize & 0x3],
+                PROQUINT_CONSONANTS[word as usize & 0xF],
+            ]
+            .iter()
+            .map(|&c| c as char)
+            .collect()
+        })
+        .collect();
+    words.join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(encode(input.as_bytes(), Encoding::Base64), expected, "base64 failed for input: '{}'", input);
+            assert_eq!(decode_base64(expected).as_deref(), Some(input.as_bytes()), "base64 decode failed for: '{}'", expected);
+        }
+
+        assert_eq!(decode_base64("Zm9vYg").as_deref(), Some(&b"foob"[..]));
+        assert_eq!(decode_base64("Zm9v!"), None);
+        assert_eq!(decode_base64("Zm9vY"), None);
+    }
+
+    #[test]
+    fn test_decode_hex_input() {
+        assert_eq!(decode_input(b"68656c6c6f", InputEncoding::Hex).as_deref(), Some(&b"hello"[..]));
+        assert_eq!(decode_input(b"68 65 6C\n6c 6f\n", InputEncoding::Hex).as_deref(), Some(&b"hello"[..]));
+        assert_eq!(decode_input(b"", InputEncoding::Hex).as_deref(), Some(&b""[..]));
+        assert_eq!(decode_input(b"686", InputEncoding::Hex), None);
+        assert_eq!(decode_input(b"zz", InputEncoding::Hex), None);
+    }
+
+    #[test]
+    fn test_decode_base64_input() {
+        assert_eq!(decode_input(b"aGVsbG8gd29y\nbGQ=\n", InputEncoding::Base64).as_deref(), Some(&b"hello world"[..]));
+        assert_eq!(decode_input(b"aGVsbG8", InputEncoding::Base64).as_deref(), Some(&b"hello"[..]));
+        assert_eq!(decode_input(b"aGVs*G8=", InputEncoding::Base64), None);
+    }
+
+    #[test]
+    fn test_base32_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(encode(input.as_bytes(), Encoding::Base32), expected, "base32 failed for input: '{}'", input);
+        }
+    }
+
+    #[test]
+    fn test_zbase32() {
+        // Examples from the z-base-32 specification
+        assert_eq!(encode(&[0xF0, 0xBF, 0xC7], Encoding::Zbase32), "6n9hq");
+        assert_eq!(encode(&[0xD4, 0x7A, 0x04], Encoding::Zbase32), "4t7ye");
+        assert_eq!(encode(&[0x00], Encoding::Zbase32), "yy");
+        assert_eq!(encode(b"", Encoding::Zbase32), "");
+    }
+
+    #[test]
+    fn test_grouping() {
+        let grouping = Grouping::parse("4").unwrap();
+        assert_eq!(grouping.apply("b94d27b9934d"), "b94d 27b9 934d");
+
+        let grouping = Grouping::parse("2::").unwrap();
+        assert_eq!(grouping.apply("B94D27B"), "B9:4D:27:B");
+
+        assert!(Grouping::parse("0").is_err());
+        assert!(Grouping::parse("x:-").is_err());
+    }
+
+    #[test]
+    fn test_proquint() {
+        // IPv4 examples from the proquint specification
+        assert_eq!(encode(&[127, 0, 0, 1], Encoding::Proquint), "lusab-babad");
+        assert_eq!(encode(&[63, 84, 220, 193], Encoding::Proquint), "gutih-tugad");
+        assert_eq!(encode(&[0xFF], Encoding::Proquint), "zusab");
+    }
+
+    #[test]
+    fn test_base64url_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(base64url(input.as_bytes()), expected, "base64url failed for input: '{}'", input);
+        }
+        assert_eq!(base64url(&[0xfb, 0xff]), "-_8");
+    }
+}
@@ -0,0 +1,54 @@
+//! Errors of the library functions, matchable by kind.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::manifest::ParseError;
+
+/// Why a library operation failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The file could not be opened.
+    OpenFailed { path: PathBuf, source: io::Error },
+    /// Reading failed part way through; `path` is `None` for readers.
+    ReadFailed { path: Option<PathBuf>, source: io::Error },
+    /// A manifest line could not be parsed; `line` counts from 1.
+    BadManifestLine { line: usize, error: ParseError },
+    /// A glob pattern is not valid.
+    BadPattern { pattern: String, error: glob::PatternError },
+}
+
+impl Error {
+    /// The underlying I/O error, if any; its kind tells a missing file
+    /// (`NotFound`) from others.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Error::OpenFailed { source, .. } | Error::ReadFailed { source, .. } => Some(source),
+            Error::BadManifestLine { .. } | Error::BadPattern { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OpenFailed { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::ReadFailed { path: Some(path), source } => write!(f, "{}: read failed: {}", path.display(), source),
+            Error::ReadFailed { path: None, source } => write!(f, "read failed: {}", source),
+            Error::BadManifestLine { line, error } => write!(f, "line {}: {}", line, error),
+            Error::BadPattern { pattern, error } => write!(f, "invalid glob pattern {}: {}", pattern, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::OpenFailed { source, .. } | Error::ReadFailed { source, .. } => Some(source),
+            Error::BadManifestLine { error, .. } => Some(error),
+            Error::BadPattern { error, .. } => Some(error),
+        }
+    }
+}
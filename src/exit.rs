@@ -0,0 +1,61 @@
+//! Exit statuses, so scripts can branch on the kind of failure instead of
+//! parsing stderr.
+
+use std::io;
+
+pub const SUCCESS: i32 = 0;
+/// A digest did not match (also: manifests differ, corrupt extended attributes).
+pub const MISMATCH: i32 = 1;
+/// A file or manifest could not be read or written.
+pub const IO_ERROR: i32 = 2;
+/// A manifest has no usable line, or improperly formatted lines with `--strict`.
+pub const MALFORMED: i32 = 3;
+/// A listed or given file does not exist.
+pub const MISSING: i32 = 4;
+/// The command line is invalid (`EX_USAGE` from sysexits.h, rather than
+/// clap's default of 2, which would read as an I/O error).
+pub const USAGE: i32 = 64;
+
+/// Failures from most to least significant, for runs with several kinds.
+const PRECEDENCE: [i32; 4] = [MISMATCH, MISSING, IO_ERROR, MALFORMED];
+
+/// Combines two statuses, keeping the more significant failure.
+pub fn combine(a: i32, b: i32) -> i32 {
+    let rank = |code| PRECEDENCE.iter().position(|&c| c == code).unwrap_or(PRECEDENCE.len());
+    if rank(b) < rank(a) { b } else { a }
+}
+
+/// The status for an error: `MISSING` if a file was not found, `IO_ERROR` otherwise.
+pub fn for_error(error: &anyhow::Error) -> i32 {
+    if is_not_found(error) { MISSING } else { IO_ERROR }
+}
+
+pub fn is_not_found(error: &anyhow::Error) -> bool {
+    error.root_cause()
+        .downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combine() {
+        assert_eq!(combine(SUCCESS, SUCCESS), SUCCESS);
+        assert_eq!(combine(SUCCESS, MALFORMED), MALFORMED);
+        assert_eq!(combine(IO_ERROR, SUCCESS), IO_ERROR);
+        assert_eq!(combine(IO_ERROR, MISSING), MISSING);
+        assert_eq!(combine(MISMATCH, MISSING), MISMATCH);
+        assert_eq!(combine(MALFORMED, IO_ERROR), IO_ERROR);
+    }
+
+    #[test]
+    fn test_for_error() {
+        let missing = anyhow::Error::new(io::Error::from(io::ErrorKind::NotFound)).context("Failed to open file: x");
+        assert_eq!(for_error(&missing), MISSING);
+        let denied = anyhow::Error::new(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(for_error(&denied), IO_ERROR);
+        assert_eq!(for_error(&anyhow::anyhow!("bad pattern")), IO_ERROR);
+    }
+}
@@ -0,0 +1,435 @@
+//! The structured output formats of `sha-calc` (`--json`, `--csv`, ...),
+//! written record by record as the command line writes them.
+
+use std::io::{self, Write};
+
+use crate::digest::Digest;
+use crate::encoding::{self, Encoding, Grouping};
+use crate::hasher::HashAlgorithm;
+use crate::record::HashRecord;
+
+/// How digests are written, as set by `--encoding`, `--upper`, `--group`,
+/// `--ni-uri` and `--oci`.
+#[derive(Debug, Clone)]
+pub struct DigestStyle {
+    pub encoding: Encoding,
+    /// Uppercase hex, overriding `encoding`.
+    pub upper: bool,
+    pub group: Option<Grouping>,
+    /// RFC 6920 `ni:///` URIs, for algorithms that have a name there.
+    pub ni_uri: bool,
+    /// OCI-style `algorithm:digest`.
+    pub oci: bool,
+}
+
+impl Default for DigestStyle {
+    /// Plain lowercase hex.
+    fn default() -> DigestStyle {
+        DigestStyle { encoding: Encoding::Hex, upper: false, group: None, ni_uri: false, oci: false }
+    }
+}
+
+impl DigestStyle {
+    pub fn format(&self, digest: &Digest) -> String {
+        self.encode(digest.algorithm(), digest.as_bytes())
+    }
+
+    /// Like [`DigestStyle::format`], for raw bytes computed with `algorithm`.
+    pub fn encode(&self, algorithm: HashAlgorithm, digest: &[u8]) -> String {
+        if let (true, Some(ni_name)) = (self.ni_uri, algorithm.ni_name()) {
+            return format!("ni:///{};{}", ni_name, encoding::base64url(digest));
+        }
+
+        let mut hash = if self.upper {
+            hex::encode_upper(digest)
+        } else {
+            encoding::encode(digest, self.encoding)
+        };
+
+        if let Some(grouping) = &self.group {
+            hash = grouping.apply(&hash);
+        }
+
+        if self.oci {
+            format!("{}:{}", algorithm_name(algorithm), hash)
+        } else {
+            hash
+        }
+    }
+}
+
+/// Structured output formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A single JSON array, one object per line.
+    Json,
+    /// One JSON object per line (NDJSON / JSON Lines).
+    JsonLines,
+    /// Comma-separated values with a header row (RFC 4180 quoting).
+    Csv,
+    /// Tab-separated values with a header row; tabs, newlines and backslashes are escaped.
+    Tsv,
+    /// A YAML sequence of mappings.
+    Yaml,
+    /// An XML document with one `<file>` element per entry.
+    Xml,
+    /// A classic `.sfv` file: comment header, then `filename CRC32` lines.
+    Sfv,
+}
+
+impl Format {
+    /// Whether failed entries are represented in the output itself; when they
+    /// are not, the caller has to report them separately.
+    pub fn reports_errors(&self) -> bool {
+        *self != Format::Sfv
+    }
+}
+
+/// Column names shared by the tabular formats.
+const COLUMNS: [&str; 5] = ["algorithm", "digest", "path", "size", "error"];
+
+/// Streams entries in a structured format as soon as each one is available.
+pub struct Printer<W: Write> {
+    out: W,
+    format: Format,
+    style: DigestStyle,
+    count: usize,
+}
+
+impl<W: Write> Printer<W> {
+    /// Creates a printer and writes the document header, if the format has one.
+    pub fn new(mut out: W, format: Format, style: DigestStyle) -> io::Result<Self> {
+        match format {
+            Format::Json => writeln!(out, "[")?,
+            Format::Csv => writeln!(out, "{}", COLUMNS.join(","))?,
+            Format::Tsv => writeln!(out, "{}", COLUMNS.join("\t"))?,
+            Format::Xml => {
+                writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+                writeln!(out, "<checksums>")?;
+            },
+            Format::Sfv => {
+                writeln!(out, "; Generated by {} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+                writeln!(out, ";")?;
+            },
+            Format::JsonLines | Format::Yaml => {},
+        }
+
+        Ok(Printer { out, format, style, count: 0 })
+    }
+
+    pub fn write_entry(&mut self, entry: &HashRecord) -> io::Result<()> {
+        let digest = entry.digest.as_ref().map(|digest| self.style.format(digest));
+        let digest = digest.as_deref();
+        match self.format {
+            Format::Json => {
+                if self.count > 0 {
+                    writeln!(self.out, ",")?;
+                }
+                write!(self.out, "  {}", json_object(entry, digest))?;
+            },
+            Format::JsonLines => {
+                writeln!(self.out, "{}", json_object(entry, digest))?;
+                self.out.flush()?;
+            },
+            Format::Csv => {
+                let fields: Vec<String> = columns(entry, digest).iter().map(|field| csv_field(field)).collect();
+                writeln!(self.out, "{}", fields.join(","))?;
+            },
+            Format::Tsv => {
+                let fields: Vec<String> = columns(entry, digest).iter().map(|field| tsv_field(field)).collect();
+                writeln!(self.out, "{}", fields.join("\t"))?;
+            },
+            Format::Yaml => {
+                // Double-quoted YAML scalars share JSON's escaping rules.
+                writeln!(self.out, "- algorithm: {}", algorithm_name(entry.algorithm))?;
+                writeln!(self.out, "  digest: {}", digest.map_or("null".to_string(), json_string))?;
+                writeln!(self.out, "  path: {}", json_string(&entry.path))?;
+                writeln!(self.out, "  size: {}", entry.size.map_or("null".to_string(), |size| size.to_string()))?;
+                writeln!(self.out, "  error: {}", entry.error.as_deref().map_or("null".to_string(), json_string))?;
+            },
+            Format::Xml => {
+                writeln!(self.out, "  <file>")?;
+                writeln!(self.out, "    <path>{}</path>", xml_text(&entry.path))?;
+                writeln!(self.out, "    <algorithm>{}</algorithm>", algorithm_name(entry.algorithm))?;
+                if let Some(digest) = digest {
+                    writeln!(self.out, "    <digest>{}</digest>", xml_text(digest))?;
+                }
+                if let Some(size) = entry.size {
+                    writeln!(self.out, "    <size>{}</size>", size)?;
+                }
+                if let Some(error) = &entry.error {
+                    writeln!(self.out, "    <error>{}</error>", xml_text(error))?;
+                }
+                writeln!(self.out, "  </file>")?;
+            },
+            Format::Sfv => {
+                if let Some(digest) = digest {
+                    writeln!(self.out, "{} {}", entry.path, digest.to_uppercase())?;
+                }
+            },
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Writes the document footer, if the format has one.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.format {
+            Format::Json => {
+                if self.count > 0 {
+                    writeln!(self.out)?;
+                }
+                writeln!(self.out, "]")?;
+            },
+            Format::Yaml if self.count == 0 => writeln!(self.out, "[]")?,
+            Format::Xml => writeln!(self.out, "</checksums>")?,
+            _ => {},
+        }
+
+        self.out.flush()
+    }
+}
+
+fn json_object(entry: &HashRecord, digest: Option<&str>) -> String {
+    format!(
+        "{{\"algorithm\":{},\"digest\":{},\"path\":{},\"size\":{},\"error\":{}}}",
+        json_string(&algorithm_name(entry.algorithm)),
+        digest.map_or("null".to_string(), json_string),
+        json_string(&entry.path),
+        entry.size.map_or("null".to_string(), |size| size.to_string()),
+        entry.error.as_deref().map_or("null".to_string(), json_string),
+    )
+}
+
+/// Field values in [`COLUMNS`] order; missing values are empty.
+fn columns(entry: &HashRecord, digest: Option<&str>) -> [String; 5] {
+    [
+        algorithm_name(entry.algorithm),
+        digest.unwrap_or_default().to_string(),
+        entry.path.clone(),
+        entry.size.map(|size| size.to_string()).unwrap_or_default(),
+        entry.error.clone().unwrap_or_default(),
+    ]
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn tsv_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn xml_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters cannot be represented in XML 1.0.
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => escaped.push(char::REPLACEMENT_CHARACTER),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `value` as a JSON string literal, quotes included.
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// The algorithm name as accepted on the command line (e.g. `sha256`).
+pub fn algorithm_name(algorithm: HashAlgorithm) -> String {
+    algorithm.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A CRC-32 record, so that digests stay short.
+    fn entry(digest: Option<&str>, error: Option<&str>) -> HashRecord {
+        HashRecord {
+            path: "dir/\"quoted\"\tname".to_string(),
+            algorithm: HashAlgorithm::Crc32,
+            digest: digest.map(|hex| Digest::from_hex(HashAlgorithm::Crc32, hex).unwrap()),
+            size: digest.map(|_| 11),
+            duration: None,
+            mtime: None,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_digest_style() {
+        let digest = crate::hash_bytes(b"hello world", HashAlgorithm::Sha256);
+        let mut style = DigestStyle::default();
+        assert_eq!(style.format(&digest), digest.to_hex());
+
+        style.upper = true;
+        style.group = Some(Grouping::parse("8:-").unwrap());
+        style.oci = true;
+        assert!(style.format(&digest).starts_with("sha256:B94D27B9-934D3E08-"));
+
+        style.ni_uri = true;
+        assert_eq!(style.format(&digest), "ni:///sha-256;uU0nuZNNPgilLlLX2n2r-sSE7-N6U4DukIj3rOLvzek");
+    }
+
+    #[test]
+    fn test_json_string_escaping() {
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+
+    fn render(format: Format, entries: &[HashRecord]) -> String {
+        let mut out = Vec::new();
+        let mut printer = Printer::new(&mut out, format, DigestStyle::default()).unwrap();
+        for entry in entries {
+            printer.write_entry(entry).unwrap();
+        }
+        printer.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_json() {
+        let entries = vec![entry(Some("0000abcd"), None), entry(None, Some("boom"))];
+
+        assert_eq!(
+            render(Format::Json, &entries),
+            "[\n  {\"algorithm\":\"crc32\",\"digest\":\"0000abcd\",\"path\":\"dir/\\\"quoted\\\"\\tname\",\"size\":11,\"error\":null},\n  \
+             {\"algorithm\":\"crc32\",\"digest\":null,\"path\":\"dir/\\\"quoted\\\"\\tname\",\"size\":null,\"error\":\"boom\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json_empty() {
+        assert_eq!(render(Format::Json, &[]), "[\n]\n");
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain.txt"), "plain.txt");
+        assert_eq!(csv_field("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(csv_field("say \"hi\".txt"), "\"say \"\"hi\"\".txt\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let entries = vec![entry(Some("0000abcd"), None), entry(None, Some("boom, again"))];
+        assert_eq!(
+            render(Format::Csv, &entries),
+            "algorithm,digest,path,size,error\n\
+             crc32,0000abcd,\"dir/\"\"quoted\"\"\tname\",11,\n\
+             crc32,,\"dir/\"\"quoted\"\"\tname\",,\"boom, again\"\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_field_escaping() {
+        assert_eq!(tsv_field("my file.txt"), "my file.txt");
+        assert_eq!(tsv_field("a\tb\nc\\d\re"), "a\\tb\\nc\\\\d\\re");
+    }
+
+    #[test]
+    fn test_write_tsv() {
+        let entries = vec![entry(Some("0000abcd"), None)];
+        assert_eq!(
+            render(Format::Tsv, &entries),
+            "algorithm\tdigest\tpath\tsize\terror\ncrc32\t0000abcd\tdir/\"quoted\"\\tname\t11\t\n"
+        );
+    }
+
+    #[test]
+    fn test_write_yaml() {
+        let entries = vec![entry(Some("00001234"), None), entry(None, Some("boom"))];
+        assert_eq!(
+            render(Format::Yaml, &entries),
+            "- algorithm: crc32\n  digest: \"00001234\"\n  path: \"dir/\\\"quoted\\\"\\tname\"\n  size: 11\n  error: null\n\
+             - algorithm: crc32\n  digest: null\n  path: \"dir/\\\"quoted\\\"\\tname\"\n  size: null\n  error: \"boom\"\n"
+        );
+    }
+
+    #[test]
+    fn test_write_yaml_empty() {
+        assert_eq!(render(Format::Yaml, &[]), "[]\n");
+    }
+
+    #[test]
+    fn test_xml_text_escaping() {
+        assert_eq!(xml_text("a<b>&'c'\"d\"\u{1}"), "a&lt;b&gt;&amp;&apos;c&apos;&quot;d&quot;\u{fffd}");
+    }
+
+    #[test]
+    fn test_write_xml() {
+        let entries = vec![entry(Some("0000abcd"), None), entry(None, Some("boom"))];
+        assert_eq!(
+            render(Format::Xml, &entries),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checksums>\n\
+             \x20 <file>\n    <path>dir/&quot;quoted&quot;\tname</path>\n    <algorithm>crc32</algorithm>\n\
+             \x20   <digest>0000abcd</digest>\n    <size>11</size>\n  </file>\n\
+             \x20 <file>\n    <path>dir/&quot;quoted&quot;\tname</path>\n    <algorithm>crc32</algorithm>\n\
+             \x20   <error>boom</error>\n  </file>\n\
+             </checksums>\n"
+        );
+    }
+
+    #[test]
+    fn test_write_sfv() {
+        let mut entries = vec![entry(Some("0d4a1185"), None), entry(None, Some("boom"))];
+        entries[0].path = "disc1.iso".to_string();
+
+        let rendered = render(Format::Sfv, &entries);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert!(lines[0].starts_with("; Generated by "));
+        assert_eq!(lines[1], ";");
+        assert_eq!(lines[2], "disc1.iso 0D4A1185");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_write_json_lines() {
+        let entries = vec![entry(Some("0000abcd"), None), entry(Some("0000ef01"), None)];
+        let rendered = render(Format::JsonLines, &entries);
+
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"algorithm\":\"crc32\",\"digest\":\"0000abcd\""));
+        assert!(lines[1].starts_with("{\"algorithm\":\"crc32\",\"digest\":\"0000ef01\""));
+    }
+}
@@ -0,0 +1,400 @@
+//! `.gitignore` rules for `--gitignore`: the ignore files of the walked
+//! directory, of its subdirectories and of its parents up to the top of the
+//! enclosing Git repository, then its `.git/info/exclude` and the
+//! `core.excludesFile` of its Git config.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// One line of a `.gitignore` file.
+#[derive(Debug)]
+struct Rule {
+    pattern: glob::Pattern,
+    /// `!pattern`: re-includes what an earlier rule ignored
+    negate: bool,
+    /// `pattern/`: matches directories only
+    dir_only: bool,
+    /// Contains a `/`: matched against the whole path relative to the
+    /// `.gitignore` file instead of the last component
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Rule> {
+        let line = trim_trailing_spaces(line.trim_end_matches('\r'));
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = glob::Pattern::new(&to_glob(line.strip_prefix('/').unwrap_or(line))?).ok()?;
+        Some(Rule { pattern, negate, dir_only, anchored })
+    }
+
+    /// Whether the rule matches `path`, relative to its `.gitignore` file.
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            self.pattern.matches_path_with(path, MATCH_OPTIONS)
+        } else {
+            path.file_name()
+                .is_some_and(|name| self.pattern.matches_with(&name.to_string_lossy(), MATCH_OPTIONS))
+        }
+    }
+}
+
+/// `line` without its trailing spaces, except one escaped with a backslash.
+fn trim_trailing_spaces(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    let backslashes = trimmed.len() - trimmed.trim_end_matches('\\').len();
+    if trimmed.len() < line.len() && backslashes % 2 == 1 {
+        &line[..trimmed.len() + 1]
+    } else {
+        trimmed
+    }
+}
+
+/// The `glob` pattern for a gitignore pattern, where a backslash makes the
+/// next character literal (`\#`, `\!`, `\ `, `\*`) and `[^...]` is a
+/// negated class like `[!...]`. None for a trailing backslash, a pattern
+/// that Git never matches.
+fn to_glob(pattern: &str) -> Option<String> {
+    let mut glob = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let c = chars.next()?;
+                if !in_class && matches!(c, '*' | '?' | '[' | ']') {
+                    glob.extend(['[', c, ']']);
+                } else {
+                    glob.push(c);
+                }
+            },
+            '[' if !in_class => {
+                glob.push('[');
+                in_class = true;
+                if chars.next_if(|&c| c == '^' || c == '!').is_some() {
+                    glob.push('!');
+                }
+                // A `]` right after the opening bracket is part of the class
+                if let Some(c) = chars.next_if_eq(&']') {
+                    glob.push(c);
+                }
+            },
+            ']' if in_class => {
+                glob.push(']');
+                in_class = false;
+            },
+            c => glob.push(c),
+        }
+    }
+    Some(glob)
+}
+
+/// The ignore rules that apply below one walked directory.
+pub struct Gitignore {
+    root: PathBuf,
+    /// Rules of the repository directories above `root`, each with the path
+    /// of `root` relative to the directory holding them
+    parents: Vec<(PathBuf, Rc<Vec<Rule>>)>,
+    /// Rules of the directories below `root` visited so far
+    loaded: RefCell<HashMap<PathBuf, Rc<Vec<Rule>>>>,
+}
+
+impl Gitignore {
+    pub fn new(root: &Path) -> Gitignore {
+        let mut parents = Vec::new();
+        let absolute = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        // Without an enclosing repository, only the walked tree counts
+        if let Some(top) = absolute.ancestors().find(|dir| dir.join(".git").exists()) {
+            for dir in absolute.ancestors().skip(1).take_while(|dir| dir.starts_with(top)) {
+                let prefix = absolute.strip_prefix(dir).unwrap_or(&absolute).to_path_buf();
+                parents.push((prefix, Rc::new(read_rules(&dir.join(".gitignore")))));
+            }
+            // Below every .gitignore in precedence, so applied before them
+            let prefix = absolute.strip_prefix(top).unwrap_or(&absolute).to_path_buf();
+            parents.push((prefix.clone(), Rc::new(read_rules(&top.join(".git/info/exclude")))));
+            if let Some(file) = excludes_file(top) {
+                parents.push((prefix, Rc::new(read_rules(&file))));
+            }
+        }
+        parents.reverse();
+
+        Gitignore { root: root.to_path_buf(), parents, loaded: RefCell::new(HashMap::new()) }
+    }
+
+    /// Whether `relative`, a path below the walked directory, is ignored. Its
+    /// parent directories are assumed not to be, as the walk skips those.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if is_dir && relative.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+
+        let mut ignored = false;
+        let mut apply = |rules: &[Rule], path: &Path| {
+            // The last matching rule wins, and deeper files come later
+            if let Some(rule) = rules.iter().rev().find(|rule| rule.matches(path, is_dir)) {
+                ignored = !rule.negate;
+            }
+        };
+
+        for (prefix, rules) in &self.parents {
+            apply(rules, &prefix.join(relative));
+        }
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        for dir in parent.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            let rules = self.rules(dir);
+            apply(&rules, relative.strip_prefix(dir).unwrap_or(relative));
+        }
+        ignored
+    }
+
+    /// The rules of the `.gitignore` in `dir`, relative to the walked directory.
+    fn rules(&self, dir: &Path) -> Rc<Vec<Rule>> {
+        self.loaded.borrow_mut()
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| Rc::new(read_rules(&self.root.join(dir).join(".gitignore"))))
+            .clone()
+    }
+}
+
+/// The rules of the ignore file at `path`; none if it is missing or unreadable.
+fn read_rules(path: &Path) -> Vec<Rule> {
+    fs::read_to_string(path)
+        .map(|text| text.lines().filter_map(Rule::parse).collect())
+        .unwrap_or_default()
+}
+
+/// The `core.excludesFile` of the repository at `top`, as set by its
+/// `.git/config` or else the global Git config; `$XDG_CONFIG_HOME/git/ignore`
+/// (`~/.config/git/ignore`) when none sets it. A relative path is taken from
+/// the top of the repository, where Git usually runs.
+fn excludes_file(top: &Path) -> Option<PathBuf> {
+    let home = env::home_dir();
+    let xdg = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+    let configs = [
+        xdg.as_ref().map(|xdg| xdg.join("git/config")),
+        home.as_ref().map(|home| home.join(".gitconfig")),
+        Some(top.join(".git/config")),
+    ];
+    // Later files override earlier ones
+    let configured = configs.iter().rev().flatten().find_map(|config| config_excludes_file(config));
+    match configured {
+        Some(value) => match value.strip_prefix("~/") {
+            Some(rest) => home.map(|home| home.join(rest)),
+            None => Some(top.join(value)),
+        },
+        None => xdg.map(|xdg| xdg.join("git/ignore")),
+    }
+}
+
+/// The last `excludesFile` of the `[core]` section of the Git config file
+/// at `path`. `include` directives are not followed.
+fn config_excludes_file(path: &Path) -> Option<String> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut in_core = false;
+    let mut value = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[') {
+            // Names are case-insensitive; `[core "sub"]` is another section
+            in_core = header.split(']').next().is_some_and(|name| name.trim().eq_ignore_ascii_case("core"));
+        } else if let Some((_, raw)) = line.split_once('=')
+            .filter(|(key, _)| in_core && key.trim().eq_ignore_ascii_case("excludesfile"))
+        {
+            value = Some(config_value(raw));
+        }
+    }
+    value
+}
+
+/// A Git config value without its quotes, escapes and trailing comment.
+fn config_value(raw: &str) -> String {
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = raw.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('t') => value.push('\t'),
+                Some('n') => value.push('\n'),
+                Some(c) => value.push(c),
+                None => {},
+            },
+            '#' | ';' if !quoted => break,
+            c => value.push(c),
+        }
+    }
+    value.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(line: &str) -> Rule {
+        Rule::parse(line).unwrap()
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        assert!(rule("*.o").matches(Path::new("src/deep/main.o"), false));
+        assert!(rule("target/").matches(Path::new("sub/target"), true));
+        assert!(!rule("target/").matches(Path::new("target"), false));
+        assert!(rule("/build").matches(Path::new("build"), true));
+        assert!(!rule("/build").matches(Path::new("src/build"), true));
+        assert!(rule("docs/*.html").matches(Path::new("docs/index.html"), false));
+        assert!(!rule("docs/*.html").matches(Path::new("docs/api/index.html"), false));
+        assert!(rule("**/cache").matches(Path::new("a/b/cache"), true));
+        assert!(rule("!keep.o").negate);
+        assert!(Rule::parse("# comment").is_none());
+        assert!(Rule::parse("   ").is_none());
+    }
+
+    #[test]
+    fn test_negated_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "!early.log\n*.log\n!important.log\nbuild/\n!build/keep.txt\n\\!bang.txt\n").unwrap();
+
+        let ignore = Gitignore::new(dir.path());
+        assert!(ignore.is_ignored(Path::new("debug.log"), false));
+        assert!(!ignore.is_ignored(Path::new("important.log"), false));
+        assert!(!ignore.is_ignored(Path::new("logs/important.log"), false));
+        // The last matching rule wins, so a negation before `*.log` is moot
+        assert!(ignore.is_ignored(Path::new("early.log"), false));
+        // A negation cannot re-include a file whose directory is ignored:
+        // the walk never enters `build`
+        assert!(ignore.is_ignored(Path::new("build"), true));
+        // `\!` escapes a literal leading `!`
+        assert!(ignore.is_ignored(Path::new("!bang.txt"), false));
+        assert!(!ignore.is_ignored(Path::new("bang.txt"), false));
+    }
+
+    #[test]
+    fn test_anchored_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "/root.txt\ndoc/frotz\nlib/**/gen\n").unwrap();
+        fs::write(dir.path().join("sub/.gitignore"), "/only-here\nnested/x.txt\n").unwrap();
+
+        let ignore = Gitignore::new(dir.path());
+        // A leading slash anchors to the directory of the .gitignore
+        assert!(ignore.is_ignored(Path::new("root.txt"), false));
+        assert!(!ignore.is_ignored(Path::new("sub/root.txt"), false));
+        // So does a slash in the middle
+        assert!(ignore.is_ignored(Path::new("doc/frotz"), true));
+        assert!(!ignore.is_ignored(Path::new("a/doc/frotz"), true));
+        assert!(ignore.is_ignored(Path::new("lib/gen"), true));
+        assert!(ignore.is_ignored(Path::new("lib/a/b/gen"), true));
+        assert!(!ignore.is_ignored(Path::new("src/lib/gen"), true));
+        // Anchors of a nested .gitignore are relative to its own directory
+        assert!(ignore.is_ignored(Path::new("sub/only-here"), false));
+        assert!(!ignore.is_ignored(Path::new("only-here"), false));
+        assert!(!ignore.is_ignored(Path::new("sub/deeper/only-here"), false));
+        assert!(ignore.is_ignored(Path::new("sub/nested/x.txt"), false));
+        assert!(!ignore.is_ignored(Path::new("nested/x.txt"), false));
+    }
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\ntarget/\n").unwrap();
+        fs::write(dir.path().join("sub/.gitignore"), "!keep.log\n/local.txt\n").unwrap();
+
+        let ignore = Gitignore::new(dir.path());
+        assert!(ignore.is_ignored(Path::new("debug.log"), false));
+        assert!(ignore.is_ignored(Path::new("sub/debug.log"), false));
+        assert!(!ignore.is_ignored(Path::new("sub/keep.log"), false));
+        assert!(ignore.is_ignored(Path::new("sub/local.txt"), false));
+        assert!(!ignore.is_ignored(Path::new("local.txt"), false));
+        assert!(ignore.is_ignored(Path::new("sub/target"), true));
+        assert!(ignore.is_ignored(Path::new(".git"), true));
+        assert!(!ignore.is_ignored(Path::new("src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_repository_ignore_files_above_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("project/src")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "project/src/*.tmp\n*.bak\n").unwrap();
+
+        let ignore = Gitignore::new(&dir.path().join("project"));
+        assert!(ignore.is_ignored(Path::new("src/a.tmp"), false));
+        assert!(ignore.is_ignored(Path::new("notes.bak"), false));
+        assert!(!ignore.is_ignored(Path::new("a.tmp"), false));
+    }
+
+    #[test]
+    fn test_escapes() {
+        // Unescaped trailing spaces are dropped, an escaped one is kept
+        assert!(rule("foo   ").matches(Path::new("foo"), false));
+        assert!(rule("foo\\  ").matches(Path::new("foo "), false));
+        assert!(!rule("foo\\  ").matches(Path::new("foo"), false));
+        assert!(rule("foo\\\\ ").matches(Path::new("foo\\"), false));
+
+        assert!(rule("\\#notes").matches(Path::new("#notes"), false));
+        assert!(!rule("\\!bang").negate);
+        assert!(rule("\\*.txt").matches(Path::new("*.txt"), false));
+        assert!(!rule("\\*.txt").matches(Path::new("a.txt"), false));
+        assert!(rule("[^a]b").matches(Path::new("cb"), false));
+        assert!(!rule("[^a]b").matches(Path::new("ab"), false));
+        assert!(rule("[]x]").matches(Path::new("]"), false));
+        // Git never matches a pattern ending in a lone backslash
+        assert!(Rule::parse("bad\\").is_none());
+    }
+
+    #[test]
+    fn test_repository_exclude_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git/info")).unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join(".git/info/exclude"), "*.tmp\n").unwrap();
+        fs::write(dir.path().join(".git/config"), "[core]\n\texcludesFile = \"editor ignore\" ; comment\n").unwrap();
+        fs::write(dir.path().join("editor ignore"), "*.swp\n").unwrap();
+        fs::write(dir.path().join(".gitignore"), "!keep.tmp\n").unwrap();
+
+        let ignore = Gitignore::new(&dir.path().join("sub"));
+        assert!(ignore.is_ignored(Path::new("a.tmp"), false));
+        assert!(ignore.is_ignored(Path::new("deep/a.swp"), false));
+        // A .gitignore takes precedence over both
+        assert!(!ignore.is_ignored(Path::new("keep.tmp"), false));
+        assert!(!ignore.is_ignored(Path::new("a.txt"), false));
+    }
+
+    #[test]
+    fn test_config_excludes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        fs::write(&config, "[Core]\n\tExcludesFile = first\n\texcludesfile = \"second\\tone\" # comment\n\
+                            [core \"sub\"]\n\texcludesfile = other\n[user]\n\texcludesfile = other\n").unwrap();
+        assert_eq!(config_excludes_file(&config).as_deref(), Some("second\tone"));
+
+        fs::write(&config, "[user]\n\tname = x\n").unwrap();
+        assert_eq!(config_excludes_file(&config), None);
+    }
+}
@@ -0,0 +1,625 @@
+//! Minimal gzip (RFC 1952) and DEFLATE (RFC 1951) decoder, for hashing the
+//! members of `.tar.gz` archives as they are read.
+
+use std::io::{self, Read};
+use std::mem;
+
+use crate::hasher::{HashAlgorithm, StreamingHasher};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+const FLAG_HCRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+const MAX_BITS: usize = 15;
+
+/// How far back DEFLATE matches may reach, and so how much output is kept.
+const WINDOW_SIZE: usize = 32 * 1024;
+
+/// Base lengths and extra bits of length symbols 257..=285.
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+
+/// Base distances and extra bits of distance symbols 0..=29.
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Order in which code length code lengths are stored in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Whether `data` starts like a gzip stream.
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&GZIP_MAGIC)
+}
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Decompresses a gzip stream as it is read, including concatenated members
+/// as written by `pigz` or `cat a.gz b.gz`, and verifies each member's CRC-32
+/// and length at its end. Only the output that later matches may copy from
+/// is kept, so memory use does not grow with the stream.
+pub struct GzDecoder<R> {
+    input: BitReader<R>,
+    state: State,
+    /// The last `WINDOW_SIZE` bytes already read, then those not yet read
+    output: Vec<u8>,
+    /// Index in `output` of the first byte not yet read
+    pos: usize,
+    /// CRC-32 and length (modulo 2^32) of the current member so far
+    crc: StreamingHasher,
+    size: u32,
+}
+
+enum State {
+    /// Before a member header; a stream may end before any but the first
+    Header { first: bool },
+    /// Before a block header
+    Block,
+    /// Inside a stored block
+    Stored { remaining: usize, last: bool },
+    /// Inside a Huffman-coded block
+    Compressed { literals: Huffman, distances: Huffman, last: bool },
+    /// After the last block of a member
+    Trailer,
+    Done,
+}
+
+impl<R: Read> GzDecoder<R> {
+    pub fn new(input: R) -> GzDecoder<R> {
+        GzDecoder {
+            input: BitReader::new(input),
+            state: State::Header { first: true },
+            output: Vec::new(),
+            pos: 0,
+            crc: StreamingHasher::new(HashAlgorithm::Crc32),
+            size: 0,
+        }
+    }
+
+    /// Decodes the next piece of the stream into `output`: a header, a
+    /// trailer, or up to `WINDOW_SIZE` bytes of a block.
+    fn step(&mut self) -> io::Result<()> {
+        self.state = match mem::replace(&mut self.state, State::Done) {
+            State::Header { first } => {
+                if !first && self.input.at_end()? {
+                    State::Done
+                } else {
+                    self.header()?;
+                    State::Block
+                }
+            },
+            State::Block => {
+                let last = self.input.bits(1)? == 1;
+                match self.input.bits(2)? {
+                    0 => {
+                        self.input.align();
+                        let len = self.input.u16_le("Unexpected end of compressed data")?;
+                        let nlen = self.input.u16_le("Unexpected end of compressed data")?;
+                        if nlen != !len {
+                            return Err(corrupt("Invalid stored block length in compressed data"));
+                        }
+                        State::Stored { remaining: usize::from(len), last }
+                    },
+                    1 => {
+                        let mut lengths = [0u8; 288];
+                        lengths[..144].fill(8);
+                        lengths[144..256].fill(9);
+                        lengths[256..280].fill(7);
+                        lengths[280..].fill(8);
+                        State::Compressed { literals: Huffman::new(&lengths)?, distances: Huffman::new(&[5; 30])?, last }
+                    },
+                    2 => {
+                        let (literals, distances) = dynamic_tables(&mut self.input)?;
+                        State::Compressed { literals, distances, last }
+                    },
+                    _ => return Err(corrupt("Invalid block type in compressed data")),
+                }
+            },
+            State::Stored { remaining, last } => {
+                let len = remaining.min(WINDOW_SIZE);
+                for _ in 0..len {
+                    let byte = self.input.byte("Unexpected end of compressed data")?;
+                    self.output.push(byte);
+                }
+                match remaining - len {
+                    0 => end_of_block(last),
+                    remaining => State::Stored { remaining, last },
+                }
+            },
+            State::Compressed { literals, distances, last } => {
+                if compressed_block(&mut self.input, &mut self.output, &literals, &distances)? {
+                    end_of_block(last)
+                } else {
+                    State::Compressed { literals, distances, last }
+                }
+            },
+            State::Trailer => {
+                self.input.align();
+                let mut trailer = [0u8; 8];
+                for byte in &mut trailer {
+                    *byte = self.input.byte("Truncated gzip trailer")?;
+                }
+                let crc = mem::replace(&mut self.crc, StreamingHasher::new(HashAlgorithm::Crc32)).finalize();
+                let size = mem::take(&mut self.size);
+                let expected_crc = u32::from_le_bytes(trailer[..4].try_into().unwrap());
+                let expected_size = u32::from_le_bytes(trailer[4..].try_into().unwrap());
+                if crc.as_bytes() != expected_crc.to_be_bytes() || size != expected_size {
+                    return Err(corrupt("gzip data is corrupt (CRC or length mismatch)"));
+                }
+                State::Header { first: false }
+            },
+            State::Done => State::Done,
+        };
+        Ok(())
+    }
+
+    /// Reads a member header, up to the compressed data.
+    fn header(&mut self) -> io::Result<()> {
+        const TRUNCATED: &str = "Truncated gzip header";
+        let mut header = [0u8; 10];
+        for byte in &mut header {
+            *byte = self.input.byte(TRUNCATED)?;
+        }
+        if header[..2] != GZIP_MAGIC || header[2] != 8 {
+            return Err(corrupt("Not a gzip stream"));
+        }
+        let flags = header[3];
+
+        if flags & FLAG_EXTRA != 0 {
+            let len = self.input.u16_le(TRUNCATED)?;
+            for _ in 0..len {
+                self.input.byte(TRUNCATED)?;
+            }
+        }
+        for flag in [FLAG_NAME, FLAG_COMMENT] {
+            if flag & flags != 0 {
+                while self.input.byte(TRUNCATED)? != 0 {}
+            }
+        }
+        if flags & FLAG_HCRC != 0 {
+            self.input.u16_le(TRUNCATED)?;
+        }
+
+        // Matches never reach into a previous member
+        self.output.clear();
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+fn end_of_block(last: bool) -> State {
+    if last { State::Trailer } else { State::Block }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.output.len() {
+            if let State::Done = self.state {
+                return Ok(0);
+            }
+            if self.output.len() > 2 * WINDOW_SIZE {
+                self.output.drain(..self.output.len() - WINDOW_SIZE);
+                self.pos = self.output.len();
+            }
+            let start = self.output.len();
+            self.step()?;
+            let decoded = &self.output[start.min(self.output.len())..];
+            self.crc.update(decoded);
+            self.size = self.size.wrapping_add(decoded.len() as u32);
+        }
+
+        let len = buf.len().min(self.output.len() - self.pos);
+        buf[..len].copy_from_slice(&self.output[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// Reads bits, least significant first, and whole bytes from a stream.
+struct BitReader<R> {
+    inner: R,
+    chunk: Box<[u8]>,
+    /// Unread bytes of `chunk`
+    start: usize,
+    end: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    fn new(inner: R) -> BitReader<R> {
+        BitReader { inner, chunk: vec![0; 16 * 1024].into_boxed_slice(), start: 0, end: 0, buffer: 0, count: 0 }
+    }
+
+    /// Whether the stream has no more bytes.
+    fn at_end(&mut self) -> io::Result<bool> {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = loop {
+                match self.inner.read(&mut self.chunk) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    result => break result?,
+                }
+            };
+        }
+        Ok(self.start == self.end)
+    }
+
+    /// The next whole byte; the stream ending here is an error described by `context`.
+    fn byte(&mut self, context: &str) -> io::Result<u8> {
+        if self.at_end()? {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, context));
+        }
+        self.start += 1;
+        Ok(self.chunk[self.start - 1])
+    }
+
+    fn u16_le(&mut self, context: &str) -> io::Result<u16> {
+        Ok(u16::from_le_bytes([self.byte(context)?, self.byte(context)?]))
+    }
+
+    /// Reads `n` bits (at most 16), least significant first.
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let byte = self.byte("Unexpected end of compressed data")?;
+            self.buffer |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Discards the bits left in the current byte.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// Canonical Huffman code, as counts per code length and symbols in code order.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// The code with the given code length per symbol. More codes of some
+    /// length than the shorter ones leave room for are an error; fewer (an
+    /// incomplete code) only make the unused codes fail to decode.
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(corrupt("Invalid Huffman table in compressed data"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[usize::from(offsets[usize::from(len)])] = symbol as u16;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, reader: &mut BitReader<R>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("Invalid Huffman code in compressed data"))
+    }
+}
+
+fn dynamic_tables<R: Read>(reader: &mut BitReader<R>) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_length_code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or_else(|| corrupt("Invalid code lengths in compressed data"))?;
+                (previous, 3 + reader.bits(2)?)
+            },
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(corrupt("Invalid code lengths in compressed data"));
+    }
+
+    let (literals, distances) = lengths.split_at(literal_count);
+    if literals[256] == 0 {
+        return Err(corrupt("Missing end of block code in compressed data"));
+    }
+    Ok((Huffman::new(literals)?, Huffman::new(distances)?))
+}
+
+/// Decodes symbols of a Huffman-coded block into `out` until the end of the
+/// block, returning true, or until `WINDOW_SIZE` bytes were added.
+fn compressed_block<R: Read>(reader: &mut BitReader<R>, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> io::Result<bool> {
+    let limit = out.len() + WINDOW_SIZE;
+    while out.len() < limit {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(true),
+            _ => {
+                let index = usize::from(symbol - 257);
+                let base = *LENGTH_BASE.get(index).ok_or_else(|| corrupt("Invalid length in compressed data"))?;
+                let len = usize::from(base) + reader.bits(u32::from(LENGTH_EXTRA[index]))? as usize;
+
+                let index = usize::from(distances.decode(reader)?);
+                let base = *DIST_BASE.get(index).ok_or_else(|| corrupt("Invalid distance in compressed data"))?;
+                let dist = usize::from(base) + reader.bits(u32::from(DIST_EXTRA[index]))? as usize;
+                if dist > out.len() {
+                    return Err(corrupt("Invalid distance in compressed data"));
+                }
+
+                // Copied byte by byte: the match may overlap its own output
+                let start = out.len() - dist;
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            },
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding;
+
+    const GZIP_STORED: &str = "H4sIAAAAAAAEAwELAPT/aGVsbG8gd29ybGSFEUoNCwAAAA==";
+    const GZIP_FIXED: &str = "H4sIAAAAAAAAA8tIzcnJV8jAIMvzi3JSADNlNDQdAAAA";
+    const GZIP_DYNAMIC: &str = "H4sIAAAAAAACA23XOU4EQRREQZ9T9BEqa68DDQJpBAZzfyFM1OF+q0Oz5Ovn59fjKtf3+/X6eFyvx8/r7fl3yv1U76d2P/X7adxP835a99O+nw4eVY+P5w8AgSAgBIYAESgCRuCocFR9DnBUOCocFY4KR4WjwlHhaHA0OJq+UHA0OBocDY4GR4OjwdHh6HB0OLp+GXB0ODocHY4OR4djwDHgGHAMOIZ+4nAMOAYcA44Bx4RjwjHhmHBMOKb+q+CYcEw4JhwLjgXHgmPBseBYcCz96cKx4FhwbDg2HBuODceGY8Ox4dhaDzg2HAeOA8eB48Bx4DhwHDgOHEczyB3UEBYtYdEUFm1h0RgWrWHRHBbtYdEgFok87RJx3LnunHfuOweeC8+J18ZHI5/KWpFIOx8NfbT00dRHWx+NfbT20dxHe5/GAJNIkx9tfjT60epHsx/tfjT80fJH05/OppRI6x/Nf7T/UQBEBRAlQNQAUQREFZDBTJZIIRCVQJQCUQtEMRDVQJQDUQ9EQZDJ8pdITRBFQVQFURZEXRCFQVQGURpEbZDFlxmJlAdRH0SBEBVClAhRI0SREFVClAnZfD+TSKUQpULUClEsRLUQ5ULUC1EwRMWQw1fO/6JfP2N4IfoPAAA=";
+
+    /// 200 000 bytes of `0123456789abcdef`, from Python's gzip module at level 9.
+    const GZIP_LONG: &str = "H4sIAAAAAAACA+3HWwHAEAAAwErGvOJg9I+wCArc/V14Ynpzqa2Pub59gru7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u1//A4koaDhADQMA";
+
+    fn gunzip(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    /// A reader handing out one byte per call, like a slow pipe.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    /// A gzip member header followed by DEFLATE bits written one field at a time.
+    struct Bits {
+        data: Vec<u8>,
+        count: u32,
+    }
+
+    impl Bits {
+        fn gzip() -> Bits {
+            Bits { data: vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3], count: 0 }
+        }
+
+        /// `n` bits of `value`, least significant first, like header fields.
+        fn bits(&mut self, value: u32, n: u32) -> &mut Bits {
+            for i in 0..n {
+                if self.count.is_multiple_of(8) {
+                    self.data.push(0);
+                }
+                *self.data.last_mut().unwrap() |= (((value >> i) & 1) as u8) << (self.count % 8);
+                self.count += 1;
+            }
+            self
+        }
+
+        /// Pads to a byte boundary, as before the length of a stored block.
+        fn align(&mut self) -> &mut Bits {
+            self.count = self.count.next_multiple_of(8);
+            self
+        }
+
+        /// A Huffman code of `n` bits, most significant first.
+        fn code(&mut self, code: u32, n: u32) -> &mut Bits {
+            for i in (0..n).rev() {
+                self.bits((code >> i) & 1, 1);
+            }
+            self
+        }
+    }
+
+    fn dynamic_text() -> String {
+        (0..200).map(|i| format!("line {} of the test\n", i)).collect()
+    }
+
+    fn gunzip_base64(text: &str) -> Vec<u8> {
+        gunzip(&encoding::decode_base64(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_gunzip() {
+        // Streams written by Python's gzip module at levels 0 (stored), 6 and 9
+        assert_eq!(gunzip_base64(GZIP_STORED), b"hello world");
+        assert_eq!(gunzip_base64(GZIP_FIXED), b"hello hello hello hello world");
+        assert_eq!(gunzip_base64(GZIP_DYNAMIC), dynamic_text().as_bytes());
+    }
+
+    #[test]
+    fn test_gunzip_streams_past_the_window() {
+        let expected = b"0123456789abcdef".repeat(12_500);
+        let data = encoding::decode_base64(GZIP_LONG).unwrap();
+        assert_eq!(gunzip(&data).unwrap(), expected);
+
+        let mut decoder = GzDecoder::new(Trickle(&data));
+        let mut out = Vec::new();
+        let mut buf = [0u8; 1000];
+        loop {
+            let n = decoder.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+            assert!(decoder.output.len() <= 2 * WINDOW_SIZE + WINDOW_SIZE + 258);
+        }
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_gunzip_long_stored_blocks() {
+        let content: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let mut data = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+        let mut blocks = content.chunks(40_000).peekable();
+        while let Some(block) = blocks.next() {
+            data.push(u8::from(blocks.peek().is_none()));
+            data.extend((block.len() as u16).to_le_bytes());
+            data.extend((!(block.len() as u16)).to_le_bytes());
+            data.extend(block);
+        }
+        let crc = rustedbytes_sha::hash_bytes(&content, HashAlgorithm::Crc32).into_bytes();
+        data.extend(crc.iter().rev());
+        data.extend((content.len() as u32).to_le_bytes());
+
+        assert_eq!(gunzip(&data).unwrap(), content);
+    }
+
+    #[test]
+    fn test_gunzip_concatenated_members() {
+        let mut data = encoding::decode_base64(GZIP_STORED).unwrap();
+        data.extend(encoding::decode_base64(GZIP_FIXED).unwrap());
+        assert_eq!(gunzip(&data).unwrap(), b"hello worldhello hello hello hello world");
+    }
+
+    #[test]
+    fn test_gunzip_detects_corruption() {
+        let mut data = encoding::decode_base64(GZIP_STORED).unwrap();
+        let len = data.len();
+        data[len - 9] ^= 1;
+        assert!(gunzip(&data).unwrap_err().to_string().contains("corrupt"));
+        assert!(gunzip(&data[..len - 4]).is_err());
+        assert!(!is_gzip(b"ustar"));
+    }
+
+    #[test]
+    fn test_gunzip_rejects_bad_huffman_tables() {
+        // A dynamic block giving all 19 code length codes a length of 1
+        let mut bits = Bits::gzip();
+        bits.bits(1, 1).bits(2, 2).bits(0, 5).bits(0, 5).bits(15, 4);
+        for _ in 0..19 {
+            bits.bits(1, 3);
+        }
+        assert!(gunzip(&bits.data).unwrap_err().to_string().contains("Invalid Huffman table"));
+
+        // Code lengths for 257 literals and 1 distance, none for end of
+        // block: code length codes 1 (code 0) and 18 (code 1)
+        let mut bits = Bits::gzip();
+        bits.bits(1, 1).bits(2, 2).bits(0, 5).bits(0, 5).bits(14, 4);
+        for symbol in &CODE_LENGTH_ORDER[..18] {
+            bits.bits(u32::from(matches!(symbol, 1 | 18)), 3);
+        }
+        bits.code(1, 1).bits(127, 7).code(1, 1).bits(108, 7).code(0, 1);
+        assert!(gunzip(&bits.data).unwrap_err().to_string().contains("Missing end of block"));
+
+        // Literal/length symbol 286 of the fixed code, which has no length
+        let mut bits = Bits::gzip();
+        bits.bits(1, 1).bits(1, 2).code(0xc6, 8);
+        assert!(gunzip(&bits.data).unwrap_err().to_string().contains("Invalid length"));
+    }
+
+    #[test]
+    fn test_gunzip_rejects_distances_before_the_window_start() {
+        // A match of distance 1 before any output
+        let mut bits = Bits::gzip();
+        bits.bits(1, 1).bits(1, 2).code(1, 7).code(0, 5);
+        assert!(gunzip(&bits.data).unwrap_err().to_string().contains("Invalid distance"));
+
+        // 'a', then a match of distance 2
+        let mut bits = Bits::gzip();
+        bits.bits(1, 1).bits(1, 2).code(0x30 + u32::from(b'a'), 8).code(1, 7).code(1, 5);
+        assert!(gunzip(&bits.data).unwrap_err().to_string().contains("Invalid distance"));
+
+        // Matches never reach into the previous member
+        let mut data = encoding::decode_base64(GZIP_STORED).unwrap();
+        data.extend(Bits::gzip().bits(1, 1).bits(1, 2).code(1, 7).code(0, 5).data.iter());
+        assert!(gunzip(&data).unwrap_err().to_string().contains("Invalid distance"));
+    }
+
+    #[test]
+    fn test_gunzip_rejects_truncated_input() {
+        assert!(gunzip(&[0x1f, 0x8b, 8]).unwrap_err().to_string().contains("Truncated gzip header"));
+        // A file name without its terminating NUL
+        let name = [0x1f, 0x8b, 8, FLAG_NAME, 0, 0, 0, 0, 0, 3, b'a', b'b'];
+        assert!(gunzip(&name).unwrap_err().to_string().contains("Truncated gzip header"));
+        // A stored block shorter than its length
+        let mut bits = Bits::gzip();
+        bits.bits(1, 1).bits(0, 2).align().bits(5, 16).bits(!5, 16).bits(u32::from(b'a'), 8);
+        assert_eq!(gunzip(&bits.data).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        // Every prefix of a valid member fails, and garbage after one is no gzip member
+        let data = encoding::decode_base64(GZIP_DYNAMIC).unwrap();
+        for len in 0..data.len() {
+            assert!(gunzip(&data[..len]).is_err() || len == 0, "{}", len);
+        }
+        let mut garbage = data.clone();
+        garbage.extend(b"trailing garbage");
+        assert!(gunzip(&garbage).unwrap_err().to_string().contains("Not a gzip stream"));
+    }
+
+    #[test]
+    fn test_gunzip_survives_corrupt_streams() {
+        // Every single-bit error in the header, Huffman tables, data or
+        // trailer ends in an error or a stream that still checks out, never
+        // a panic or an endless loop
+        let data = encoding::decode_base64(GZIP_DYNAMIC).unwrap();
+        for bit in 0..data.len() * 8 {
+            let mut corrupt = data.clone();
+            corrupt[bit / 8] ^= 1 << (bit % 8);
+            if let Ok(out) = gunzip(&corrupt) {
+                assert_eq!(out, dynamic_text().as_bytes(), "bit {}", bit);
+            }
+        }
+    }
+}
@@ -1 +1,299 @@
-pub mod hasher;
\ No newline at end of file
+//! Hashing with the algorithms of the `sha-calc` tool, for programs that
+//! would otherwise run the binary and parse its output.
+//!
+#![cfg_attr(feature = "sha2", doc = "```")]
+#![cfg_attr(not(feature = "sha2"), doc = "```ignore")]
+//! use rustedbytes_sha::{hash_bytes, HashAlgorithm};
+//!
+//! let digest = hash_bytes(b"hello world", HashAlgorithm::Sha256);
+//! assert_eq!(digest.to_hex(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+//! assert_eq!(digest.to_base64(), "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=");
+//! ```
+//!
+//! Without the default `std` feature the crate is `no_std` (it still needs
+//! `alloc`) and provides only [`core`]: no file or reader hashing. The
+//! `wasm` feature links `std` for its allocator and panic handler only.
+
+#![cfg_attr(not(any(feature = "std", feature = "wasm", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, BufReader, Read, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod core;
+mod digest;
+pub mod encoding;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+mod gitignore;
+pub mod hasher;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod runner;
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "std")]
+pub mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+mod writer;
+
+pub use digest::Digest;
+pub use encoding::Encoding;
+#[cfg(feature = "std")]
+pub use error::Error;
+#[cfg(feature = "std")]
+pub use format::Format;
+pub use hasher::{HashAlgorithm, MultiHasher, StreamingHasher, UnknownAlgorithm};
+#[cfg(feature = "std")]
+pub use record::{HashRecord, hash_file_record};
+#[cfg(feature = "std")]
+pub use runner::{RunOutput, Runner};
+#[cfg(feature = "std")]
+pub use verify::{EntryReport, EntryStatus, VerificationReport, VerifyHooks, VerifyOptions, verify_manifest, verify_manifest_with, verify_text_with};
+#[cfg(feature = "std")]
+pub use writer::HashingWriter;
+
+/// Size of the chunks read by [`hash_reader`] and [`hash_file`].
+#[cfg(feature = "std")]
+pub const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Size of the chunks read by [`hash_async_reader`], smaller than
+/// [`BUFFER_SIZE`] as a service may hash many bodies at once.
+#[cfg(feature = "async")]
+pub const ASYNC_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The digest of `data`.
+pub fn hash_bytes(data: &[u8], algorithm: HashAlgorithm) -> Digest {
+    let mut hasher = StreamingHasher::new(algorithm);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// The digest of everything `reader` yields up to its end, copied in
+/// chunks of [`BUFFER_SIZE`] bytes through a [`HashingWriter`], so that
+/// memory use does not grow with the input.
+#[cfg(feature = "std")]
+pub fn hash_reader<R: Read>(reader: R, algorithm: HashAlgorithm) -> Result<Digest, Error> {
+    hash_reader_with_progress(reader, algorithm, None, |_, _| {})
+}
+
+/// Like [`hash_reader`], calling `progress(bytes_done, total)` after each
+/// chunk, where `total` is the expected size if the caller knows it. To
+/// report from another thread, send the values on a channel from `progress`.
+#[cfg(feature = "std")]
+pub fn hash_reader_with_progress<R, F>(reader: R, algorithm: HashAlgorithm, total: Option<u64>, progress: F) -> Result<Digest, Error>
+where
+    R: Read,
+    F: FnMut(u64, Option<u64>),
+{
+    copy_digest(reader, algorithm, Progress { done: 0, total, callback: progress })
+        .map_err(|source| Error::ReadFailed { path: None, source })
+}
+
+/// The digest of everything `reader` yields up to its end, for async
+/// services hashing request bodies or streams: the reads are awaited, so
+/// the executor thread is never blocked, and memory use stays at one
+/// [`ASYNC_BUFFER_SIZE`] chunk.
+#[cfg(feature = "async")]
+pub async fn hash_async_reader<R: AsyncRead + Unpin>(mut reader: R, algorithm: HashAlgorithm) -> Result<Digest, Error> {
+    let mut hasher = StreamingHasher::new(algorithm);
+    let mut buf = vec![0; ASYNC_BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(source) => return Err(Error::ReadFailed { path: None, source }),
+        }
+    }
+}
+
+/// The digest of the file at `path`.
+#[cfg(feature = "std")]
+pub fn hash_file<P: AsRef<Path>>(path: P, algorithm: HashAlgorithm) -> Result<Digest, Error> {
+    hash_file_with_progress(path, algorithm, |_, _| {})
+}
+
+/// Like [`hash_file`], calling `progress(bytes_done, total)` after each
+/// chunk, with the file size as `total` when the metadata has one.
+#[cfg(feature = "std")]
+pub fn hash_file_with_progress<P, F>(path: P, algorithm: HashAlgorithm, progress: F) -> Result<Digest, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(u64, Option<u64>),
+{
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|source| Error::OpenFailed { path: path.to_path_buf(), source })?;
+    // Pipes and devices report no useful length
+    let total = file.metadata().ok().filter(|metadata| metadata.is_file()).map(|metadata| metadata.len());
+    copy_digest(file, algorithm, Progress { done: 0, total, callback: progress })
+        .map_err(|source| Error::ReadFailed { path: Some(path.to_path_buf()), source })
+}
+
+#[cfg(feature = "std")]
+fn copy_digest<R: Read, W: Write>(reader: R, algorithm: HashAlgorithm, sink: W) -> io::Result<Digest> {
+    let mut writer = HashingWriter::new(sink, algorithm);
+    io::copy(&mut BufReader::with_capacity(BUFFER_SIZE, reader), &mut writer)?;
+    Ok(writer.finalize().1)
+}
+
+/// A sink reporting the bytes written to it so far.
+#[cfg(feature = "std")]
+struct Progress<F> {
+    done: u64,
+    total: Option<u64>,
+    callback: F,
+}
+
+#[cfg(feature = "std")]
+impl<F: FnMut(u64, Option<u64>)> Write for Progress<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.done += buf.len() as u64;
+        (self.callback)(self.done, self.total);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn test_hash_bytes() {
+        assert_eq!(hash_bytes(b"", HashAlgorithm::Sha1).to_hex(), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hash_bytes(b"123456789", HashAlgorithm::Crc32).as_bytes(), [0xcb, 0xf4, 0x39, 0x26]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "sha2", feature = "blake2"))]
+    fn test_hash_reader_matches_hash_bytes() {
+        let data = vec![7u8; BUFFER_SIZE + 100];
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake2b, HashAlgorithm::Crc32] {
+            assert_eq!(hash_reader(&data[..], algorithm).unwrap(), hash_bytes(&data, algorithm));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_hash_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"hello world").unwrap();
+        assert_eq!(
+            hash_file(file.path(), HashAlgorithm::Sha256).unwrap().to_hex(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+        );
+        match hash_file("/nonexistent/file", HashAlgorithm::Sha256) {
+            Err(Error::OpenFailed { path, source }) => {
+                assert_eq!(path, Path::new("/nonexistent/file"));
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_progress() {
+        let data = vec![1u8; BUFFER_SIZE * 2 + 100];
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+
+        let mut calls = Vec::new();
+        let digest = hash_file_with_progress(file.path(), HashAlgorithm::Sha256, |done, total| calls.push((done, total)))
+            .unwrap();
+        assert_eq!(digest, hash_bytes(&data, HashAlgorithm::Sha256));
+        let total = data.len() as u64;
+        assert!(calls.len() >= 3, "{:?}", calls);
+        assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(calls.last(), Some(&(total, Some(total))));
+
+        let mut last = None;
+        hash_reader_with_progress(&data[..], HashAlgorithm::Crc32, None, |done, total| last = Some((done, total)))
+            .unwrap();
+        assert_eq!(last, Some((total, None)));
+    }
+
+    #[test]
+    fn test_hash_reader_error() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("device gone"))
+            }
+        }
+
+        let error = hash_reader(Failing, HashAlgorithm::Crc32).unwrap_err();
+        assert!(matches!(error, Error::ReadFailed { path: None, .. }));
+        assert_eq!(error.to_string(), "read failed: device gone");
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_hash_async_reader() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+        use tokio::io::ReadBuf;
+
+        /// Yields `data` a few bytes at a time, interrupted before each read
+        /// and failing at the end when `fail` is set.
+        struct Trickle {
+            data: Vec<u8>,
+            interrupt: bool,
+            fail: bool,
+        }
+
+        impl AsyncRead for Trickle {
+            fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+                self.interrupt = !self.interrupt;
+                if self.interrupt {
+                    return Poll::Ready(Err(io::ErrorKind::Interrupted.into()));
+                }
+                if self.data.is_empty() && self.fail {
+                    return Poll::Ready(Err(io::Error::other("connection reset")));
+                }
+                let n = self.data.len().min(buf.remaining()).min(1000);
+                buf.put_slice(&self.data[..n]);
+                self.data.drain(..n);
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let data: Vec<u8> = (0..ASYNC_BUFFER_SIZE * 2 + 100).map(|i| i as u8).collect();
+
+        let digest = runtime.block_on(hash_async_reader(&data[..], HashAlgorithm::Crc32)).unwrap();
+        assert_eq!(digest, hash_bytes(&data, HashAlgorithm::Crc32));
+
+        let trickle = Trickle { data: data.clone(), interrupt: false, fail: false };
+        let digest = runtime.block_on(hash_async_reader(trickle, HashAlgorithm::Crc32)).unwrap();
+        assert_eq!(digest, hash_bytes(&data, HashAlgorithm::Crc32));
+
+        let trickle = Trickle { data, interrupt: false, fail: true };
+        let error = runtime.block_on(hash_async_reader(trickle, HashAlgorithm::Crc32)).unwrap_err();
+        assert!(matches!(error, Error::ReadFailed { path: None, .. }));
+        assert_eq!(error.to_string(), "read failed: connection reset");
+    }
+}
@@ -0,0 +1,119 @@
+//! Magnet link generation from a BitTorrent v1 info-hash (`btih`).
+
+use std::io::{self, Write};
+
+use crate::hasher::{HashAlgorithm, StreamingHasher};
+
+/// Piece size of the single-file torrent the info-hash is derived from
+/// (256 KiB, the default of mktorrent and most clients for small files).
+pub const PIECE_LENGTH: usize = 256 * 1024;
+
+/// Collects the piece hashes of a file written to it, so a magnet link can
+/// be built without holding the file in memory.
+pub struct MagnetLink {
+    piece: StreamingHasher,
+    /// Bytes of the current piece written so far.
+    piece_len: usize,
+    pieces: Vec<u8>,
+    length: u64,
+}
+
+impl MagnetLink {
+    pub fn new() -> MagnetLink {
+        MagnetLink {
+            piece: StreamingHasher::new(HashAlgorithm::Sha1),
+            piece_len: 0,
+            pieces: Vec::new(),
+            length: 0,
+        }
+    }
+
+    /// The magnet URI of the data written, with `name` as the display name
+    /// (`dn`) when given.
+    pub fn finish(mut self, name: Option<&str>) -> String {
+        if self.piece_len > 0 {
+            self.end_piece();
+        }
+        let mut uri = format!("magnet:?xt=urn:btih:{}", hex::encode(self.info_hash(name.unwrap_or_default())));
+        if let Some(name) = name {
+            uri.push_str("&dn=");
+            uri.push_str(&percent_encode(name));
+        }
+        uri.push_str(&format!("&xl={}", self.length));
+        uri
+    }
+
+    fn end_piece(&mut self) {
+        let piece = std::mem::replace(&mut self.piece, StreamingHasher::new(HashAlgorithm::Sha1));
+        self.pieces.extend_from_slice(piece.finalize().as_bytes());
+        self.piece_len = 0;
+    }
+
+    /// SHA-1 of the bencoded `info` dictionary of a single-file torrent.
+    fn info_hash(&self, name: &str) -> Vec<u8> {
+        // Keys of a bencoded dictionary must appear in sorted order.
+        let mut info = Vec::new();
+        info.extend_from_slice(format!("d6:lengthi{}e", self.length).as_bytes());
+        info.extend_from_slice(format!("4:name{}:{}", name.len(), name).as_bytes());
+        info.extend_from_slice(format!("12:piece lengthi{}e", PIECE_LENGTH).as_bytes());
+        info.extend_from_slice(format!("6:pieces{}:", self.pieces.len()).as_bytes());
+        info.extend_from_slice(&self.pieces);
+        info.push(b'e');
+
+        rustedbytes_sha::hash_bytes(&info, HashAlgorithm::Sha1).into_bytes()
+    }
+}
+
+impl Write for MagnetLink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(PIECE_LENGTH - self.piece_len);
+        self.piece.update(&buf[..len]);
+        self.piece_len += len;
+        self.length += len as u64;
+        if self.piece_len == PIECE_LENGTH {
+            self.end_piece();
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn test_info_hash_multiple_pieces() {
+        let data = b"hello world".repeat(50_000);
+        let mut link = MagnetLink::new();
+        // Writes that straddle piece boundaries
+        for chunk in data.chunks(100_000) {
+            link.write_all(chunk).unwrap();
+        }
+        assert_eq!(
+            link.finish(Some("hello.txt")),
+            "magnet:?xt=urn:btih:e88b3810780a8971ad4153bc8d4ae585eea197a8&dn=hello.txt&xl=550000"
+        );
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("my file (1).iso"), "my%20file%20%281%29.iso");
+        assert_eq!(percent_encode("è"), "%C3%A8");
+    }
+}
@@ -1,216 +1,2832 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use clap::parser::ValueSource;
+use std::borrow::Cow;
 use std::env;
+use std::ffi::OsString;
 use std::fs::File;
-use std::io::{self, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 
-mod hasher;
-use hasher::{HashAlgorithm, calculate_hash};
+
+mod atomic;
+mod blockdev;
+#[cfg(feature = "cloud")]
+mod cloud;
+mod ed25519;
+mod exit;
+mod inflate;
+mod magnet;
+mod minisign;
+mod output;
+mod qr;
+mod remote;
+mod streams;
+mod tar;
+mod template;
+mod xattr;
+use rustedbytes_sha::encoding::{self, Encoding, Grouping, InputEncoding};
+use rustedbytes_sha::hasher::{self, HashAlgorithm, MultiHasher, StreamingHasher};
+use rustedbytes_sha::verify::{self, EntryReport, EntryStatus, VerifyOptions};
+use rustedbytes_sha::walk::{self, Hidden, WalkOptions};
+use rustedbytes_sha::{BUFFER_SIZE, Digest, HashRecord, HashingWriter, manifest};
+use output::{CheckSummary, Color, ColorChoice, DigestStyle, Format, ProgressFormat, SummaryFormat};
+use template::Template;
+
+/// `--progress` reporter writing to stderr or the `--progress-fd` descriptor.
+type ProgressReport = output::Progress<Box<dyn Write>>;
+
+/// Digests and sizes of hardlinked files already hashed, by device and
+/// inode, for `--one-hash-per-inode`.
+type Links = std::collections::HashMap<(u64, u64), (Vec<u8>, u64)>;
+
+/// How often `--watch` polls the watched files for changes.
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
 
 #[derive(Parser)]
 #[command(name = env!("CARGO_PKG_NAME"), version = env!("CARGO_PKG_VERSION"))]
 #[command(about = "Calculate SHA hashes for files or stdin")]
 struct Args {
-    /// Hash algorithm to use
-    #[arg(short, long, default_value = "sha256")]
+    /// Hash algorithm to use (in check mode, for digests of its length; others
+    /// are detected per line from their length or tag); several separated by
+    /// commas print a digest for each, from a single read of every input
+    #[arg(id = "algorithm", short = 'a', long = "algorithm", value_name = "ALGORITHM", value_delimiter = ',',
+          default_values_t = [HashAlgorithm::default()])]
+    algorithms: Vec<HashAlgorithm>,
+
+    /// The first of `algorithms`, the only one outside plain hashing
+    #[arg(skip)]
     algorithm: HashAlgorithm,
+
+    /// Whether `--algorithm` was given on the command line
+    #[arg(skip)]
+    algorithm_explicit: bool,
     
-    /// Input files or glob patterns (if none provided, reads from stdin)
+    /// Input files, glob patterns or HTTPS URLs (if none provided, reads from stdin)
     #[arg(value_name = "FILES")]
     files: Vec<String>,
     
-    /// Output only the hash (no filename)
+    /// Hash this string instead of a file (repeatable); no newline is added
+    #[arg(short, long, value_name = "STRING",
+          conflicts_with_all = ["check", "format", "template", "magnet", "sidecar", "update", "check_xattr", "diff", "expect"])]
+    string: Vec<String>,
+
+    /// Split stdin into records and print a digest for each, named `-:N` after
+    /// its record number: lines (the default) or NUL-terminated records
+    #[arg(long, value_name = "DELIM", num_args = 0..=1, require_equals = true, default_missing_value = "newline",
+          conflicts_with_all = ["files", "string", "check", "format", "template", "magnet", "sidecar", "update",
+          "check_xattr", "diff", "expect", "tee", "archive", "tree", "watch", "dupes", "concat_list", "parts",
+          "member", "compare", "compare_dirs", "offset", "length", "hex_input", "base64_input", "streams", "progress"])]
+    records: Option<RecordDelimiter>,
+
+    /// Decode stdin and -s strings from hex before hashing, for data only
+    /// available as a hex dump (whitespace is ignored)
+    #[arg(long, conflicts_with_all = ["check", "update", "check_xattr", "diff"])]
+    hex_input: bool,
+
+    /// Decode stdin and -s strings from base64 before hashing, e.g. a
+    /// certificate or attachment copied from JSON or YAML (whitespace is ignored)
+    #[arg(long, conflicts_with_all = ["hex_input", "check", "update", "check_xattr", "diff"])]
+    base64_input: bool,
+
+    /// Treat FILES as tar archives (optionally gzip-compressed) and print a
+    /// digest for each file they contain, with its path inside the archive
+    #[arg(long, conflicts_with_all = ["check", "recursive", "update", "check_xattr", "diff", "expect", "magnet", "sidecar", "string"])]
+    archive: bool,
+
+    /// Hash only the file at PATH inside each tar or .tar.gz archive in
+    /// FILES, printed as `archive!PATH`; the same as giving `archive!PATH`
+    #[arg(long, value_name = "PATH", requires = "files",
+          conflicts_with_all = ["archive", "tree", "recursive", "concat_list", "check", "update", "check_xattr", "diff",
+          "compare", "compare_dirs", "dupes", "watch", "tee", "string", "streams"])]
+    member: Option<String>,
+
+    /// Copy stdin unchanged to stdout and print its digest to stderr (or the
+    /// --output file) at end of input, to hash data in the middle of a pipe
+    #[arg(long, conflicts_with_all = ["files", "string", "check", "update", "check_xattr", "diff", "expect",
+          "archive", "format", "template", "magnet", "sidecar", "qr", "raw"])]
+    tee: bool,
+
+    /// Print one digest for each directory in FILES, covering the relative
+    /// paths, modes and contents of every file below it in a canonical order,
+    /// so whole trees can be compared with a single value
+    #[arg(long, conflicts_with_all = ["check", "recursive", "archive", "string", "tee", "update", "check_xattr",
+          "diff", "magnet", "sidecar", "git_blob", "offset", "length"])]
+    tree: bool,
+
+    /// Hash inputs as Git blobs (`blob <size>\0` + content), matching
+    /// `git hash-object`; SHA-1 unless `-a sha256` is given for SHA-256 repositories
+    #[arg(long, conflicts_with_all = ["check", "tee", "update", "check_xattr", "diff", "magnet", "sidecar"])]
+    git_blob: bool,
+
+    /// Hash only the bytes from this offset on, e.g. `512`, `0x8000` or `4M`
+    /// (K, M, G and T are powers of 1024)
+    #[arg(long, value_name = "BYTES", value_parser = parse_size,
+          conflicts_with_all = ["check", "string", "tee", "archive", "update", "check_xattr", "diff", "magnet", "sidecar"])]
+    offset: Option<u64>,
+
+    /// Hash exactly this many bytes (from --offset); fails if the input is shorter
+    #[arg(long, value_name = "BYTES", value_parser = parse_size,
+          conflicts_with_all = ["check", "string", "tee", "archive", "update", "check_xattr", "diff", "magnet", "sidecar"])]
+    length: Option<u64>,
+
+    /// Hash the files listed in LIST (one path per line, relative to the
+    /// list's directory) as a single stream, in the listed order
+    #[arg(long, value_name = "LIST",
+          conflicts_with_all = ["files", "string", "tee", "check", "update", "check_xattr", "diff", "compare",
+          "compare_dirs", "dupes", "watch", "archive", "tree", "magnet", "sidecar", "streams", "offset", "length", "git_blob"])]
+    concat_list: Option<String>,
+
+    /// Treat FILES as the volumes of one split file (`.z01`, `.part2`, `.003`)
+    /// and hash their concatenation, in natural order (`part2` before `part10`)
+    #[arg(long, requires = "files",
+          conflicts_with_all = ["concat_list", "member", "string", "tee", "check", "update", "check_xattr", "diff",
+          "compare", "compare_dirs", "dupes", "watch", "archive", "tree", "recursive", "magnet", "sidecar", "streams",
+          "offset", "length", "git_blob"])]
+    parts: bool,
+
+    /// Hash every regular file below the directories given as FILES
+    #[arg(short, long, conflicts_with_all = ["check", "diff"])]
+    recursive: bool,
+
+    /// With -r or --tree, skip files ignored by .gitignore files (those in
+    /// the tree and, inside a Git repository, in its parent directories, its
+    /// .git/info/exclude and core.excludesFile) and `.git` directories.
+    /// Unlike Git, files already tracked are skipped too when they match, and
+    /// the system Git config and config `include` directives are not read
+    #[arg(long)]
+    gitignore: bool,
+
+    /// With -r or --tree, only hash files at most N levels below the
+    /// directories given (1: the files directly inside them)
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Print groups of files with identical contents, separated by blank
+    /// lines, and the space they waste (only files sharing a size are hashed)
+    #[arg(long, requires = "files",
+          conflicts_with_all = ["check", "diff", "compare", "compare_dirs", "update", "check_xattr", "expect", "watch",
+          "string", "tee", "archive", "tree", "magnet", "sidecar", "format", "template", "quiet", "progress", "qr",
+          "git_blob", "offset", "length"])]
+    dupes: bool,
+
+    /// Include hidden files and directories when walking directories: all of
+    /// them, or with --no-hidden none (dotfiles, and on Windows files with the
+    /// hidden attribute). By default only Windows protected system files are skipped
+    #[arg(long, overrides_with = "no_hidden")]
+    hidden: bool,
+
+    /// Skip hidden files and directories when walking directories (see --hidden)
+    #[arg(long, overrides_with = "hidden")]
+    no_hidden: bool,
+
+    /// When walking directories, only hash files with one of these
+    /// extensions, e.g. `iso,img,qcow2` (case-insensitive)
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    ext: Vec<String>,
+
+    /// When walking directories, only hash regular files (`f`) or symbolic
+    /// links to them (`l`)
+    #[arg(long = "type", value_name = "TYPE")]
+    file_type: Option<walk::FileType>,
+
+    /// Skip files smaller than this, e.g. `1` to skip empty files or `4K`
+    /// (K, M, G and T are powers of 1024)
+    #[arg(long, value_name = "BYTES", value_parser = parse_size)]
+    min_size: Option<u64>,
+
+    /// Skip files larger than this, e.g. `2G`
+    #[arg(long, value_name = "BYTES", value_parser = parse_size)]
+    max_size: Option<u64>,
+
+    /// Also hash the extended attribute values (Linux, macOS) or NTFS
+    /// alternate data streams (Windows) of each file, named `<file>:<name>`
+    #[arg(long, conflicts_with_all = ["check", "update", "check_xattr", "diff", "compare", "compare_dirs", "dupes",
+          "watch", "tee", "archive", "tree", "magnet"])]
+    streams: bool,
+
+    /// Hash hardlinked files (same device and inode) only once, printing the
+    /// digest under each of their names; speeds up scans of snapshot backups (Unix)
+    #[arg(long)]
+    one_hash_per_inode: bool,
+
+    /// Output only the hash (no filename); in check mode, don't print OK
+    /// for each successfully verified file
     #[arg(short, long)]
     quiet: bool,
+
+    /// Check mode: print nothing, the exit status tells whether all files verified
+    #[arg(long, requires = "check", conflicts_with_all = ["warn", "summary"])]
+    status: bool,
     
-    /// Check hash files (format: hash filename)
+    /// Check hash files, `-` (stdin) or HTTPS URLs (format: hash filename)
     #[arg(short, long)]
     check: bool,
 
-    /// List all supported hash algorithms
-    #[arg(long = "list-algorithms")]
-    list_algorithms: bool,
+    /// Exit with status 3 if a manifest contains improperly formatted lines
+    #[arg(long, requires = "check")]
+    strict: bool,
+
+    /// Warn about each improperly formatted manifest line, with the reason
+    #[arg(short, long, requires = "check")]
+    warn: bool,
+
+    /// Verify FILES (or stdin) against a single digest, e.g. one copied from a
+    /// download page; the algorithm is detected from its length unless given
+    #[arg(long, value_name = "DIGEST",
+          conflicts_with_all = ["check", "check_xattr", "update", "diff", "format", "output", "sidecar", "raw", "template", "magnet", "qr"])]
+    expect: Option<String>,
+
+    /// Only check manifest entries, or hash files, whose path matches one of
+    /// these glob patterns (`*` also matches `/`), e.g. `docs/*` or `*.tar.gz`;
+    /// with -r, paths are relative to the directory walked
+    #[arg(long, value_name = "PATTERN", value_parser = parse_pattern)]
+    include: Vec<glob::Pattern>,
+
+    /// Skip manifest entries or files whose path matches one of these glob
+    /// patterns, e.g. `*.log` or `node_modules/**`
+    #[arg(long, value_name = "PATTERN", value_parser = parse_pattern)]
+    exclude: Vec<glob::Pattern>,
+
+    /// Report progress (files and bytes done/total, current file) as JSON Lines
+    /// on stderr, at most twice a second, plus a final `done` record; block
+    /// device sizes are queried from the device
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, require_equals = true,
+          default_missing_value = "json", conflicts_with_all = ["watch", "tee"])]
+    progress: Option<ProgressFormat>,
+
+    /// Write --progress records to this file descriptor instead of stderr (Unix)
+    #[arg(long, value_name = "FD", requires = "progress")]
+    progress_fd: Option<i32>,
+
+    /// Offer to store the current digest of each FAILED file in its manifest,
+    /// for files changed on purpose (the run still reports them as FAILED)
+    #[arg(long, requires = "check", conflicts_with_all = ["status", "watch", "minisign_pubkey"])]
+    fix: bool,
+
+    /// Apply --fix without asking
+    #[arg(short, long, requires = "fix")]
+    yes: bool,
+
+    /// Keep running and print a new digest whenever the contents of a file
+    /// change, watching directories recursively; with -c, re-verify and print
+    /// only status transitions (polls file metadata; stop with Ctrl-C)
+    #[arg(long, conflicts_with_all = ["status", "summary", "failed_output", "recursive_manifests", "minisign_pubkey",
+          "string", "tee", "update", "check_xattr", "diff", "expect", "archive", "tree", "magnet", "sidecar",
+          "output", "format"])]
+    watch: bool,
+
+    /// Also verify the entries of listed manifests (e.g. `sub/SHA256SUMS.sha256`),
+    /// relative to their own directory, once their digest matched
+    #[arg(long, requires = "check")]
+    recursive_manifests: bool,
+
+    /// Treat manifest paths containing `*`, `?` or `[` as glob patterns:
+    /// every matching file must have the listed digest
+    #[arg(long, requires = "check")]
+    glob_entries: bool,
+
+    /// With --glob-entries, report patterns that match no file as MISSING
+    #[arg(long, requires = "glob_entries")]
+    require_glob_match: bool,
+
+    /// Compare two manifests and list added, removed and changed paths
+    /// without hashing anything
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"],
+          conflicts_with_all = ["check", "format", "output", "sidecar", "raw", "template", "magnet", "files"])]
+    diff: Option<Vec<String>>,
+
+    /// Hash two files in parallel and report whether their contents are
+    /// identical (exit status 0) or differ (1), like `cmp -s`
+    #[arg(long, num_args = 2, value_names = ["FILE_A", "FILE_B"],
+          conflicts_with_all = ["check", "diff", "update", "check_xattr", "expect", "watch", "string", "tee", "archive",
+          "tree", "progress", "format", "output", "sidecar", "raw", "template", "magnet", "files"])]
+    compare: Option<Vec<String>>,
+
+    /// Compare two directory trees by content, listing files only in the
+    /// left or right one and files whose contents differ
+    #[arg(long, num_args = 2, value_names = ["LEFT", "RIGHT"],
+          conflicts_with_all = ["check", "diff", "compare", "update", "check_xattr", "expect", "watch", "string", "tee",
+          "archive", "tree", "progress", "format", "output", "sidecar", "raw", "template", "magnet", "files"])]
+    compare_dirs: Option<Vec<String>>,
+
+    /// Bring MANIFEST up to date: re-hash entries whose size or mtime changed
+    /// since the last update (recorded in MANIFEST.state), add FILES not listed
+    /// yet and drop entries whose file was deleted
+    #[arg(long, value_name = "MANIFEST", conflicts_with_all = ["check", "check_xattr", "format", "output", "sidecar", "raw", "template", "magnet", "quiet"])]
+    update: Option<PathBuf>,
+
+    /// Compare files with the digests stored in their `user.shatag.<algorithm>`
+    /// extended attributes by shatag/cshatag
+    #[arg(long, conflicts_with_all = ["check", "format", "output", "sidecar", "raw", "template", "magnet"])]
+    check_xattr: bool,
+
+    /// Print OK/FAILED/missing/unreadable counters after checking (text or json)
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, require_equals = true,
+          default_missing_value = "text", requires = "check")]
+    summary: Option<SummaryFormat>,
+
+    /// Write the manifest lines of failed or missing files to FILE, for a follow-up run
+    #[arg(long, value_name = "FILE", requires = "check")]
+    failed_output: Option<PathBuf>,
+
+    /// Verify each manifest's minisign/signify signature with this public key
+    /// (a key file or the base64 key itself) before checking any file
+    #[arg(long, value_name = "KEY", requires = "check")]
+    minisign_pubkey: Option<String>,
+
+    /// Signature file or URL of the manifest (default: `<manifest>.minisig`, then `<manifest>.sig`)
+    #[arg(long, value_name = "FILE", requires = "minisign_pubkey")]
+    signature: Option<String>,
+
+    /// Algorithms to prefer when a digest length is ambiguous in check mode,
+    /// e.g. `sha3-256` for 64-digit digests (default: the SHA-1/SHA-2 family)
+    #[arg(long, value_name = "ALGORITHM", value_delimiter = ',', requires = "check", conflicts_with = "algorithm")]
+    prefer: Vec<HashAlgorithm>,
+
+    /// Write the raw digest bytes to stdout (no hex encoding, no newline)
+    #[arg(long, conflicts_with_all = ["check", "format"])]
+    raw: bool,
+
+    /// Emit uppercase hex digests
+    #[arg(long, conflicts_with = "raw")]
+    upper: bool,
+
+    /// Text encoding of the digest
+    #[arg(long, value_name = "ENCODING", default_value = "hex", conflicts_with_all = ["raw", "upper"])]
+    encoding: Encoding,
+
+    /// Split the digest into groups of N characters, separated by SEP (default: space),
+    /// e.g. `4` or `2::` for colon-separated pairs
+    #[arg(long, value_name = "N[:SEP]", value_parser = Grouping::parse,
+          conflicts_with_all = ["raw", "ni_uri", "check"])]
+    group: Option<Grouping>,
+
+    /// Mark files as read in binary mode (`hash *file`), like GNU sha256sum
+    #[arg(short, long, conflicts_with_all = ["text", "tag", "quiet", "raw", "format", "template", "magnet"])]
+    binary: bool,
+
+    /// Mark files as read in text mode (`hash  file`, the default)
+    #[arg(short, long, conflicts_with_all = ["tag", "quiet", "raw", "format", "template", "magnet"])]
+    text: bool,
+
+    /// Create BSD-style checksums (`SHA256 (file) = hash`)
+    #[arg(long, conflicts_with_all = ["raw", "quiet", "check", "format"])]
+    tag: bool,
+
+    /// Prefix digests with the algorithm name, like OCI/Docker digests (`sha256:<hex>`)
+    #[arg(long, visible_alias = "prefix-algo", conflicts_with_all = ["raw", "tag", "check", "sfv"])]
+    oci: bool,
+
+    /// Print RFC 6920 named-information URIs (`ni:///sha-256;<base64url>`)
+    #[arg(long, conflicts_with_all = ["raw", "upper", "encoding", "tag", "oci", "check", "sfv"])]
+    ni_uri: bool,
+
+    /// Print a magnet link (BitTorrent v1 info-hash, name and size) for each file
+    #[arg(long, conflicts_with_all = ["raw", "upper", "tag", "oci", "ni_uri", "check", "quiet", "format"])]
+    magnet: bool,
+
+    /// Also render each digest as a QR code in the terminal
+    #[arg(long, conflicts_with_all = ["raw", "check", "magnet", "format", "template", "zero"])]
+    qr: bool,
+
+    /// Custom output line, e.g. "{digest}  {path}  {size}  {mtime}"
+    /// (placeholders: digest, path, name, size, mtime, algorithm)
+    #[arg(long = "format", value_name = "TEMPLATE", value_parser = Template::parse,
+          conflicts_with_all = ["raw", "tag", "quiet", "check", "magnet", "format"])]
+    template: Option<Template>,
+
+    /// Include size, mtime and algorithm name alongside the digest
+    #[arg(short, long, conflicts_with_all = ["raw", "tag", "quiet", "check", "magnet", "format", "template"])]
+    long: bool,
+
+    /// End each output record with NUL instead of newline (for `xargs -0`)
+    #[arg(short, long, conflicts_with_all = ["raw", "check", "format"])]
+    zero: bool,
+
+    /// Colorize check results (OK, FAILED, MISSING)
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
+
+    /// Write results to FILE (atomically, via a temporary file renamed on success)
+    #[arg(short, long, value_name = "FILE", conflicts_with = "check")]
+    output: Option<PathBuf>,
+
+    /// Append to the --output file instead of replacing it
+    #[arg(long, requires = "output", conflicts_with_all = ["json", "csv", "tsv", "yaml", "xml", "sfv"])]
+    append: bool,
+
+    /// Also write each digest to a sidecar file next to the input (e.g. `file.iso.sha256`)
+    #[arg(long, conflicts_with_all = ["check", "format", "magnet", "template"])]
+    sidecar: bool,
+
+    /// Output results as a JSON array of {algorithm, digest, path, size, error} objects
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet"])]
+    json: bool,
+
+    /// Output one JSON object per line as each file completes (JSON Lines)
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet"])]
+    jsonl: bool,
+
+    /// Output results as CSV with a header row
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet"])]
+    csv: bool,
+
+    /// Output results as tab-separated values with a header row
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet"])]
+    tsv: bool,
+
+    /// Output results as a YAML sequence
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet"])]
+    yaml: bool,
+
+    /// Output results as an XML document
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet"])]
+    xml: bool,
+
+    /// Generate a classic SFV file (filename + CRC32, with header comments)
+    #[arg(long, group = "format", conflicts_with_all = ["check", "quiet", "algorithm"])]
+    sfv: bool,
+
+    /// List all supported hash algorithms with their sizes and security notes
+    #[arg(long = "list-algorithms")]
+    list_algorithms: bool,
+}
+
+impl Args {
+    /// The structured output format selected on the command line, if any.
+    fn output_format(&self) -> Option<Format> {
+        if self.json {
+            Some(Format::Json)
+        } else if self.jsonl {
+            Some(Format::JsonLines)
+        } else if self.csv {
+            Some(Format::Csv)
+        } else if self.tsv {
+            Some(Format::Tsv)
+        } else if self.yaml {
+            Some(Format::Yaml)
+        } else if self.xml {
+            Some(Format::Xml)
+        } else if self.sfv {
+            Some(Format::Sfv)
+        } else {
+            None
+        }
+    }
+
+    /// How inputs are read for hashing.
+    fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            input: self.input_encoding(),
+            offset: self.offset.unwrap_or(0),
+            length: self.length,
+            git_blob: self.git_blob,
+        }
+    }
+
+    /// How manifests are read in check mode, with relative paths resolved
+    /// against `base_dir` if given.
+    fn verify_options(&self, base_dir: Option<&Path>) -> VerifyOptions {
+        let mut options = VerifyOptions::default();
+        options.algorithm = self.algorithm_explicit.then_some(self.algorithm);
+        options.preferred = self.prefer.clone();
+        options.base_dir = base_dir.map(Path::to_path_buf);
+        options.glob_entries = self.glob_entries;
+        options.require_match = self.require_glob_match;
+        options.include = self.include.clone();
+        options.exclude = self.exclude.clone();
+        options
+    }
+
+    /// How directories are walked with -r, --tree and --watch.
+    fn walk_options(&self) -> WalkOptions {
+        let mut options = WalkOptions::default();
+        options.hidden = if self.hidden {
+            Hidden::Include
+        } else if self.no_hidden {
+            Hidden::Skip
+        } else {
+            Hidden::SkipSystem
+        };
+        options.max_depth = self.max_depth;
+        options.extensions = self.ext.clone();
+        options.file_type = self.file_type;
+        options.min_size = self.min_size;
+        options.max_size = self.max_size;
+        options.gitignore = self.gitignore;
+        options.prune = self.exclude.clone();
+        options
+    }
+
+    /// How digests are encoded in the output.
+    fn digest_style(&self) -> DigestStyle {
+        DigestStyle {
+            encoding: self.encoding,
+            upper: self.upper,
+            group: self.group.clone(),
+            ni_uri: self.ni_uri,
+            oci: self.oci,
+        }
+    }
+
+    /// How stdin and `-s` strings are decoded before hashing, if at all.
+    fn input_encoding(&self) -> Option<InputEncoding> {
+        if self.hex_input {
+            Some(InputEncoding::Hex)
+        } else if self.base64_input {
+            Some(InputEncoding::Base64)
+        } else {
+            None
+        }
+    }
+}
+
+/// What ends a record with `--records`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RecordDelimiter {
+    /// `\n`; a `\r` before it is part of the record
+    Newline,
+    /// `\0`, as written by `find -print0`
+    Nul,
+}
+
+impl RecordDelimiter {
+    fn byte(self) -> u8 {
+        match self {
+            RecordDelimiter::Newline => b'\n',
+            RecordDelimiter::Nul => b'\0',
+        }
+    }
+}
+
+/// How `hash_input` reads an input: the decoding applied to stdin, the
+/// byte range selected with `--offset`/`--length`, and the `--git-blob` header.
+#[derive(Debug, Clone, Copy, Default)]
+struct ReadOptions {
+    input: Option<InputEncoding>,
+    offset: u64,
+    length: Option<u64>,
+    git_blob: bool,
+}
+
+impl ReadOptions {
+    /// A hasher already fed with whatever precedes `len` bytes of content.
+    fn hasher(&self, algorithm: HashAlgorithm, len: u64) -> StreamingHasher {
+        let mut hasher = StreamingHasher::new(algorithm);
+        if self.git_blob {
+            hasher.update(format!("blob {}\0", len).as_bytes());
+        }
+        hasher
+    }
+
+    /// The digest of `data` as a whole input.
+    fn digest(&self, data: &[u8], algorithm: HashAlgorithm) -> Vec<u8> {
+        let mut hasher = self.hasher(algorithm, data.len() as u64);
+        hasher.update(data);
+        hasher.finalize().into_bytes()
+    }
+
+    /// The part of `chunk`, found at `position` in the input, that lies in the range.
+    fn select<'a>(&self, chunk: &'a [u8], position: u64) -> &'a [u8] {
+        let chunk_end = position + chunk.len() as u64;
+        let range_end = self.length.map_or(u64::MAX, |length| self.offset.saturating_add(length));
+        let start = self.offset.clamp(position, chunk_end);
+        let end = range_end.clamp(start, chunk_end);
+        &chunk[(start - position) as usize..(end - position) as usize]
+    }
+
+    /// Number of bytes of an input of `size` bytes that lie in the range.
+    fn range_len(&self, size: u64) -> u64 {
+        let available = size.saturating_sub(self.offset);
+        self.length.map_or(available, |length| length.min(available))
+    }
+
+    /// Fails if an input of `size` bytes did not contain the whole range.
+    fn check_size(&self, size: u64) -> Result<()> {
+        if self.offset > size {
+            anyhow::bail!("Offset {} is beyond the end of the input ({} bytes)", self.offset, size);
+        }
+        if let Some(length) = self.length.filter(|&length| self.range_len(size) < length) {
+            anyhow::bail!("Input ends {} bytes into the requested {}-byte range", self.range_len(size), length);
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let args = parse_args(env::args_os()).unwrap_or_else(|e| {
+        // --help and --version are "errors" that print to stdout and succeed
+        if !e.use_stderr() {
+            e.exit();
+        }
+        let _ = e.print();
+        std::process::exit(exit::USAGE)
+    });
+    match run(args, &mut io::stdout().lock()) {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(exit::for_error(&e));
+        },
+    }
+}
+
+fn parse_args<I, T>(argv: I) -> Result<Args, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = Args::command().try_get_matches_from(argv)?;
+    let mut args = Args::from_arg_matches(&matches)?;
+    args.algorithm_explicit = matches.value_source("algorithm") == Some(ValueSource::CommandLine);
+    args.algorithm = args.algorithms[0];
+    Ok(args)
+}
+
+/// Runs the command and returns its exit status.
+fn run(mut args: Args, out: &mut dyn Write) -> Result<i32> {
+    if args.sfv {
+        // SFV files always carry CRC32 checksums
+        args.algorithm = HashAlgorithm::Crc32;
+    }
+
+    if args.long {
+        args.template = Some(Template::parse(template::LONG_TEMPLATE).map_err(anyhow::Error::msg)?);
+    }
+
+    if args.algorithms.len() > 1 && let Some(option) = single_algorithm_option(&args) {
+        anyhow::bail!("{} works with a single algorithm; give only one with -a", option);
+    }
+
+    if let Some(algorithm) = args.algorithms.iter().find(|algorithm| args.ni_uri && algorithm.ni_name().is_none()) {
+        anyhow::bail!("{} has no RFC 6920 named-information identifier", algorithm.name());
+    }
+
+    if args.git_blob {
+        if !args.algorithm_explicit {
+            args.algorithm = HashAlgorithm::Sha1;
+        } else if !matches!(args.algorithm, HashAlgorithm::Sha1 | HashAlgorithm::Sha256) {
+            anyhow::bail!("Git object IDs are SHA-1 or SHA-256, not {}", args.algorithm.name());
+        }
+    }
+
+    // Only --git-blob picks an algorithm clap has not already checked
+    if !args.algorithm.is_available() {
+        anyhow::bail!("{} support is not compiled in; choose another with -a", args.algorithm.name());
+    }
+
+    if args.magnet && !HashAlgorithm::Sha1.is_available() {
+        anyhow::bail!("Magnet links need SHA-1 support, which is not compiled in");
+    }
+
+    if args.list_algorithms {
+        list_algorithms(out)?;
+        return Ok(exit::SUCCESS);
+    }
+
+    if args.tee {
+        return tee_stdin(&args, out);
+    }
+
+    if let Some(expected) = &args.expect {
+        let inputs = if args.files.is_empty() { vec!["-".to_string()] } else { expand_patterns(&args.files, &args)? };
+        return verify_expected(expected, &inputs, &args, out);
+    }
+
+    if args.check && args.watch {
+        return watch_check(&args, out);
+    }
+
+    if args.watch {
+        return watch_hash(&args, out);
+    }
+
+    if args.check {
+        return check_hashes(&args, out);
+    }
+
+    if let Some(manifests) = &args.diff {
+        let same = diff_manifests(&manifests[0], &manifests[1], &args, out)?;
+        return Ok(if same { exit::SUCCESS } else { exit::MISMATCH });
+    }
+
+    if let Some(files) = &args.compare {
+        return compare_files(&files[0], &files[1], &args, out);
+    }
+
+    if let Some(dirs) = &args.compare_dirs {
+        return compare_dirs(&dirs[0], &dirs[1], &args, out);
+    }
+
+    if let Some(manifest_path) = &args.update {
+        let inputs = expand_patterns(&args.files, &args)?;
+        update_manifest(manifest_path, &inputs, &args)?;
+        return Ok(exit::SUCCESS);
+    }
+
+    if args.check_xattr {
+        let inputs = expand_patterns(&args.files, &args)?;
+        return check_xattrs(&inputs, &args, out);
+    }
+
+    let inputs = if let Some(list) = &args.concat_list {
+        vec![list.clone()]
+    } else if args.parts {
+        args.files = expand_patterns(&args.files, &args)?;
+        args.files.sort_by(|a, b| natural_cmp(a, b));
+        match args.files.first() {
+            Some(first) => vec![first.clone()],
+            None => anyhow::bail!("No parts to hash"),
+        }
+    } else if let Some(member) = &args.member {
+        expand_patterns(&args.files, &args)?
+            .into_iter()
+            .map(|archive| format!("{}!{}", archive, member))
+            .collect()
+    } else if args.records.is_some() || (args.files.is_empty() && !args.string.is_empty()) {
+        Vec::new()
+    } else if args.files.is_empty() {
+        // Read from stdin
+        vec!["-".to_string()]
+    } else {
+        expand_patterns(&args.files, &args)?
+    };
+
+    match &args.output {
+        Some(path) => {
+            let mut file = atomic::AtomicFile::create(path, args.append)
+                .with_context(|| format!("Failed to create output file: {}", path.display()))?;
+            let code = hash_inputs(&inputs, &args, &mut file)?;
+
+            // Structured formats record failures inline, so their output is
+            // complete even when some inputs failed; text output stops at the
+            // first failure and must not replace the manifest.
+            if code == exit::SUCCESS || args.output_format().is_some() {
+                file.commit()
+                    .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+            }
+            Ok(code)
+        },
+        None => hash_inputs(&inputs, &args, out),
+    }
+}
+
+/// Copies stdin to stdout unchanged, hashing it on the way, then prints the
+/// digest to stderr, or atomically to the `--output` file.
+fn tee_stdin(args: &Args, out: &mut dyn Write) -> Result<i32> {
+    let mut output = HashingWriter::new(out, args.algorithm);
+    // Reading stdin and writing stdout fail alike for io::copy
+    io::copy(&mut io::stdin().lock(), &mut output)
+        .context("Failed to copy standard input to standard output")?;
+    output.flush().context("Failed to write to standard output")?;
+
+    let (_, digest) = output.finalize();
+    match &args.output {
+        Some(path) => {
+            let mut file = atomic::AtomicFile::create(path, args.append)
+                .with_context(|| format!("Failed to create output file: {}", path.display()))?;
+            print_digest(&mut file, digest.as_bytes(), "-", args)?;
+            file.commit()
+                .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+        },
+        None => print_digest(&mut io::stderr().lock(), digest.as_bytes(), "-", args)?,
+    }
+    Ok(exit::SUCCESS)
+}
+
+/// Hashes every input and writes the results to `out`. Returns the exit
+/// status: `MISSING` or `IO_ERROR` if some input could not be hashed.
+fn hash_inputs(inputs: &[String], args: &Args, out: &mut dyn Write) -> Result<i32> {
+    if args.dupes {
+        return find_dupes(inputs, args, out);
+    }
+
+    if args.algorithms.len() > 1 {
+        return hash_inputs_multi(inputs, args, out);
+    }
+
+    if let Some(format) = args.output_format() {
+        let mut printer = output::Printer::new(out, format, args.digest_style())
+            .context("Failed to write output")?;
+
+        let mut progress = start_progress(inputs, args)?;
+        let mut links = Links::new();
+        let mut code = exit::SUCCESS;
+        for path in inputs {
+            if args.archive {
+                match archive_entries(path, args) {
+                    Ok(entries) => {
+                        for entry in entries {
+                            printer.write_entry(&entry)
+                                .context("Failed to write output")?;
+                        }
+                        continue;
+                    },
+                    Err(error) => {
+                        code = exit::combine(code, exit::for_error(&error));
+                        if !format.reports_errors() {
+                            eprintln!("sha-calc: {}: {:#}", path, error);
+                        }
+                        printer.write_entry(&error_entry(path, &error, args))
+                            .context("Failed to write output")?;
+                        continue;
+                    },
+                }
+            }
+
+            if let Some(progress) = &mut progress {
+                progress.start_file(path).context("Failed to write progress")?;
+            }
+            let (entry, error) = hash_entry(path, args, progress.as_mut(), &mut links);
+            if let Some(progress) = &mut progress {
+                progress.finish_file();
+            }
+            if let Some(error) = error {
+                code = exit::combine(code, exit::for_error(&error));
+                if !format.reports_errors() {
+                    eprintln!("sha-calc: {}: {:#}", path, error);
+                }
+            }
+            printer.write_entry(&entry)
+                .context("Failed to write output")?;
+
+            if args.streams && entry.error.is_none() {
+                let entries = stream_entries(path, args).unwrap_or_else(|error| {
+                    code = exit::combine(code, exit::for_error(&error));
+                    if !format.reports_errors() {
+                        eprintln!("sha-calc: {}: {:#}", path, error);
+                    }
+                    vec![error_entry(path, &error, args)]
+                });
+                for entry in entries {
+                    printer.write_entry(&entry)
+                        .context("Failed to write output")?;
+                }
+            }
+        }
+        printer.finish()
+            .context("Failed to write output")?;
+        if let Some(progress) = &mut progress {
+            progress.finish().context("Failed to write progress")?;
+        }
+
+        return Ok(code);
+    }
+
+    if let Some(delimiter) = args.records {
+        return hash_records(delimiter, args, out);
+    }
+
+    for string in &args.string {
+        // Quoted, like `md5 -s`, so the name cannot be mistaken for a file
+        let name = format!("\"{}\"", string);
+        let data = match args.input_encoding() {
+            Some(input) => match decode_input(string.as_bytes(), input) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("sha-calc: {}: {}", name, e);
+                    return Ok(exit::for_error(&e));
+                },
+            },
+            None => string.as_bytes().to_vec(),
+        };
+        let digest = args.read_options().digest(&data, args.algorithm);
+        print_digest(out, &digest, &name, args)?;
+        if args.qr {
+            print_qr(out, &digest, args)?;
+        }
+    }
+
+    let mut progress = start_progress(inputs, args)?;
+    let mut links = Links::new();
+    for file_path in inputs {
+        if let Some(progress) = &mut progress {
+            progress.start_file(file_path).context("Failed to write progress")?;
+        }
+        if let Err(e) = process_file(file_path, args, out, progress.as_mut(), &mut links) {
+            eprintln!("sha-calc: {}: {}", file_path, e);
+            return Ok(exit::for_error(&e));
+        }
+        if let Some(progress) = &mut progress {
+            progress.finish_file();
+        }
+    }
+    if let Some(progress) = &mut progress {
+        progress.finish().context("Failed to write progress")?;
+    }
+
+    out.flush().context("Failed to write output")?;
+    Ok(exit::SUCCESS)
+}
+
+/// The first option given that only works with a single algorithm, such as
+/// those reading a manifest or building one combined digest.
+fn single_algorithm_option(args: &Args) -> Option<&'static str> {
+    [
+        (args.check, "--check"),
+        (args.expect.is_some(), "--expect"),
+        (args.tee, "--tee"),
+        (args.watch, "--watch"),
+        (args.diff.is_some(), "--diff"),
+        (args.compare.is_some(), "--compare"),
+        (args.compare_dirs.is_some(), "--compare-dirs"),
+        (args.update.is_some(), "--update"),
+        (args.check_xattr, "--check-xattr"),
+        (args.dupes, "--dupes"),
+        (args.tree, "--tree"),
+        (args.concat_list.is_some(), "--concat-list"),
+        (args.parts, "--parts"),
+        (args.archive, "--archive"),
+        (args.member.is_some(), "--member"),
+        (args.streams, "--streams"),
+        (args.records.is_some(), "--records"),
+        (args.git_blob, "--git-blob"),
+        (args.offset.is_some(), "--offset"),
+        (args.length.is_some(), "--length"),
+        (args.magnet, "--magnet"),
+        (args.sidecar, "--sidecar"),
+        (args.qr, "--qr"),
+        (args.raw, "--raw"),
+    ]
+    .into_iter()
+    .find(|&(given, _)| given)
+    .map(|(_, option)| option)
+}
+
+/// Hashes every input once through a `MultiHasher` for the several
+/// algorithms of `-a`, printing a digest (or record) per algorithm in the
+/// order they were given.
+fn hash_inputs_multi(inputs: &[String], args: &Args, out: &mut dyn Write) -> Result<i32> {
+    if let Some(format) = args.output_format() {
+        let mut printer = output::Printer::new(out, format, args.digest_style())
+            .context("Failed to write output")?;
+        let mut progress = start_progress(inputs, args)?;
+        let mut code = exit::SUCCESS;
+        for path in inputs {
+            if let Some(progress) = &mut progress {
+                progress.start_file(path).context("Failed to write progress")?;
+            }
+            let entries = multi_entries(path, args, progress.as_mut()).unwrap_or_else(|error| {
+                code = exit::combine(code, exit::for_error(&error));
+                if !format.reports_errors() {
+                    eprintln!("sha-calc: {}: {:#}", path, error);
+                }
+                args.algorithms.iter()
+                    .map(|&algorithm| HashRecord::failed(path.as_str(), algorithm, format!("{:#}", error)))
+                    .collect()
+            });
+            if let Some(progress) = &mut progress {
+                progress.finish_file();
+            }
+            for entry in &entries {
+                printer.write_entry(entry)
+                    .context("Failed to write output")?;
+            }
+        }
+        printer.finish()
+            .context("Failed to write output")?;
+        if let Some(progress) = &mut progress {
+            progress.finish().context("Failed to write progress")?;
+        }
+        return Ok(code);
+    }
+
+    for string in &args.string {
+        let name = format!("\"{}\"", string);
+        let data = match args.input_encoding() {
+            Some(input) => match decode_input(string.as_bytes(), input) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("sha-calc: {}: {}", name, e);
+                    return Ok(exit::for_error(&e));
+                },
+            },
+            None => string.as_bytes().to_vec(),
+        };
+        let mut hasher = MultiHasher::new(&args.algorithms);
+        hasher.update(&data);
+        for digest in hasher.finalize() {
+            print_multi_entry(out, &HashRecord::new(name.as_str(), digest, data.len() as u64), args)?;
+        }
+    }
+
+    let mut progress = start_progress(inputs, args)?;
+    for path in inputs {
+        if let Some(progress) = &mut progress {
+            progress.start_file(path).context("Failed to write progress")?;
+        }
+        let entries = match multi_entries(path, args, progress.as_mut()) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("sha-calc: {}: {}", path, e);
+                return Ok(exit::for_error(&e));
+            },
+        };
+        for entry in &entries {
+            print_multi_entry(out, entry, args)?;
+        }
+        if let Some(progress) = &mut progress {
+            progress.finish_file();
+        }
+    }
+    if let Some(progress) = &mut progress {
+        progress.finish().context("Failed to write progress")?;
+    }
+
+    out.flush().context("Failed to write output")?;
+    Ok(exit::SUCCESS)
+}
+
+/// One record per algorithm of `-a` for the file, stdin or download at
+/// `file_path`, all from a single read of it.
+fn multi_entries(file_path: &str, args: &Args, progress: Option<&mut ProgressReport>) -> Result<Vec<HashRecord>> {
+    let start = std::time::Instant::now();
+    let mut sink = MultiSink { hasher: MultiHasher::new(&args.algorithms), progress: ProgressSink { path: file_path, progress } };
+    let size = copy_input(file_path, args.input_encoding(), &mut sink)?;
+    let mtime = (file_path != "-").then(|| std::fs::metadata(file_path).and_then(|meta| meta.modified()).ok()).flatten();
+    Ok(sink.hasher.finalize().into_iter()
+        .map(|digest| {
+            let mut entry = HashRecord::new(file_path, digest, size);
+            entry.duration = Some(start.elapsed());
+            entry.mtime = mtime;
+            entry
+        })
+        .collect())
+}
+
+/// Prints a record of `multi_entries` as a text line, through `--format`
+/// if given.
+fn print_multi_entry(out: &mut dyn Write, entry: &HashRecord, args: &Args) -> Result<()> {
+    let Some(digest) = &entry.digest else { return Ok(()) };
+    match &args.template {
+        Some(template) => print_record(out, &template.render(entry, &args.digest_style()), args),
+        None => print_digest_as(out, entry.algorithm, digest.as_bytes(), &entry.path, args),
+    }
+}
+
+/// Prints a digest for each record of stdin, in a single pass. A final
+/// record without a delimiter still counts; an empty input has no records.
+fn hash_records(delimiter: RecordDelimiter, args: &Args, out: &mut dyn Write) -> Result<i32> {
+    let mut input = io::stdin().lock();
+    let mut record = Vec::new();
+    let mut number = 0;
+    loop {
+        record.clear();
+        if input.read_until(delimiter.byte(), &mut record).context("Failed to read from input")? == 0 {
+            break;
+        }
+        if record.last() == Some(&delimiter.byte()) {
+            record.pop();
+        }
+        number += 1;
+        let digest = args.read_options().digest(&record, args.algorithm);
+        print_digest(out, &digest, &format!("-:{}", number), args)?;
+        if args.qr {
+            print_qr(out, &digest, args)?;
+        }
+    }
+    out.flush().context("Failed to write output")?;
+    Ok(exit::SUCCESS)
+}
+
+/// Prints the groups of identical files among `inputs` as manifest lines,
+/// the groups wasting the most space first, then a summary on stderr. Only
+/// files that share their size with another one are hashed; empty files are
+/// left out.
+fn find_dupes(inputs: &[String], args: &Args, out: &mut dyn Write) -> Result<i32> {
+    let mut code = exit::SUCCESS;
+    let mut by_size: std::collections::BTreeMap<u64, Vec<&String>> = std::collections::BTreeMap::new();
+    for path in inputs {
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() > 0 => by_size.entry(meta.len()).or_default().push(path),
+            Ok(_) => {},
+            Err(e) => code = exit::combine(code, report_errors(vec![(path, e.into())])),
+        }
+    }
+
+    let mut groups = Vec::new();
+    let mut links = Links::new();
+    for (&size, paths) in by_size.iter().filter(|(_, paths)| paths.len() > 1) {
+        let mut by_digest: std::collections::BTreeMap<Vec<u8>, Vec<&String>> = std::collections::BTreeMap::new();
+        for path in paths {
+            match hash_linked(path, args, ReadOptions::default(), None, &mut links) {
+                Ok((digest, _)) => by_digest.entry(digest).or_default().push(path),
+                Err(e) => code = exit::combine(code, report_errors(vec![(path, e)])),
+            }
+        }
+        groups.extend(by_digest.into_iter().filter(|(_, paths)| paths.len() > 1).map(|(digest, paths)| (size, digest, paths)));
+    }
+    groups.sort_by_key(|(size, _, paths)| std::cmp::Reverse(size * (paths.len() as u64 - 1)));
+
+    let (mut redundant, mut reclaimable) = (0, 0);
+    for (i, (size, digest, paths)) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(out).context("Failed to write output")?;
+        }
+        for path in paths {
+            print_digest(out, digest, path, args)?;
+        }
+        redundant += paths.len() - 1;
+        reclaimable += size * (paths.len() as u64 - 1);
+    }
+    out.flush().context("Failed to write output")?;
+
+    eprintln!(
+        "sha-calc: {} groups of duplicates, {} redundant files, {} reclaimable",
+        groups.len(), redundant, output::format_size(reclaimable)
+    );
+    Ok(code)
+}
+
+/// Opens the `--progress` reporter, if requested, with `inputs` as its total.
+fn start_progress(inputs: &[String], args: &Args) -> Result<Option<ProgressReport>> {
+    let Some(_) = args.progress else {
+        return Ok(None);
+    };
+    let mut progress = output::Progress::new(open_progress_output(args)?);
+    let options = args.read_options();
+    let mut seen = std::collections::HashSet::new();
+    // The single --parts input stands for all of them
+    let sized = if args.parts { &args.files } else { inputs };
+    let bytes = sized.iter()
+        .filter(|path| !args.one_hash_per_inode || file_id(path).is_none_or(|id| seen.insert(id)))
+        .map(|path| options.range_len(input_size(path)))
+        .sum();
+    progress.add_total(inputs.len() as u64, bytes);
+    Ok(Some(progress))
+}
+
+/// Size of a local file or block device, or 0 if unknown (stdin, downloads,
+/// directories, unreadable files).
+fn input_size(path: &str) -> u64 {
+    if path == "-" || is_remote(path) || Path::new(path).is_dir() || blockdev::is_stream(Path::new(path)) {
+        return 0;
+    }
+    File::open(path).and_then(|file| blockdev::size(&file)).unwrap_or(0)
+}
+
+fn expand_patterns(patterns: &[String], args: &Args) -> Result<Vec<String>> {
+    let mut all_files = Vec::new();
+
+    for pattern in patterns {
+        if manifest::is_glob(pattern) && !is_remote(pattern) {
+            // Handle glob pattern
+            let paths = manifest::expand_glob(pattern)
+                .with_context(|| format!("Failed to expand glob pattern: {}", pattern))?;
+            all_files.extend(paths.iter().map(|path| path.display().to_string()));
+        } else {
+            // Regular file
+            all_files.push(pattern.clone());
+        }
+    }
+
+    if args.recursive || args.watch {
+        all_files = expand_directories(all_files, args)?;
+    } else {
+        // With --archive and --tree, the filters select members and files instead
+        all_files.retain(|path| (args.archive || args.tree || is_selected(path, args)) && has_selected_size(path, args));
+    }
+
+    walk::sort_paths(&mut all_files);
+    Ok(all_files)
+}
+
+/// Replaces each directory among `paths` with the regular files below it.
+/// `--include`/`--exclude` match paths relative to the directory walked,
+/// and directories matching an exclude pattern (`node_modules/**`) are not
+/// entered at all.
+fn expand_directories(paths: Vec<String>, args: &Args) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path == "-" || is_remote(&path) || !Path::new(&path).is_dir() {
+            if is_selected(&path, args) && has_selected_size(&path, args) {
+                files.push(path);
+            }
+            continue;
+        }
+
+        let root = Path::new(&path);
+        let walked = walk::walk_files(root, &args.walk_options())
+            .with_context(|| format!("Failed to read directory: {}", path))?;
+        for file in walked {
+            let relative = file.strip_prefix(root).unwrap_or(&file).to_string_lossy();
+            if is_selected(&relative, args) {
+                files.push(file.display().to_string());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Whether an input given on the command line is within `--min-size` and
+/// `--max-size`. Stdin, downloads, directories, FIFOs and other streams, and
+/// files whose size cannot be read are kept, so that errors are still reported.
+fn has_selected_size(path: &str, args: &Args) -> bool {
+    if (args.min_size.is_none() && args.max_size.is_none()) || path == "-" || is_remote(path) || Path::new(path).is_dir() {
+        return true;
+    }
+    if blockdev::is_stream(Path::new(path)) {
+        return true;
+    }
+    File::open(path).and_then(|file| blockdev::size(&file))
+        .map_or(true, |size| args.walk_options().is_size_selected(size))
+}
+
+/// Parses a byte count or offset: decimal, `0x` hexadecimal, or decimal
+/// with a K, M, G or T (binary) suffix.
+fn parse_size(text: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid size '{}' (expected e.g. 4096, 0x1000 or 4K)", text);
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return u64::from_str_radix(hex, 16).map_err(|_| invalid());
+    }
+
+    let (number, shift) = match text.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&text[..i], 10),
+        Some((i, 'm' | 'M')) => (&text[..i], 20),
+        Some((i, 'g' | 'G')) => (&text[..i], 30),
+        Some((i, 't' | 'T')) => (&text[..i], 40),
+        _ => (text, 0),
+    };
+    number.parse::<u64>().ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(invalid)
+}
+
+fn parse_pattern(pattern: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(pattern).map_err(|e| format!("invalid glob pattern: {}", e))
+}
+
+fn list_algorithms(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "Supported hash algorithms:")?;
+    for alg in HashAlgorithm::ALL.into_iter().filter(HashAlgorithm::is_available) {
+        let mut line = format!(
+            "- {:<9} {:<12} {:>3}-bit digest, {:>3}-byte blocks",
+            alg.to_string(), alg.name(), alg.digest_size() * 8, alg.block_size(),
+        );
+        if let Some(note) = alg.security_note() {
+            line.push_str(&format!("  ({})", note));
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn process_file(
+    file_path: &str,
+    args: &Args,
+    out: &mut dyn Write,
+    progress: Option<&mut ProgressReport>,
+    links: &mut Links,
+) -> Result<()> {
+    if args.magnet {
+        let mut link = magnet::MagnetLink::new();
+        copy_input(file_path, args.input_encoding(), &mut link)?;
+        let name = (file_path != "-")
+            .then(|| Path::new(file_path).file_name())
+            .flatten()
+            .map(|name| name.to_string_lossy());
+        return print_record(out, &link.finish(name.as_deref()), args);
+    }
+
+    if args.archive {
+        return print_entries(out, archive_entries(file_path, args)?, args);
+    }
+
+    if let Some(template) = &args.template {
+        let (entry, error) = hash_entry(file_path, args, progress, links);
+        if let Some(error) = error {
+            return Err(error);
+        }
+        print_record(out, &template.render(&entry, &args.digest_style()), args)?;
+    } else {
+        let (digest, _) = hash_path(file_path, args, progress, links)?;
+        print_digest(out, &digest, file_path, args)?;
+
+        if args.qr {
+            print_qr(out, &digest, args)?;
+        }
+
+        if args.sidecar {
+            write_sidecar(file_path, &digest, args)?;
+        }
+    }
+
+    if args.streams {
+        print_entries(out, stream_entries(file_path, args)?, args)?;
+    }
+
+    Ok(())
+}
+
+/// Prints records made by `archive_entries` or `stream_entries`.
+fn print_entries(out: &mut dyn Write, entries: Vec<HashRecord>, args: &Args) -> Result<()> {
+    for entry in entries {
+        let Some(digest) = &entry.digest else { continue };
+        match &args.template {
+            Some(template) => print_record(out, &template.render(&entry, &args.digest_style()), args)?,
+            None => print_digest(out, digest.as_bytes(), &entry.path, args)?,
+        }
+        if args.qr {
+            print_qr(out, digest.as_bytes(), args)?;
+        }
+    }
+    Ok(())
+}
+
+/// The output record of an input that could not be hashed.
+fn error_entry(path: &str, error: &anyhow::Error, args: &Args) -> HashRecord {
+    HashRecord::failed(path, args.algorithm, format!("{:#}", error))
+}
+
+/// Wraps digest bytes computed with the algorithm of the command line.
+fn to_digest(digest: Vec<u8>, args: &Args) -> Digest {
+    Digest::new(args.algorithm, digest).expect("digest length matches the algorithm")
+}
+
+/// Writes `<file>.<algorithm>` next to `file_path`, referring to the file by
+/// its base name so the sidecar can be checked from its own directory.
+fn write_sidecar(file_path: &str, digest: &[u8], args: &Args) -> Result<()> {
+    let path = Path::new(file_path);
+    let name = path.file_name()
+        .filter(|_| file_path != "-" && !is_remote(file_path))
+        .with_context(|| format!("Cannot write a sidecar file for {}", file_path))?;
+
+    let mut sidecar_name = name.to_os_string();
+    sidecar_name.push(".");
+    sidecar_name.push(output::algorithm_name(args.algorithm));
+    let sidecar_path = path.with_file_name(sidecar_name);
+
+    let mut sidecar = atomic::AtomicFile::create(&sidecar_path, false)
+        .with_context(|| format!("Failed to create sidecar file: {}", sidecar_path.display()))?;
+    print_digest(&mut sidecar, digest, &name.to_string_lossy(), args)?;
+    sidecar.commit()
+        .with_context(|| format!("Failed to write sidecar file: {}", sidecar_path.display()))
+}
+
+/// Hashes the regular files inside a tar or `.tar.gz` archive into output
+/// records named by their path in the archive, reading it as a stream.
+/// `--include`/`--exclude` apply to those paths.
+fn archive_entries(file_path: &str, args: &Args) -> Result<Vec<HashRecord>> {
+    let mut archive = open_archive(file_path)?;
+    let options = args.read_options();
+
+    let mut entries = Vec::new();
+    while let Some(member) = archive.next_member()? {
+        if !is_selected(&member.path, args) {
+            continue;
+        }
+        let mut writer = HashingWriter::with_hasher(io::sink(), options.hasher(args.algorithm, member.size));
+        io::copy(&mut archive, &mut writer).context("Failed to read from input")?;
+        let mut entry = HashRecord::new(member.path, to_digest(writer.finalize().1.into_bytes(), args), member.size);
+        entry.mtime = Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(member.mtime));
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Hashes the extended attribute values or alternate data streams of a file
+/// into output records named `<file>:<name>`.
+fn stream_entries(file_path: &str, args: &Args) -> Result<Vec<HashRecord>> {
+    if file_path == "-" || is_remote(file_path) {
+        return Ok(Vec::new());
+    }
+    let streams = streams::read_all(Path::new(file_path))
+        .context("Failed to read extended attributes or streams")?;
+
+    Ok(streams.into_iter()
+        .map(|(name, data)| {
+            let digest = ReadOptions::default().digest(&data, args.algorithm);
+            HashRecord::new(format!("{}:{}", file_path, name), to_digest(digest, args), data.len() as u64)
+        })
+        .collect())
+}
+
+/// Hashes a file into an output record, also returning the error that
+/// prevented hashing, if any.
+fn hash_entry(
+    file_path: &str,
+    args: &Args,
+    progress: Option<&mut ProgressReport>,
+    links: &mut Links,
+) -> (HashRecord, Option<anyhow::Error>) {
+    let start = std::time::Instant::now();
+    match hash_path(file_path, args, progress, links) {
+        Ok((digest, size)) => {
+            let mut entry = HashRecord::new(file_path, to_digest(digest, args), size);
+            entry.duration = Some(start.elapsed());
+            if file_path != "-" {
+                entry.mtime = std::fs::metadata(file_path).and_then(|meta| meta.modified()).ok();
+            }
+            (entry, None)
+        },
+        Err(e) => (error_entry(file_path, &e, args), Some(e)),
+    }
+}
+
+/// Hashes an input as the command line asks: a whole directory with
+/// `--tree`, the files of a `--concat-list` or all the `--parts`, otherwise
+/// through `hash_input`.
+fn hash_path(
+    file_path: &str,
+    args: &Args,
+    progress: Option<&mut ProgressReport>,
+    links: &mut Links,
+) -> Result<(Vec<u8>, u64)> {
+    if args.tree {
+        tree_digest(file_path, args, progress, links)
+    } else if args.concat_list.is_some() {
+        concat_list_digest(file_path, args, progress)
+    } else if args.parts {
+        concat_digest(&args.files, args.algorithm, progress)
+    } else {
+        hash_linked(file_path, args, args.read_options(), progress, links)
+    }
+}
+
+/// Hashes the files listed in `list_path` as one stream, in the listed
+/// order. Each line is a path, relative to the list's directory; blank lines
+/// and `#` comments are skipped.
+fn concat_list_digest(list_path: &str, args: &Args, progress: Option<&mut ProgressReport>) -> Result<(Vec<u8>, u64)> {
+    let text = String::from_utf8(read_source(list_path)?)
+        .with_context(|| format!("Failed to read file list: {}: not valid UTF-8", list_path))?;
+    let base_dir = Path::new(list_path).parent().filter(|_| list_path != "-" && !is_remote(list_path));
+
+    let parts: Vec<String> = text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| match base_dir {
+            Some(dir) if Path::new(line).is_relative() => dir.join(line).display().to_string(),
+            _ => line.to_string(),
+        })
+        .collect();
+    if parts.is_empty() {
+        anyhow::bail!("No files listed in {}", list_path);
+    }
+    concat_digest(&parts, args.algorithm, progress)
+}
+
+/// Hashes local files one after the other, as if they were a single file.
+fn concat_digest(parts: &[String], algorithm: HashAlgorithm, progress: Option<&mut ProgressReport>) -> Result<(Vec<u8>, u64)> {
+    let mut writer = HashingWriter::new(ProgressSink { path: "", progress }, algorithm);
+    let mut total = 0;
+    for part in parts {
+        let mut file = File::open(part)
+            .with_context(|| format!("Failed to open file: {}", part))?;
+        writer.get_mut().path = part;
+        total += hash_reader(&mut file, &mut writer)?;
+    }
+    Ok((writer.finalize().1.into_bytes(), total))
+}
+
+/// `hash_input`, except that with `--one-hash-per-inode` a file with several
+/// hardlinks is read only the first time one of its names comes up.
+fn hash_linked(
+    file_path: &str,
+    args: &Args,
+    options: ReadOptions,
+    progress: Option<&mut ProgressReport>,
+    links: &mut Links,
+) -> Result<(Vec<u8>, u64)> {
+    let id = if args.one_hash_per_inode { file_id(file_path) } else { None };
+    if let Some(hashed) = id.and_then(|id| links.get(&id)) {
+        return Ok(hashed.clone());
+    }
+
+    let hashed = hash_input(file_path, args.algorithm, options, progress)?;
+    if let Some(id) = id {
+        links.insert(id, hashed.clone());
+    }
+    Ok(hashed)
+}
+
+/// The device and inode of a local file with more than one hardlink.
+#[cfg(unix)]
+fn file_id(path: &str) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    if path == "-" || is_remote(path) {
+        return None;
+    }
+    std::fs::metadata(path).ok()
+        .filter(|meta| meta.is_file() && meta.nlink() > 1)
+        .map(|meta| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &str) -> Option<(u64, u64)> {
+    None
+}
+
+/// The `--tree` digest of the directory `dir`, returned with the total size
+/// of its files. It hashes one record per regular file selected below `dir`,
+/// in byte order of their `/`-separated relative paths: `<mode> <size>
+/// <path>\0` followed by the raw digest of the file. The mode is `755` for
+/// executable files and `644` otherwise, so that umasks do not matter;
+/// directories themselves, timestamps and owners are not part of the digest.
+fn tree_digest(
+    dir: &str,
+    args: &Args,
+    mut progress: Option<&mut ProgressReport>,
+    links: &mut Links,
+) -> Result<(Vec<u8>, u64)> {
+    let root = Path::new(dir);
+    if !root.is_dir() {
+        anyhow::bail!("Not a directory: {}", dir);
+    }
+
+    let walked = walk::walk_files(root, &args.walk_options())
+        .with_context(|| format!("Failed to read directory: {}", dir))?;
+    let mut files: Vec<(String, PathBuf)> = walked.into_iter()
+        .map(|file| (relative_path(root, &file), file))
+        .filter(|(relative, _)| is_selected(relative, args))
+        .collect();
+    // String order is byte order, unlike the per-directory order of the walk
+    files.sort();
+
+    let mut hasher = StreamingHasher::new(args.algorithm);
+    let mut total = 0;
+    for (relative, file) in files {
+        let path = file.to_string_lossy();
+        let (digest, size) = hash_linked(&path, args, ReadOptions::default(), progress.as_deref_mut(), links)?;
+        let mode = if is_executable(&file) { "755" } else { "644" };
+        hasher.update(format!("{} {} {}\0", mode, size, relative).as_bytes());
+        hasher.update(&digest);
+        total += size;
+    }
+    Ok((hasher.finalize().into_bytes(), total))
+}
+
+/// The path of `file` relative to `root`, with `/` separators on every platform.
+fn relative_path(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root).unwrap_or(file).components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Orders names with runs of digits compared by value, so that `disk.part2`
+/// comes before `disk.part10`; other bytes compare as they are.
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    loop {
+        let (Some(&x), Some(&y)) = (a.first(), b.first()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let digits = |s: &[u8]| s.iter().take_while(|c| c.is_ascii_digit()).count();
+            let (run_a, rest_a) = a.split_at(digits(a));
+            let (run_b, rest_b) = b.split_at(digits(b));
+            let zeros = |run: &[u8]| run.iter().take_while(|&&c| c == b'0').count();
+            let (value_a, value_b) = (&run_a[zeros(run_a)..], &run_b[zeros(run_b)..]);
+            // Equal values with more leading zeros sort later, to stay total
+            let order = value_a.len().cmp(&value_b.len())
+                .then_with(|| value_a.cmp(value_b))
+                .then_with(|| run_a.len().cmp(&run_b.len()));
+            if order.is_ne() {
+                return order;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else if x != y {
+            return x.cmp(&y);
+        } else {
+            (a, b) = (&a[1..], &b[1..]);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+/// Hashes a file or block device, stdin when `file_path` is `-`, or a
+/// download streamed from an HTTPS URL, returning the digest and the number
+/// of bytes hashed. Files are read in chunks, each reported to `progress`,
+/// and only the range selected in `options` is hashed.
+fn hash_input(
+    file_path: &str,
+    algorithm: HashAlgorithm,
+    options: ReadOptions,
+    progress: Option<&mut ProgressReport>,
+) -> Result<(Vec<u8>, u64)> {
+    if is_remote(file_path) && options.git_blob {
+        // The blob header needs the size before any content
+        let data = read_input(file_path, None)?;
+        options.check_size(data.len() as u64)?;
+        let data = options.select(&data, 0);
+        return Ok((options.digest(data, algorithm), data.len() as u64));
+    }
+
+    if is_remote(file_path) {
+        let mut hasher = StreamingHasher::new(algorithm);
+        let mut position = 0;
+        stream_remote(file_path, &mut |chunk| {
+            hasher.update(options.select(chunk, position));
+            position += chunk.len() as u64;
+        })?;
+        options.check_size(position)?;
+        return Ok((hasher.finalize().into_bytes(), options.range_len(position)));
+    }
+
+    if let Some((archive, member)) = archive_member(file_path) {
+        return hash_member(archive, member, &options, algorithm);
+    }
+
+    if file_path != "-" && blockdev::is_stream(Path::new(file_path)) {
+        let mut stream = blockdev::open_stream(Path::new(file_path))
+            .with_context(|| format!("Failed to open file: {}", file_path))?;
+        if options.git_blob {
+            // The blob header needs the size before any content
+            let mut data = Vec::new();
+            stream.read_to_end(&mut data).context("Failed to read from input")?;
+            options.check_size(data.len() as u64)?;
+            let data = options.select(&data, 0);
+            return Ok((options.digest(data, algorithm), data.len() as u64));
+        }
+
+        let skipped = io::copy(&mut stream.by_ref().take(options.offset), &mut io::sink())
+            .context("Failed to read from input")?;
+        let mut writer = HashingWriter::new(ProgressSink { path: file_path, progress }, algorithm);
+        let size = hash_reader(&mut stream.take(options.length.unwrap_or(u64::MAX)), &mut writer)?;
+        options.check_size(skipped + size)?;
+        return Ok((writer.finalize().1.into_bytes(), size));
+    }
+
+    if file_path != "-" {
+        let mut file = File::open(file_path)
+            .with_context(|| format!("Failed to open file: {}", file_path))?;
+        let mut len = 0;
+        if options.offset > 0 || options.length.is_some() || options.git_blob {
+            let size = blockdev::size(&file)
+                .with_context(|| format!("Failed to read size of {}", file_path))?;
+            options.check_size(size)?;
+            file.seek(SeekFrom::Start(options.offset))
+                .context("Failed to seek in input")?;
+            len = options.range_len(size);
+        }
+        let mut writer = HashingWriter::with_hasher(ProgressSink { path: file_path, progress }, options.hasher(algorithm, len));
+        let size = hash_reader(&mut file.take(options.length.unwrap_or(u64::MAX)), &mut writer)?;
+        return Ok((writer.finalize().1.into_bytes(), size));
+    }
+
+    let data = read_input(file_path, options.input)?;
+    options.check_size(data.len() as u64)?;
+    let data = options.select(&data, 0);
+    Ok((options.digest(data, algorithm), data.len() as u64))
+}
+
+/// Splits `archive!member` into the local archive and the path inside it.
+/// Paths that exist as given, `!` and all, are left alone.
+fn archive_member(path: &str) -> Option<(&str, &str)> {
+    if path == "-" || is_remote(path) || Path::new(path).exists() {
+        return None;
+    }
+    path.match_indices('!')
+        .map(|(i, _)| (&path[..i], &path[i + 1..]))
+        .find(|(archive, member)| !member.is_empty() && Path::new(archive).is_file())
+}
+
+/// Hashes the regular file `member` inside a tar or `.tar.gz` archive, read
+/// as a stream; the last copy wins if the archive holds several.
+fn hash_member(archive: &str, member: &str, options: &ReadOptions, algorithm: HashAlgorithm) -> Result<(Vec<u8>, u64)> {
+    let mut reader = open_archive(archive)?;
+    let mut found = None;
+    while let Some(stored) = reader.next_member()? {
+        if !stored.is(member) {
+            continue;
+        }
+        options.check_size(stored.size)?;
+        io::copy(&mut (&mut reader).take(options.offset), &mut io::sink())
+            .context("Failed to read from input")?;
+        let len = options.range_len(stored.size);
+        let mut writer = HashingWriter::with_hasher(ProgressSink { path: archive, progress: None }, options.hasher(algorithm, len));
+        let size = hash_reader(&mut (&mut reader).take(len), &mut writer)?;
+        found = Some((writer.finalize().1.into_bytes(), size));
+    }
+    found.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No file {} in archive {}", member, archive)).into())
+}
+
+/// Opens a tar or `.tar.gz` archive for reading front to back. Zip archives
+/// are recognized and refused rather than reported as corrupt tar files.
+fn open_archive(file_path: &str) -> Result<tar::Archive<Box<dyn Read>>> {
+    let mut input = open_input(file_path)?;
+    let mut head = Vec::new();
+    input.by_ref().take(ARCHIVE_HEAD_SIZE as u64).read_to_end(&mut head)
+        .context("Failed to read from input")?;
+
+    if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        anyhow::bail!("Zip archives are not supported, only tar and .tar.gz");
+    }
+    let gzip = inflate::is_gzip(&head);
+    let input = io::Cursor::new(head).chain(input);
+    Ok(tar::Archive::new(if gzip { Box::new(inflate::GzDecoder::new(input)) } else { Box::new(input) }))
+}
+
+/// Bytes read ahead to tell gzip, zip and plain tar archives apart.
+const ARCHIVE_HEAD_SIZE: usize = 512;
+
+/// Opens a file, stdin or download for reading as a stream. Downloads are
+/// fed through a pipe by a thread; a failed transfer makes the last read fail.
+fn open_input(file_path: &str) -> Result<Box<dyn Read>> {
+    if is_remote(file_path) {
+        let (reader, mut writer) = io::pipe().context("Failed to create pipe")?;
+        let url = file_path.to_string();
+        let transfer = std::thread::spawn(move || {
+            let mut result = Ok(());
+            stream_remote(&url, &mut |chunk| {
+                if result.is_ok() {
+                    result = writer.write_all(chunk);
+                }
+            }).map_err(|e| io::Error::other(format!("{:#}", e)))?;
+            result
+        });
+        return Ok(Box::new(Download { pipe: reader, transfer: Some(transfer) }));
+    }
+    if file_path == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = File::open(file_path)
+        .with_context(|| format!("Failed to open file: {}", file_path))?;
+    Ok(Box::new(BufReader::with_capacity(BUFFER_SIZE, file)))
+}
+
+/// The reading end of a download made by another thread.
+struct Download {
+    pipe: io::PipeReader,
+    transfer: Option<std::thread::JoinHandle<io::Result<()>>>,
+}
+
+impl Read for Download {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.pipe.read(buf)?;
+        if len == 0 && !buf.is_empty() && let Some(transfer) = self.transfer.take() {
+            transfer.join().map_err(|_| io::Error::other("Download thread panicked"))??;
+        }
+        Ok(len)
+    }
+}
+
+/// Reads a whole file, stdin when `file_path` is `-`, or an HTTPS URL. Stdin
+/// is decoded with `input`, if given; files are always hashed as they are.
+fn read_input(file_path: &str, input: Option<InputEncoding>) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if remote::is_url(file_path) {
+        data = remote::fetch(file_path)?;
+    } else if is_remote(file_path) {
+        stream_remote(file_path, &mut |chunk| data.extend_from_slice(chunk))?;
+    } else if file_path == "-" {
+        io::stdin().lock().read_to_end(&mut data)
+            .context("Failed to read from input")?;
+        if let Some(input) = input {
+            data = decode_input(&data, input)?;
+        }
+    } else {
+        let file = File::open(file_path)
+            .with_context(|| format!("Failed to open file: {}", file_path))?;
+        BufReader::new(file).read_to_end(&mut data)
+            .context("Failed to read from input")?;
+    }
+
+    Ok(data)
+}
+
+/// Writes a file, stdin or download to `sink` in chunks, like `read_input`
+/// without holding it in memory (except stdin decoded with `input`).
+fn copy_input(file_path: &str, input: Option<InputEncoding>, sink: &mut dyn Write) -> Result<u64> {
+    if is_remote(file_path) {
+        let mut result = Ok(());
+        let size = stream_remote(file_path, &mut |chunk| {
+            if result.is_ok() {
+                result = sink.write_all(chunk);
+            }
+        })?;
+        result.context("Failed to write output")?;
+        return Ok(size);
+    }
+    if file_path == "-" && input.is_some() {
+        let data = read_input(file_path, input)?;
+        sink.write_all(&data).context("Failed to write output")?;
+        return Ok(data.len() as u64);
+    }
+
+    let mut reader: Box<dyn Read> = if file_path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?)
+    };
+    io::copy(&mut BufReader::with_capacity(BUFFER_SIZE, &mut reader), sink)
+        .context("Failed to read from input")
+}
+
+/// Whether `path` names a download (an HTTPS URL, or an object storage URL
+/// with the `cloud` feature) rather than a local file.
+fn is_remote(path: &str) -> bool {
+    #[cfg(feature = "cloud")]
+    if cloud::is_object_url(path) {
+        return true;
+    }
+    remote::is_url(path)
+}
+
+/// Streams a download to `consume`, returning its size.
+fn stream_remote(url: &str, consume: &mut dyn FnMut(&[u8])) -> Result<u64> {
+    #[cfg(feature = "cloud")]
+    if cloud::is_object_url(url) {
+        return cloud::stream(url, consume);
+    }
+    remote::stream(url, consume)
+}
+
+fn decode_input(text: &[u8], input: InputEncoding) -> Result<Vec<u8>> {
+    encoding::decode_input(text, input)
+        .with_context(|| format!("Input is not valid {}", input.name()))
+}
+
+fn encode_digest(digest: &[u8], args: &Args) -> String {
+    args.digest_style().encode(args.algorithm, digest)
+}
+
+fn print_digest(out: &mut dyn Write, digest: &[u8], name: &str, args: &Args) -> Result<()> {
+    print_digest_as(out, args.algorithm, digest, name, args)
+}
+
+/// Like `print_digest`, for a digest of `algorithm` rather than of the
+/// algorithm of the command line.
+fn print_digest_as(out: &mut dyn Write, algorithm: HashAlgorithm, digest: &[u8], name: &str, args: &Args) -> Result<()> {
+    if args.raw {
+        out.write_all(digest)
+            .and_then(|_| out.flush())
+            .context("Failed to write output")?;
+        return Ok(());
+    }
+
+    let hash = args.digest_style().encode(algorithm, digest);
+    if args.quiet {
+        return print_record(out, &hash, args);
+    }
+
+    // Like coreutils, names with newlines or backslashes are escaped and the
+    // line is flagged with a leading backslash; NUL-terminated output is verbatim.
+    let (prefix, name) = match manifest::escape_filename(name).filter(|_| !args.zero) {
+        Some(escaped) => ("\\", escaped),
+        None => ("", name.to_string()),
+    };
+
+    if args.tag {
+        print_record(out, &format!("{}{} ({}) = {}", prefix, algorithm.tag(), name, hash), args)
+    } else {
+        let marker = if args.binary { '*' } else { ' ' };
+        print_record(out, &format!("{}{} {}{}", prefix, hash, marker, name), args)
+    }
+}
+
+/// Renders the encoded digest as a QR code in the terminal.
+fn print_qr(out: &mut dyn Write, digest: &[u8], args: &Args) -> Result<()> {
+    let hash = encode_digest(digest, args);
+    let code = qr::QrCode::encode(hash.as_bytes())
+        .context("Digest is too long for a QR code")?;
+    out.write_all(code.render().as_bytes())
+        .context("Failed to write output")
+}
+
+/// Prints one output record, terminated by NUL with `--zero` or by a newline otherwise.
+fn print_record(out: &mut dyn Write, record: &str, args: &Args) -> Result<()> {
+    let terminator = if args.zero { '\0' } else { '\n' };
+    write!(out, "{}{}", record, terminator)
+        .context("Failed to write output")
+}
+
+/// The end of the pipe in `hash_reader`: counts the bytes hashed from
+/// `path` into `--progress`, if enabled, and discards them.
+struct ProgressSink<'a> {
+    path: &'a str,
+    progress: Option<&'a mut ProgressReport>,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+impl Write for ProgressSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(progress) = &mut self.progress {
+            progress.read(self.path, buf.len() as u64)
+                .map_err(|e| io::Error::new(e.kind(), format!("Failed to write progress: {}", e)))?;
+        }
+        Ok(buf.len())
+    }
 
-    if args.list_algorithms {
-        list_algorithms();
-        return Ok(());
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The sink of `multi_entries`: feeds every hasher of `-a` and counts the
+/// bytes into `--progress`.
+struct MultiSink<'a> {
+    hasher: MultiHasher,
+    progress: ProgressSink<'a>,
+}
+
+impl Write for MultiSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hasher.update(buf);
+        self.progress.write(buf)
     }
 
-    if args.check {
-        return check_hashes(&args);
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
+}
+
+/// Copies `reader` through the hashing `writer` in chunks of the same size
+/// as the library's `hash_reader`, and returns the number of bytes read.
+fn hash_reader(reader: &mut dyn Read, writer: &mut HashingWriter<ProgressSink>) -> Result<u64> {
+    io::copy(&mut BufReader::with_capacity(BUFFER_SIZE, reader), writer)
+        .context("Failed to read from input")
+}
 
+#[cfg(all(test, feature = "sha2"))]
+fn calculate_digest_from_reader<R: Read>(reader: &mut R, algorithm: HashAlgorithm) -> Result<Vec<u8>> {
+    Ok(rustedbytes_sha::hash_reader(reader, algorithm)?.into_bytes())
+}
+
+#[cfg(all(test, feature = "sha2"))]
+fn calculate_hash_from_reader<R: Read>(reader: &mut R, algorithm: HashAlgorithm) -> Result<String> {
+    calculate_digest_from_reader(reader, algorithm).map(hex::encode)
+}
+
+fn check_hashes(args: &Args, out: &mut dyn Write) -> Result<i32> {
     if args.files.is_empty() {
-        // Read from stdin
-        let hash = calculate_hash_from_reader(&mut io::stdin().lock(), args.algorithm)?;
-        if args.quiet {
-            println!("{}", hash);
-        } else {
-            println!("{}  -", hash);
-        }
-    } else {
-        // Process files
-        let mut all_files = Vec::new();
-
-        for pattern in &args.files {
-            if pattern.contains('*') || pattern.contains('?') || pattern.contains('[') {
-                // Handle glob pattern
-                let paths = glob::glob(pattern)
-                    .with_context(|| format!("Failed to parse glob pattern: {}", pattern))?;
-
-                for path in paths {
-                    let path = path.with_context(|| format!("Failed to process glob: {}", pattern))?;
-                    all_files.push(path.display().to_string());
-                }
+        anyhow::bail!("No hash files specified for checking");
+    }
+    
+    let mut code = exit::SUCCESS;
+    let mut summary = CheckSummary::default();
+
+    let mut failed_output = args.failed_output.as_ref()
+        .map(|path| atomic::AtomicFile::create(path, false)
+            .with_context(|| format!("Failed to create output file: {}", path.display())))
+        .transpose()?;
+
+    let public_key = args.minisign_pubkey.as_deref()
+        .map(|key| {
+            let text = if Path::new(key).is_file() {
+                std::fs::read_to_string(key)
+                    .with_context(|| format!("Failed to read public key: {}", key))?
             } else {
-                // Regular file
-                all_files.push(pattern.clone());
+                key.to_string()
+            };
+            minisign::PublicKey::parse(&text)
+        })
+        .transpose()?;
+
+    let mut progress = args.progress
+        .map(|_| open_progress_output(args).map(output::Progress::new))
+        .transpose()?;
+
+    // Manifests to check, with whether each was listed by another manifest
+    let mut pending: std::collections::VecDeque<(String, bool)> =
+        args.files.iter().map(|path| (path.clone(), false)).collect();
+    let mut nested_seen = std::collections::HashSet::new();
+
+    while let Some((manifest_path, nested)) = pending.pop_front() {
+        let manifest_path = manifest_path.as_str();
+        // `-c -` reads the manifest itself from stdin
+        let hash_file = if manifest_path == "-" { "standard input" } else { manifest_path };
+        let data = read_source(manifest_path)
+            .with_context(|| format!("Failed to read hash file: {}", hash_file))?;
+
+        // A nested manifest is covered by the digest its parent listed
+        if let (Some(public_key), false) = (&public_key, nested) {
+            verify_manifest_signature(manifest_path, &data, public_key, args)
+                .with_context(|| format!("{}: signature not verified", hash_file))?;
+        }
+
+        let content = String::from_utf8(data)
+            .with_context(|| format!("Failed to read hash file: {}: not valid UTF-8", hash_file))?;
+
+        let fixable = args.fix && manifest_path != "-" && !is_remote(manifest_path);
+        if args.fix && !fixable {
+            eprintln!("sha-calc: {}: cannot fix a manifest that is not a local file", hash_file);
+        }
+
+        // SFV files and nested manifests list files relative to their own directory
+        let sfv = manifest::is_sfv(manifest_path);
+        let base_dir = Path::new(manifest_path).parent().filter(|_| (sfv || nested) && !is_remote(manifest_path));
+        let options = args.verify_options(base_dir);
+
+        if let Some(progress) = &mut progress {
+            let (files, bytes) = manifest_totals(&content, sfv, &options);
+            progress.add_total(files, bytes);
+        }
+
+        let mut check = ManifestCheck {
+            args,
+            out: &mut *out,
+            hash_file,
+            nested,
+            fixable,
+            progress: progress.as_mut(),
+            fixes: std::collections::HashMap::new(),
+        };
+        let report = verify::verify_text_with(&content, sfv, &options, &mut check)?;
+        let fixes = check.fixes;
+
+        if args.warn {
+            for (line, e) in &report.improper_lines {
+                eprintln!("sha-calc: {}: {}: improperly formatted line: {}", hash_file, line, e);
             }
         }
 
-        all_files.sort();
+        for entry in &report.entries {
+            let (count, entry_code) = match entry.status {
+                EntryStatus::Ok => (&mut summary.ok, exit::SUCCESS),
+                EntryStatus::Failed { .. } => (&mut summary.failed, exit::MISMATCH),
+                EntryStatus::Missing => (&mut summary.missing, exit::MISSING),
+                EntryStatus::Unreadable(_) => (&mut summary.unreadable, exit::IO_ERROR),
+            };
+            *count += 1;
+            code = exit::combine(code, entry_code);
+        }
 
-        for file_path in all_files {
-            match process_file(&file_path, args.algorithm, args.quiet) {
-                Ok(()) => {},
-                Err(e) => {
-                    eprintln!("sha-calc: {}: {}", file_path, e);
-                    std::process::exit(1);
+        let lines: Vec<&str> = content.lines().collect();
+        let mut failed_lines = Vec::new();
+        for entries in report.entries.chunk_by(|a, b| a.line == b.line) {
+            if !entries.iter().all(EntryReport::is_ok) {
+                failed_lines.push(lines[entries[0].line - 1]);
+            } else if args.recursive_manifests {
+                // Manifests listed here are only trusted once their own digest matched
+                for entry in entries {
+                    if manifest::is_manifest_name(&entry.location) && nested_seen.insert(entry.location.clone()) {
+                        pending.push_back((entry.location.clone(), true));
+                    }
                 }
             }
         }
+
+        if !fixes.is_empty() {
+            rewrite_manifest_lines(Path::new(manifest_path), &content, &fixes)?;
+            let entries = if fixes.len() == 1 { "entry" } else { "entries" };
+            eprintln!("sha-calc: {}: updated {} {}", manifest_path, fixes.len(), entries);
+        }
+
+        if let Some(failed) = &mut failed_output {
+            for line in failed_lines {
+                writeln!(failed, "{}", line)
+                    .context("Failed to write failed entries")?;
+            }
+        }
+
+        // Like coreutils, malformed lines are skipped with a single warning,
+        // unless the manifest has no usable line at all
+        let improper = report.improper_lines.len();
+        if report.formatted_lines == 0 {
+            if !args.status {
+                eprintln!("sha-calc: {}: no properly formatted checksum lines found", hash_file);
+            }
+            code = exit::combine(code, exit::MALFORMED);
+        } else if improper > 0 {
+            summary.improperly_formatted += improper;
+            if !args.status {
+                let lines = if improper == 1 { "line is" } else { "lines are" };
+                eprintln!("sha-calc: WARNING: {} {} improperly formatted", improper, lines);
+            }
+            if args.strict {
+                code = exit::combine(code, exit::MALFORMED);
+            }
+        }
     }
 
-    Ok(())
+    if let Some(progress) = &mut progress {
+        progress.finish().context("Failed to write progress")?;
+    }
+    
+    if let Some(format) = args.summary {
+        writeln!(out, "{}", summary.render(format))?;
+    }
+
+    if let (Some(failed), Some(path)) = (failed_output, &args.failed_output) {
+        failed.commit()
+            .with_context(|| format!("Failed to write output file: {}", path.display()))?;
+    }
+    
+    Ok(code)
 }
-fn list_algorithms() {
-    use hasher::HashAlgorithm;
-    use clap::ValueEnum;
-    println!("Supported hash algorithms:");
-    for alg in HashAlgorithm::value_variants() {
-        // Use the clap name for CLI compatibility
-        println!("- {}", alg.to_possible_value().unwrap().get_name());
+
+/// Checks every input against the digest given with `--expect`.
+fn verify_expected(expected: &str, inputs: &[String], args: &Args, out: &mut dyn Write) -> Result<i32> {
+    let entry = manifest::parse_digest(expected, "-")
+        .map_err(|e| anyhow::anyhow!("Invalid expected digest: {}", e))?;
+    let explicit = args.algorithm_explicit.then_some(args.algorithm);
+    let algorithm = manifest::resolve_algorithm(&entry, explicit, &[])
+        .map_err(|e| anyhow::anyhow!("Invalid expected digest: {}", e))?;
+
+    let mut code = exit::SUCCESS;
+    for file_path in inputs {
+        match hash_input(file_path, algorithm, args.read_options(), None) {
+            Ok((digest, _)) if digest == entry.digest_bytes => print_check_status(out, file_path, "OK", Color::Green, args)?,
+            Ok(_) => {
+                print_check_status(out, file_path, "FAILED", Color::Red, args)?;
+                code = exit::combine(code, exit::MISMATCH);
+            },
+            Err(e) => {
+                eprintln!("sha-calc: {}: {}", file_path, e);
+                code = exit::combine(code, exit::for_error(&e));
+            },
+        }
     }
+    Ok(code)
 }
 
-fn process_file(file_path: &str, algorithm: HashAlgorithm, quiet: bool) -> Result<()> {
-    let file = File::open(file_path)
-        .with_context(|| format!("Failed to open file: {}", file_path))?;
-    
-    let mut reader = BufReader::new(file);
-    let hash = calculate_hash_from_reader(&mut reader, algorithm)?;
-    
-    if quiet {
-        println!("{}", hash);
-    } else {
-        println!("{}  {}", hash, file_path);
+/// Where `--progress` records go: stderr, or the descriptor given with `--progress-fd`.
+fn open_progress_output(args: &Args) -> Result<Box<dyn Write>> {
+    let Some(fd) = args.progress_fd else {
+        return Ok(Box::new(io::stderr()));
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::fd::FromRawFd;
+        if fd < 0 || unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+            anyhow::bail!("Invalid progress file descriptor: {}", fd);
+        }
+        // SAFETY: the descriptor is open, and was handed to us for exclusive use
+        Ok(Box::new(unsafe { File::from_raw_fd(fd) }))
+    }
+    #[cfg(not(unix))]
+    anyhow::bail!("--progress-fd {} is only supported on Unix", fd)
+}
+
+/// Number and total size of the files a manifest's usable entries refer to,
+/// for progress reporting.
+fn manifest_totals(content: &str, sfv: bool, options: &VerifyOptions) -> (u64, u64) {
+    let mut files = 0;
+    let mut bytes = 0;
+    for line in content.lines() {
+        let Ok((entry, _)) = verify::parse_entry(line, sfv, options) else {
+            continue;
+        };
+        let Ok(targets) = verify::entry_files(&entry.path, options) else {
+            continue;
+        };
+        for (listed, location) in targets {
+            if entry.path != "-" && options.is_selected(&listed) {
+                files += 1;
+                bytes += input_size(&location);
+            }
+        }
+    }
+    (files, bytes)
+}
+
+/// Asks on stderr whether to store a FAILED file's current digest in the
+/// manifest, reading the answer from stdin, unless `--yes` was given.
+fn confirm_fix(path: &str, manifest_path: &str, args: &Args) -> Result<bool> {
+    if args.yes {
+        return Ok(true);
+    }
+    eprint!("sha-calc: update the digest of {} in {}? [y/N] ", path, manifest_path);
+    io::stderr().flush().context("Failed to write prompt")?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).context("Failed to read answer")?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Atomically rewrites a manifest with some lines replaced (by index),
+/// keeping every other line and all line endings as they were.
+fn rewrite_manifest_lines(manifest_path: &Path, content: &str, replacements: &std::collections::HashMap<usize, String>) -> Result<()> {
+    let display = manifest_path.display();
+    let mut out = atomic::AtomicFile::create(manifest_path, false)
+        .with_context(|| format!("Failed to create output file: {}", display))?;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        match replacements.get(&index) {
+            Some(replacement) => {
+                let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+                write!(out, "{}{}", replacement, ending)
+            },
+            None => out.write_all(line.as_bytes()),
+        }
+        .context("Failed to write output")?;
+    }
+
+    out.commit()
+        .with_context(|| format!("Failed to write output file: {}", display))
+}
+
+/// The checks of `-c` on one manifest, printing each result as it is known.
+struct ManifestCheck<'a> {
+    args: &'a Args,
+    out: &'a mut dyn Write,
+    /// The manifest, as named in messages.
+    hash_file: &'a str,
+    /// Whether the manifest was listed by another one; its files are then
+    /// named by where they are.
+    nested: bool,
+    fixable: bool,
+    progress: Option<&'a mut ProgressReport>,
+    /// Replacement lines for `--fix`, by line index.
+    fixes: std::collections::HashMap<usize, String>,
+}
+
+impl verify::VerifyHooks for ManifestCheck<'_> {
+    type Error = anyhow::Error;
+
+    fn digest(&mut self, path: &str, location: &str, algorithm: HashAlgorithm) -> io::Result<Digest> {
+        if let Some(progress) = &mut self.progress {
+            progress.start_file(if self.nested { location } else { path })?;
+        }
+        let result = file_digest(location, algorithm, self.progress.as_deref_mut());
+        if let Some(progress) = &mut self.progress {
+            progress.finish_file();
+        }
+        match result {
+            Ok(digest) => Ok(Digest::new(algorithm, digest).expect("digest length matches the algorithm")),
+            Err(e) => {
+                let kind = if exit::is_not_found(&e) { io::ErrorKind::NotFound } else { io::ErrorKind::Other };
+                Err(io::Error::new(kind, e.to_string()))
+            },
+        }
+    }
+
+    fn checked(&mut self, line: &str, entry: &manifest::ManifestEntry, report: &EntryReport) -> Result<()> {
+        let args = self.args;
+        let path = if self.nested { &report.location } else { &report.path };
+        match &report.status {
+            EntryStatus::Ok => print_check_status(self.out, path, "OK", Color::Green, args)?,
+            EntryStatus::Failed { actual } => {
+                print_check_status(self.out, path, "FAILED", Color::Red, args)?;
+                // A glob entry's single digest cannot describe several files
+                if self.fixable && !(args.glob_entries && manifest::is_glob(&entry.path)) && confirm_fix(path, self.hash_file, args)? {
+                    let digest = manifest::reencode_digest(&entry.digest, actual.as_bytes());
+                    self.fixes.insert(report.line - 1, replace_digest(line, entry, &digest));
+                }
+            },
+            EntryStatus::Missing => print_check_status(self.out, path, "MISSING", Color::Yellow, args)?,
+            EntryStatus::Unreadable(message) if !args.status => match path.as_str() {
+                "-" => eprintln!("sha-calc: {}", message),
+                path => eprintln!("sha-calc: {}: {}", path, message),
+            },
+            EntryStatus::Unreadable(_) => {},
+        }
+        Ok(())
+    }
+}
+
+/// Prints the result for one checked file. Like coreutils, `--quiet` hides
+/// only the OK lines and `--status` hides everything.
+fn print_check_status(out: &mut dyn Write, path: &str, status: &str, color_code: Color, args: &Args) -> io::Result<()> {
+    if args.status || (args.quiet && status == "OK") {
+        return Ok(());
+    }
+    writeln!(out, "{}: {}", display_name(path), output::paint(status, color_code, args.color.enabled()))
+}
+
+/// Whether a manifest entry or input path passes the `--include`/`--exclude` filters.
+fn is_selected(path: &str, args: &Args) -> bool {
+    (args.include.is_empty() || args.include.iter().any(|pattern| pattern.matches(path)))
+        && !args.exclude.iter().any(|pattern| pattern.matches(path))
+}
+
+/// Checks the minisign/signify signature of a manifest before any of its
+/// entries are trusted.
+fn verify_manifest_signature(manifest_path: &str, data: &[u8], public_key: &minisign::PublicKey, args: &Args) -> Result<()> {
+    let (signature_source, signature_data) = match &args.signature {
+        Some(source) => (source.clone(), read_source(source)?),
+        None if manifest_path == "-" => anyhow::bail!("--signature is required when the manifest is read from stdin"),
+        None => [".minisig", ".sig"]
+            .iter()
+            .map(|ext| format!("{}{}", manifest_path, ext))
+            .find_map(|source| read_source(&source).ok().map(|sig| (source, sig)))
+            .with_context(|| format!("No {0}.minisig or {0}.sig signature found", manifest_path))?,
+    };
+
+    let text = String::from_utf8(signature_data)
+        .with_context(|| format!("Invalid signature: {}", signature_source))?;
+    let signature = minisign::Signature::parse(&text)?;
+    signature.verify(public_key, data)?;
+
+    if !args.quiet && !args.status {
+        match signature.trusted_comment() {
+            Some(comment) => eprintln!("sha-calc: {}: signature verified (trusted comment: {})", manifest_path, comment),
+            None => eprintln!("sha-calc: {}: signature verified", manifest_path),
+        }
     }
-    
     Ok(())
 }
 
-fn calculate_hash_from_reader<R: Read>(reader: &mut R, algorithm: HashAlgorithm) -> Result<String> {
-    let mut buffer = [0; 8192];
-    let hash = loop {
-        let bytes_read = reader.read(&mut buffer)
-            .context("Failed to read from input")?;
-        
-        if bytes_read == 0 {
-            break calculate_hash(&[], algorithm, true);
+/// Reads a manifest or signature from a file, stdin (`-`) or an HTTPS URL.
+fn read_source(source: &str) -> Result<Vec<u8>> {
+    read_input(source, None)
+}
+
+/// Parses a line of a regular or SFV manifest.
+fn parse_manifest_line(line: &str, sfv: bool, args: &Args) -> Result<(manifest::ManifestEntry, HashAlgorithm), manifest::ParseError> {
+    verify::parse_entry(line, sfv, &args.verify_options(None))
+}
+
+/// Parses a manifest line and picks the algorithm to verify it with.
+fn parse_check_line(line: &str, args: &Args) -> Result<(manifest::ManifestEntry, HashAlgorithm), manifest::ParseError> {
+    parse_manifest_line(line, false, args)
+}
+
+/// Prints the differences between two manifests; returns `false` if they differ.
+fn diff_manifests(old: &str, new: &str, args: &Args, out: &mut dyn Write) -> Result<bool> {
+    let changes = manifest::diff(&load_digests(old, args)?, &load_digests(new, args)?);
+    let color = args.color.enabled();
+
+    for (path, change) in &changes {
+        let (status, color_code) = match change {
+            manifest::Change::Added => ("ADDED", Color::Green),
+            manifest::Change::Removed => ("REMOVED", Color::Red),
+            manifest::Change::Changed => ("CHANGED", Color::Yellow),
+        };
+        writeln!(out, "{}: {}", display_name(path), output::paint(status, color_code, color))?;
+    }
+    Ok(changes.is_empty())
+}
+
+/// Reports whether two inputs have the same contents.
+fn compare_files(first: &str, second: &str, args: &Args, out: &mut dyn Write) -> Result<i32> {
+    let same = match same_contents(first, second, args) {
+        Ok(same) => same,
+        Err(errors) => return Ok(report_errors(errors)),
+    };
+
+    if !args.quiet {
+        writeln!(out, "Files {} and {} {}", first, second, if same { "are identical" } else { "differ" })?;
+    }
+    Ok(if same { exit::SUCCESS } else { exit::MISMATCH })
+}
+
+/// Lists the files found in only one of two directories and the files whose
+/// contents differ; returns `MISMATCH` if there are any.
+fn compare_dirs(left: &str, right: &str, args: &Args, out: &mut dyn Write) -> Result<i32> {
+    let list = |dir: &str| -> Result<std::collections::BTreeMap<String, String>> {
+        let root = Path::new(dir);
+        if !root.is_dir() {
+            anyhow::bail!("Not a directory: {}", dir);
         }
-        
-        if bytes_read == buffer.len() {
-            // More data might be available, read all at once for efficiency
-            let mut all_data = buffer.to_vec();
-            reader.read_to_end(&mut all_data)
-                .context("Failed to read remaining data")?;
-            break calculate_hash(&all_data, algorithm, false);
-        } else {
-            // This is the last chunk
-            break calculate_hash(&buffer[..bytes_read], algorithm, false);
+        let files = walk::walk_files(root, &args.walk_options())
+            .with_context(|| format!("Failed to read directory: {}", dir))?;
+        Ok(files.into_iter()
+            .map(|file| (relative_path(root, &file), file.display().to_string()))
+            .filter(|(relative, _)| is_selected(relative, args))
+            .collect())
+    };
+    let (left_files, right_files) = (list(left)?, list(right)?);
+    let paths: std::collections::BTreeSet<&String> = left_files.keys().chain(right_files.keys()).collect();
+
+    let color = args.color.enabled();
+    let mut code = exit::SUCCESS;
+    for path in paths {
+        let (status, color_code) = match (left_files.get(path), right_files.get(path)) {
+            (Some(_), None) => ("LEFT ONLY", Color::Red),
+            (None, Some(_)) => ("RIGHT ONLY", Color::Green),
+            (Some(left), Some(right)) => match same_contents(left, right, args) {
+                Ok(true) => continue,
+                Ok(false) => ("DIFFERENT", Color::Yellow),
+                Err(errors) => {
+                    code = exit::combine(code, report_errors(errors));
+                    continue;
+                },
+            },
+            (None, None) => unreachable!(),
+        };
+        writeln!(out, "{}: {}", display_name(path), output::paint(status, color_code, color))?;
+        code = exit::combine(code, exit::MISMATCH);
+    }
+    Ok(code)
+}
+
+/// Whether two inputs have the same contents, hashing both at once; local
+/// files of different sizes differ without being read. Fails with the
+/// errors of the inputs that could not be hashed.
+fn same_contents<'a>(first: &'a str, second: &'a str, args: &Args) -> Result<bool, Vec<(&'a str, anyhow::Error)>> {
+    let local_size = |path: &str| {
+        if path == "-" || is_remote(path) {
+            return None;
         }
+        File::open(path).and_then(|file| blockdev::size(&file)).ok()
     };
-    
-    Ok(hash)
+    let options = args.read_options();
+    if let (Some(a), Some(b)) = (local_size(first), local_size(second))
+        && options.range_len(a) != options.range_len(b)
+    {
+        return Ok(false);
+    }
+
+    let (a, b) = std::thread::scope(|scope| {
+        let b = scope.spawn(|| hash_input(second, args.algorithm, options, None));
+        (hash_input(first, args.algorithm, options, None), b.join().unwrap())
+    });
+    match (a, b) {
+        (Ok((a, _)), Ok((b, _))) => Ok(a == b),
+        (a, b) => Err([(first, a.err()), (second, b.err())]
+            .into_iter()
+            .filter_map(|(path, error)| Some((path, error?)))
+            .collect()),
+    }
+}
+
+/// Prints errors of the form `(path, error)` and returns the exit status they call for.
+fn report_errors(errors: Vec<(&str, anyhow::Error)>) -> i32 {
+    errors.into_iter().fold(exit::SUCCESS, |code, (path, error)| {
+        eprintln!("sha-calc: {}: {}", path, error);
+        exit::combine(code, exit::for_error(&error))
+    })
+}
+
+/// Reads a manifest (file, stdin or URL) into digests keyed by path,
+/// skipping lines that cannot be parsed.
+fn load_digests(source: &str, args: &Args) -> Result<manifest::Digests> {
+    Ok(load_entries(source, args)?
+        .into_iter()
+        .map(|(entry, algorithm)| (entry.path, (algorithm, hex::encode(entry.digest_bytes))))
+        .collect())
 }
 
-fn check_hashes(args: &Args) -> Result<()> {
+/// Reads the usable entries of a manifest (file, stdin or URL), skipping
+/// comments and lines that cannot be parsed.
+fn load_entries(source: &str, args: &Args) -> Result<Vec<(manifest::ManifestEntry, HashAlgorithm)>> {
+    let data = read_source(source)
+        .with_context(|| format!("Failed to read hash file: {}", source))?;
+    let content = String::from_utf8(data)
+        .with_context(|| format!("Failed to read hash file: {}: not valid UTF-8", source))?;
+    let sfv = manifest::is_sfv(source);
+
+    Ok(content.lines()
+        .filter(|line| if sfv { !line.starts_with(';') } else { !manifest::is_comment(line) })
+        .filter_map(|line| parse_manifest_line(line, sfv, args).ok())
+        .collect())
+}
+
+/// A manifest entry watched by `--check --watch`.
+struct WatchedFile {
+    path: String,
+    resolved: String,
+    digest: Vec<u8>,
+    algorithm: HashAlgorithm,
+    /// Size and mtime when last verified, `None` if the file was missing.
+    fingerprint: Option<(u64, Option<std::time::SystemTime>)>,
+    status: Option<&'static str>,
+}
+
+/// Re-verifies the manifests' entries whenever a listed file changes,
+/// polling file metadata, and prints only status transitions. The first
+/// pass prints every status. Runs until interrupted.
+fn watch_check(args: &Args, out: &mut dyn Write) -> Result<i32> {
     if args.files.is_empty() {
         anyhow::bail!("No hash files specified for checking");
     }
-    
-    let mut all_ok = true;
-    
-    for hash_file in &args.files {
-        let content = std::fs::read_to_string(hash_file)
-            .with_context(|| format!("Failed to read hash file: {}", hash_file))?;
-        
-        for (line_num, line) in content.lines().enumerate() {
-            if line.trim().is_empty() {
-                continue;
+
+    let mut files = Vec::new();
+    for manifest_path in &args.files {
+        let sfv = manifest::is_sfv(manifest_path);
+        let base_dir = Path::new(manifest_path).parent().filter(|_| sfv && !is_remote(manifest_path));
+        let options = args.verify_options(base_dir);
+        for (entry, algorithm) in load_entries(manifest_path, args)? {
+            for (path, resolved) in verify::entry_files(&entry.path, &options)? {
+                if !options.is_selected(&path) {
+                    continue;
+                }
+                files.push(WatchedFile {
+                    path,
+                    resolved,
+                    digest: entry.digest_bytes.clone(),
+                    algorithm,
+                    fingerprint: None,
+                    status: None,
+                });
             }
-            
-            let parts: Vec<&str> = line.splitn(2, "  ").collect();
-            if parts.len() != 2 {
-                eprintln!("sha-calc: {}: line {}: improperly formatted", hash_file, line_num + 1);
-                all_ok = false;
+        }
+    }
+    if files.is_empty() {
+        anyhow::bail!("no properly formatted checksum lines found");
+    }
+
+    loop {
+        for file in &mut files {
+            let fingerprint = std::fs::metadata(&file.resolved).ok()
+                .map(|meta| (meta.len(), meta.modified().ok()));
+            if file.status.is_some() && fingerprint == file.fingerprint {
                 continue;
             }
-            
-            let expected_hash = parts[0];
-            let file_path = parts[1];
-            
-            if file_path == "-" {
-                eprintln!("sha-calc: cannot check stdin");
-                all_ok = false;
+            file.fingerprint = fingerprint;
+
+            let (status, color_code) = match file_digest(&file.resolved, file.algorithm, None) {
+                Ok(digest) if digest == file.digest => ("OK", Color::Green),
+                Ok(_) => ("FAILED", Color::Red),
+                Err(e) if exit::is_not_found(&e) => ("MISSING", Color::Yellow),
+                Err(e) => {
+                    eprintln!("sha-calc: {}: {}", file.path, e);
+                    ("UNREADABLE", Color::Red)
+                },
+            };
+            if file.status != Some(status) {
+                file.status = Some(status);
+                print_check_status(out, &file.path, status, color_code, args)?;
+            }
+        }
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// Prints the digest of every input, then again whenever the contents of
+/// one change, polling file metadata. Patterns and directories are expanded
+/// again on each pass, so new files are picked up. Runs until interrupted.
+fn watch_hash(args: &Args, out: &mut dyn Write) -> Result<i32> {
+    if args.files.is_empty() {
+        anyhow::bail!("No files specified for watching");
+    }
+
+    type Fingerprint = Option<(u64, Option<std::time::SystemTime>)>;
+    // Fingerprint and digest of each file, the digest empty if hashing failed
+    let mut known: std::collections::HashMap<String, (Fingerprint, Vec<u8>)> = std::collections::HashMap::new();
+    loop {
+        let inputs = expand_patterns(&args.files, args)?;
+        known.retain(|path, _| {
+            let listed = inputs.contains(path);
+            if !listed {
+                eprintln!("sha-calc: {}: removed", path);
+            }
+            listed
+        });
+
+        for path in &inputs {
+            let fingerprint = std::fs::metadata(path).ok()
+                .map(|meta| (meta.len(), meta.modified().ok()));
+            let previous = known.get(path);
+            if previous.is_some_and(|(seen, _)| *seen == fingerprint) {
                 continue;
             }
-            
-            match process_file_check(file_path, expected_hash, args.algorithm) {
-                Ok(true) => {
-                    if !args.quiet {
-                        println!("{}: OK", file_path);
+
+            let digest = match hash_path(path, args, None, &mut Links::new()) {
+                Ok((digest, size)) => {
+                    // Touched but unchanged files are not printed again
+                    if previous.is_none_or(|(_, last)| *last != digest) {
+                        match &args.template {
+                            Some(template) => {
+                                let mut entry = HashRecord::new(path.clone(), to_digest(digest.clone(), args), size);
+                                entry.mtime = fingerprint.and_then(|(_, mtime)| mtime);
+                                print_record(out, &template.render(&entry, &args.digest_style()), args)?;
+                            },
+                            None => print_digest(out, &digest, path, args)?,
+                        }
+                        if args.qr {
+                            print_qr(out, &digest, args)?;
+                        }
+                        out.flush().context("Failed to write output")?;
                     }
-                },
-                Ok(false) => {
-                    println!("{}: FAILED", file_path);
-                    all_ok = false;
+                    digest
                 },
                 Err(e) => {
-                    eprintln!("sha-calc: {}: {}", file_path, e);
-                    all_ok = false;
-                }
-            }
+                    eprintln!("sha-calc: {}: {}", path, e);
+                    Vec::new()
+                },
+            };
+            known.insert(path.clone(), (fingerprint, digest));
         }
+        std::thread::sleep(WATCH_INTERVAL);
     }
-    
-    if !all_ok {
-        std::process::exit(1);
+}
+
+/// Rewrites a manifest atomically, re-hashing only the entries whose file was
+/// modified since the manifest itself was last written, appending `inputs`
+/// that are not listed yet and dropping entries for deleted files.
+/// Unrecognized lines and unchanged entries are kept verbatim.
+fn update_manifest(manifest_path: &Path, inputs: &[String], args: &Args) -> Result<()> {
+    let display = manifest_path.display();
+    let content = match std::fs::read_to_string(manifest_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read hash file: {}", display)),
+    };
+    // Entries are relative to the manifest, wherever it is updated from
+    let base_dir = manifest_path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let stamps_path = stamps_path(manifest_path);
+    let old_stamps = read_stamps(&stamps_path);
+
+    let mut out = atomic::AtomicFile::create(manifest_path, false)
+        .with_context(|| format!("Failed to create output file: {}", display))?;
+    let mut stamps = atomic::AtomicFile::create(&stamps_path, false)
+        .with_context(|| format!("Failed to create output file: {}", stamps_path.display()))?;
+    let mut listed = std::collections::HashSet::new();
+    let (mut updated, mut added, mut removed) = (0, 0, 0);
+
+    for line in content.lines() {
+        let Ok((entry, algorithm)) = parse_check_line(line, args) else {
+            writeln!(out, "{}", line).context("Failed to write output")?;
+            continue;
+        };
+        listed.insert(entry.path.clone());
+
+        let location = base_dir.map_or_else(|| PathBuf::from(&entry.path), |dir| dir.join(&entry.path));
+        let stamp = match std::fs::metadata(&location) {
+            Ok(meta) => file_stamp(&meta),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                removed += 1;
+                continue;
+            },
+            Err(e) => return Err(e).with_context(|| format!("Failed to read metadata: {}", entry.path)),
+        };
+        write_stamp(&mut stamps, &entry.path, stamp)?;
+
+        if stamp.is_some() && old_stamps.get(&entry.path) == stamp.as_ref() {
+            writeln!(out, "{}", line).context("Failed to write output")?;
+            continue;
+        }
+
+        let (digest, _) = hash_input(&location.to_string_lossy(), algorithm, ReadOptions::default(), None)?;
+        if digest != entry.digest_bytes {
+            updated += 1;
+        }
+        let digest = manifest::reencode_digest(&entry.digest, &digest);
+        writeln!(out, "{}", replace_digest(line, &entry, &digest)).context("Failed to write output")?;
     }
-    
+
+    for file_path in inputs {
+        let path = listed_path(file_path, base_dir)?;
+        if !listed.insert(path.clone()) {
+            continue;
+        }
+        let stamp = std::fs::metadata(file_path).ok().as_ref().and_then(file_stamp);
+        let (digest, _) = hash_input(file_path, args.algorithm, ReadOptions::default(), None)
+            .with_context(|| format!("Failed to hash {}", file_path))?;
+        print_digest(&mut out, &digest, &path, args)?;
+        write_stamp(&mut stamps, &path, stamp)?;
+        added += 1;
+    }
+
+    out.commit()
+        .with_context(|| format!("Failed to write output file: {}", display))?;
+    stamps.commit()
+        .with_context(|| format!("Failed to write output file: {}", stamps_path.display()))?;
+    eprintln!("sha-calc: {}: {} updated, {} added, {} removed", display, updated, added, removed);
     Ok(())
 }
 
-fn process_file_check(file_path: &str, expected_hash: &str, algorithm: HashAlgorithm) -> Result<bool> {
-    let file = File::open(file_path)
-        .with_context(|| format!("Failed to open file: {}", file_path))?;
-    
-    let mut reader = BufReader::new(file);
-    let actual_hash = calculate_hash_from_reader(&mut reader, algorithm)?;
-    
-    Ok(actual_hash.to_lowercase() == expected_hash.to_lowercase())
+/// Size and modification time (nanoseconds since the Unix epoch) of a file
+/// when `--update` last hashed it.
+type FileStamp = (u64, u128);
+
+fn file_stamp(meta: &std::fs::Metadata) -> Option<FileStamp> {
+    let mtime = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((meta.len(), mtime.as_nanos()))
+}
+
+/// Where `--update` keeps the stamps of a manifest's files: next to it, in
+/// `<manifest>.state`.
+fn stamps_path(manifest_path: &Path) -> PathBuf {
+    let mut path = manifest_path.as_os_str().to_owned();
+    path.push(".state");
+    PathBuf::from(path)
+}
+
+/// Reads the `size mtime path` lines of a stamps file. A missing or damaged
+/// file only means that the files it lost are hashed again.
+fn read_stamps(path: &Path) -> std::collections::HashMap<String, FileStamp> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return std::collections::HashMap::new();
+    };
+    content.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let size = fields.next()?.parse().ok()?;
+            let mtime = fields.next()?.parse().ok()?;
+            Some((manifest::unescape_filename(fields.next()?)?, (size, mtime)))
+        })
+        .collect()
+}
+
+fn write_stamp(out: &mut dyn Write, path: &str, stamp: Option<FileStamp>) -> Result<()> {
+    if let Some((size, mtime)) = stamp {
+        let path = manifest::escape_filename(path).map_or(Cow::Borrowed(path), Cow::Owned);
+        writeln!(out, "{} {} {}", size, mtime, path).context("Failed to write output")?;
+    }
+    Ok(())
+}
+
+/// `path` as listed by a manifest in `base_dir`, which must contain it
+/// unless it is absolute.
+fn listed_path(path: &str, base_dir: Option<&Path>) -> Result<String> {
+    let Some(dir) = base_dir else {
+        return Ok(path.to_string());
+    };
+    match Path::new(path).strip_prefix(dir) {
+        Ok(relative) => Ok(relative.to_string_lossy().into_owned()),
+        Err(_) if Path::new(path).is_absolute() => Ok(path.to_string()),
+        Err(_) => anyhow::bail!("{} is not inside the directory of the manifest, {}", path, dir.display()),
+    }
+}
+
+/// Replaces the digest in a manifest line, which comes last in tagged lines
+/// and first otherwise, so a path containing the same text is left alone.
+fn replace_digest(line: &str, entry: &manifest::ManifestEntry, digest: &str) -> String {
+    let index = if entry.algorithm.is_some() { line.rfind(&entry.digest) } else { line.find(&entry.digest) };
+    match index {
+        Some(index) => format!("{}{}{}", &line[..index], digest, &line[index + entry.digest.len()..]),
+        None => line.to_string(),
+    }
+}
+
+/// Result of comparing a file with its shatag extended attributes.
+enum XattrStatus {
+    /// The stored digest matches.
+    Ok,
+    /// The digest differs although the mtime is unchanged: silent corruption.
+    Corrupt,
+    /// The file was modified after the digest was stored.
+    Outdated,
+    /// No digest is stored for this algorithm.
+    New,
+}
+
+/// Checks every input against its shatag/cshatag extended attributes. Only
+/// corruption and errors count as failures; outdated and untagged files are
+/// reported but expected in a live tree.
+fn check_xattrs(inputs: &[String], args: &Args, out: &mut dyn Write) -> Result<i32> {
+    if inputs.is_empty() {
+        anyhow::bail!("No files specified for checking");
+    }
+
+    let color = args.color.enabled();
+    let mut code = exit::SUCCESS;
+    for file_path in inputs {
+        let (status, color_code) = match check_xattr(file_path, args.algorithm) {
+            Ok(XattrStatus::Ok) if args.quiet => continue,
+            Ok(XattrStatus::Ok) => ("OK", Color::Green),
+            Ok(XattrStatus::Corrupt) => {
+                code = exit::combine(code, exit::MISMATCH);
+                ("FAILED", Color::Red)
+            },
+            Ok(XattrStatus::Outdated) => ("OUTDATED", Color::Yellow),
+            Ok(XattrStatus::New) => ("NEW", Color::Yellow),
+            Err(e) => {
+                eprintln!("sha-calc: {}: {:#}", file_path, e);
+                code = exit::combine(code, exit::for_error(&e));
+                continue;
+            },
+        };
+        writeln!(out, "{}: {}", display_name(file_path), output::paint(status, color_code, color))?;
+    }
+    Ok(code)
+}
+
+fn check_xattr(file_path: &str, algorithm: HashAlgorithm) -> Result<XattrStatus> {
+    let path = Path::new(file_path);
+    let name = format!("user.shatag.{}", output::algorithm_name(algorithm));
+    let Some(stored) = xattr::get(path, &name).context("Failed to read extended attributes")? else {
+        return Ok(XattrStatus::New);
+    };
+    let stored = String::from_utf8_lossy(&stored);
+    let stored = stored.trim_end_matches('\0').trim();
+
+    let (digest, _) = hash_input(file_path, algorithm, ReadOptions::default(), None)?;
+    if hex::encode(digest).eq_ignore_ascii_case(stored) {
+        return Ok(XattrStatus::Ok);
+    }
+
+    // cshatag records the mtime the digest was computed at; a different
+    // mtime means the file was legitimately rewritten since
+    let stored_mtime = xattr::get(path, "user.shatag.ts")
+        .context("Failed to read extended attributes")?
+        .and_then(|value| xattr::parse_timestamp(&value));
+    let mtime = std::fs::metadata(path)?.modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| (d.as_secs() as i64, d.subsec_nanos()))
+        .ok();
+
+    match stored_mtime {
+        Some(stored_mtime) if Some(stored_mtime) != mtime => Ok(XattrStatus::Outdated),
+        _ => Ok(XattrStatus::Corrupt),
+    }
+}
+
+/// A file name as shown in check results, escaped like in manifests.
+fn display_name(file_path: &str) -> String {
+    match manifest::escape_filename(file_path) {
+        Some(escaped) => format!("\\{}", escaped),
+        None => file_path.to_string(),
+    }
+}
+
+fn file_digest(file_path: &str, algorithm: HashAlgorithm, progress: Option<&mut ProgressReport>) -> Result<Vec<u8>> {
+    hash_input(file_path, algorithm, ReadOptions::default(), progress).map(|(digest, _)| digest)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "sha2")]
     use std::io::Cursor;
     
     #[test]
+    #[cfg(feature = "sha2")]
     fn test_calculate_hash_from_reader_sha256() {
         let data = b"hello world";
         let mut cursor = Cursor::new(data);
@@ -219,6 +2835,7 @@ mod tests {
     }
     
     #[test]
+    #[cfg(feature = "sha2")]
     fn test_calculate_hash_from_reader_empty() {
         let data = b"";
         let mut cursor = Cursor::new(data);
@@ -227,6 +2844,7 @@ mod tests {
     }
     
     #[test]
+    #[cfg(all(feature = "sha1", feature = "sha2"))]
     fn test_different_algorithms() {
         let data = b"test";
         let mut cursor = Cursor::new(data);
@@ -239,4 +2857,35 @@ mod tests {
         assert_eq!(sha1_hash.len(), 40); // SHA-1 produces 160-bit hash (40 hex chars)
         assert_eq!(sha256_hash.len(), 64); // SHA-256 produces 256-bit hash (64 hex chars)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["disk.part10", "disk.part2", "disk.part1", "a.z01", "a.zip", "a.z02", "x007", "x7", "x"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, ["a.z01", "a.z02", "a.zip", "disk.part1", "disk.part2", "disk.part10", "x", "x7", "x007"]);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("0x8000"), Ok(0x8000));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size("2m"), Ok(2 << 20));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("1.5G").is_err());
+        assert!(parse_size("99999999999T").is_err());
+    }
+
+    #[test]
+    fn test_read_options_range() {
+        let options = ReadOptions { offset: 6, length: Some(3), ..ReadOptions::default() };
+        assert_eq!(options.select(b"hello", 0), b"");
+        assert_eq!(options.select(b"o wo", 4), b"wo");
+        assert_eq!(options.select(b"rld", 8), b"r");
+        assert_eq!(options.range_len(100), 3);
+        assert!(options.check_size(9).is_ok());
+        assert!(options.check_size(8).is_err());
+        assert!(options.check_size(5).is_err());
+        assert_eq!(ReadOptions::default().select(b"all", 10), b"all");
+    }
+}
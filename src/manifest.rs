@@ -0,0 +1,650 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::{self, Encoding};
+use crate::error::Error;
+use crate::hasher::HashAlgorithm;
+
+/// A single entry of a checksum manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// The digest as written: hex, base64 or an SRI string (`sha256-<base64>`).
+    pub digest: String,
+    /// The decoded digest.
+    pub digest_bytes: Vec<u8>,
+    pub path: String,
+    /// Whether the entry carries the `*` binary-mode marker.
+    pub binary: bool,
+    /// Algorithm named by the tag of a BSD-style line or by an SRI prefix.
+    pub algorithm: Option<HashAlgorithm>,
+}
+
+/// Why a manifest line could not be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// No `  `/` *` separator after the digest and no recognizable tag.
+    MissingSeparator,
+    /// A backslash-flagged line with an unknown escape in its filename.
+    InvalidEscape,
+    /// The digest is neither hex nor base64.
+    InvalidDigest,
+    /// No supported algorithm produces a digest of this many bytes.
+    UnknownLength(usize),
+    /// The digest length does not fit the algorithm chosen for the line.
+    WrongLength { algorithm: HashAlgorithm, len: usize },
+    /// The line's algorithm was left out of this build.
+    Unavailable(HashAlgorithm),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingSeparator => write!(f, "missing separator between digest and file name"),
+            ParseError::InvalidEscape => write!(f, "invalid escape sequence in file name"),
+            ParseError::InvalidDigest => write!(f, "digest is not valid hexadecimal or base64"),
+            ParseError::UnknownLength(len) => write!(f, "no supported algorithm has {}-byte digests", len),
+            ParseError::WrongLength { algorithm, len } => write!(
+                f, "{} digests are {} bytes, found {}", algorithm.name(), algorithm.digest_size(), len
+            ),
+            ParseError::Unavailable(algorithm) => write!(f, "{} support is not compiled in", algorithm.name()),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a whole manifest, `.sfv` style if `sfv` is set, skipping blank
+/// and comment lines. Unlike `--check`, which skips lines it cannot parse,
+/// this stops at the first one.
+pub fn parse(text: &str, sfv: bool) -> Result<Vec<ManifestEntry>, Error> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter(|(_, line)| if sfv { !line.starts_with(';') } else { !is_comment(line) })
+        .map(|(index, line)| {
+            let entry = if sfv { parse_sfv_line(line) } else { parse_line(line) };
+            entry.map_err(|error| Error::BadManifestLine { line: index + 1, error })
+        })
+        .collect()
+}
+
+/// Parses a manifest line in either the default `hash  filename` format
+/// (`hash *filename` for binary mode; a single space or a tab is accepted
+/// too, and a trailing CR is ignored) or a tagged format naming the
+/// algorithm: BSD/`shasum --tag` (`SHA256 (filename) = hash`) or
+/// `openssl dgst` (`SHA2-256(filename)= hash`). Digests may be hex, base64
+/// or Subresource Integrity strings (`sha384-<base64>`).
+///
+/// Tagged lines record their algorithm in [`ManifestEntry::algorithm`], so
+/// one manifest can mix algorithms. Lines starting with a backslash carry an
+/// escaped filename (see [`escape_filename`]).
+pub fn parse_line(line: &str) -> Result<ManifestEntry, ParseError> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    let mut entry = if let Some(entry) = parse_tag_line(line) {
+        entry
+    } else {
+        let (digest, rest) = line.split_once([' ', '\t']).ok_or(ParseError::MissingSeparator)?;
+        // Two spaces or ` *` as written by coreutils, but also a single space
+        // or a tab as written by other tools
+        let (binary, path) = match rest.as_bytes().first() {
+            Some(b'*') => (true, &rest[1..]),
+            Some(b' ' | b'\t') => (false, &rest[1..]),
+            _ => (false, rest),
+        };
+        if path.is_empty() {
+            return Err(ParseError::MissingSeparator);
+        }
+        ManifestEntry {
+            digest: digest.to_string(),
+            digest_bytes: Vec::new(),
+            path: path.to_string(),
+            binary,
+            algorithm: None,
+        }
+    };
+
+    let (digest_bytes, sri_algorithm) = decode_digest(&entry.digest).ok_or(ParseError::InvalidDigest)?;
+    entry.digest_bytes = digest_bytes;
+    entry.algorithm = entry.algorithm.or(sri_algorithm);
+
+    if escaped {
+        entry.path = unescape_filename(&entry.path).ok_or(ParseError::InvalidEscape)?;
+    }
+    Ok(entry)
+}
+
+/// Whether `line` is a `#` comment, which manifests may contain anywhere.
+pub fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+/// Builds an entry for a digest given on the command line rather than in a
+/// manifest, in any encoding [`parse_line`] accepts.
+pub fn parse_digest(digest: &str, path: &str) -> Result<ManifestEntry, ParseError> {
+    let digest = digest.trim();
+    let (digest_bytes, algorithm) = decode_digest(digest).ok_or(ParseError::InvalidDigest)?;
+    Ok(ManifestEntry {
+        digest: digest.to_string(),
+        digest_bytes,
+        path: path.to_string(),
+        binary: false,
+        algorithm,
+    })
+}
+
+/// SRI hash prefixes (W3C Subresource Integrity).
+const SRI_ALGORITHMS: [(&str, HashAlgorithm); 3] = [
+    ("sha256", HashAlgorithm::Sha256),
+    ("sha384", HashAlgorithm::Sha384),
+    ("sha512", HashAlgorithm::Sha512),
+];
+
+/// Splits an SRI string into its algorithm prefix and base64 payload. Only
+/// the known prefixes count, so a `-` inside a base64url digest is not
+/// mistaken for a separator.
+fn split_sri(text: &str) -> Option<(&'static str, HashAlgorithm, &str)> {
+    SRI_ALGORITHMS.iter().find_map(|&(name, algorithm)| {
+        let encoded = text.strip_prefix(name)?.strip_prefix('-')?;
+        Some((name, algorithm, encoded))
+    })
+}
+
+/// Decodes a digest written as hex, base64, base64url or an SRI string,
+/// returning the bytes and the algorithm named by an SRI prefix. Hex wins
+/// when a digest is valid in more than one encoding.
+fn decode_digest(text: &str) -> Option<(Vec<u8>, Option<HashAlgorithm>)> {
+    if let Some((_, algorithm, encoded)) = split_sri(text) {
+        return encoding::decode_base64(encoded)
+            .filter(|bytes| !bytes.is_empty())
+            .map(|bytes| (bytes, Some(algorithm)));
+    }
+
+    hex::decode(text).ok()
+        .or_else(|| encoding::decode_base64(text))
+        .or_else(|| encoding::decode_base64url(text))
+        .filter(|bytes| !bytes.is_empty())
+        .map(|bytes| (bytes, None))
+}
+
+/// Encodes `digest` the way `original` was written, so rewritten manifest
+/// lines keep their style.
+pub fn reencode_digest(original: &str, digest: &[u8]) -> String {
+    if let Some((prefix, _, _)) = split_sri(original) {
+        return format!("{}-{}", prefix, encoding::encode(digest, Encoding::Base64));
+    }
+
+    if hex::decode(original).is_ok() {
+        if original.bytes().any(|b| b.is_ascii_uppercase()) {
+            hex::encode_upper(digest)
+        } else {
+            hex::encode(digest)
+        }
+    } else {
+        let mut encoded = encoding::encode(digest, Encoding::Base64);
+        if original.contains(['-', '_']) {
+            encoded = encoded.replace('+', "-").replace('/', "_");
+        }
+        if original.ends_with('=') { encoded } else { encoded.trim_end_matches('=').to_string() }
+    }
+}
+
+/// Whether `path` names a Simple File Verification (`.sfv`) manifest.
+pub fn is_sfv(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sfv"))
+}
+
+/// Whether `path` looks like a checksum manifest: an SFV file or a file
+/// with an algorithm extension such as `.sha256` or `.sha3-512`.
+pub fn is_manifest_name(path: &str) -> bool {
+    is_sfv(path) || Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.parse::<HashAlgorithm>().is_ok())
+}
+
+/// Whether `path` is a glob pattern (contains `*`, `?` or `[`), as
+/// `sha-calc` expands its arguments and, with `--glob-entries`, manifest
+/// entries. The `?` of a Windows `\\?\` prefix is not a wildcard.
+pub fn is_glob(path: &str) -> bool {
+    let path = &path[path_prefix_len(path)..];
+    path.contains('*') || path.contains('?') || path.contains('[')
+}
+
+/// Length of the Windows prefix of `path` (`C:`, `\\server\share`,
+/// `\\?\C:`), whose `?` is not a wildcard.
+#[cfg(windows)]
+fn path_prefix_len(path: &str) -> usize {
+    match Path::new(path).components().next() {
+        Some(std::path::Component::Prefix(prefix)) => prefix.as_os_str().len(),
+        _ => 0,
+    }
+}
+
+#[cfg(not(windows))]
+fn path_prefix_len(_path: &str) -> usize {
+    0
+}
+
+/// The paths matching a glob pattern, in the glob crate's order.
+pub fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    let paths = glob::glob(&glob_pattern(pattern))
+        .map_err(|error| Error::BadPattern { pattern: pattern.to_string(), error })?;
+    paths
+        .map(|path| path.map_err(|e| Error::ReadFailed { path: Some(e.path().to_path_buf()), source: e.into_error() }))
+        .collect()
+}
+
+/// `pattern` as the glob crate can expand it. It finds nothing below a
+/// verbatim UNC prefix, so `\\?\UNC\server\share` is written as
+/// `\\server\share`; paths longer than `MAX_PATH` still open, since the
+/// standard library adds the verbatim prefix back where needed.
+fn glob_pattern(pattern: &str) -> Cow<'_, str> {
+    #[cfg(windows)]
+    if let Some(rest) = pattern.strip_prefix(r"\\?\UNC\") {
+        return Cow::Owned(format!(r"\\{}", rest));
+    }
+    Cow::Borrowed(pattern)
+}
+
+/// Parses an SFV line (`filename CRC32`); the filename may contain spaces.
+/// Comment lines starting with `;` must be skipped by the caller.
+pub fn parse_sfv_line(line: &str) -> Result<ManifestEntry, ParseError> {
+    let (path, digest) = line.trim_end().rsplit_once([' ', '\t']).ok_or(ParseError::MissingSeparator)?;
+    let path = path.trim_end();
+    if path.is_empty() {
+        return Err(ParseError::MissingSeparator);
+    }
+    let digest_bytes = hex::decode(digest).map_err(|_| ParseError::InvalidDigest)?;
+
+    let entry = ManifestEntry {
+        digest: digest.to_string(),
+        digest_bytes,
+        path: path.to_string(),
+        binary: true,
+        algorithm: Some(HashAlgorithm::Crc32),
+    };
+    resolve_algorithm(&entry, None, &[])?;
+    Ok(entry)
+}
+
+/// Picks the algorithm to verify `entry` with: the line's own tag, then
+/// `explicit` (the `--algorithm` option), then a guess from the digest
+/// length that favors `preferred`.
+pub fn resolve_algorithm(
+    entry: &ManifestEntry,
+    explicit: Option<HashAlgorithm>,
+    preferred: &[HashAlgorithm],
+) -> Result<HashAlgorithm, ParseError> {
+    let len = entry.digest_bytes.len();
+    let algorithm = entry.algorithm
+        .or(explicit)
+        .or_else(|| HashAlgorithm::from_hex_len(len * 2, preferred))
+        .ok_or(ParseError::UnknownLength(len))?;
+
+    if algorithm.digest_size() != len {
+        return Err(ParseError::WrongLength { algorithm, len });
+    }
+    if !algorithm.is_available() {
+        return Err(ParseError::Unavailable(algorithm));
+    }
+    Ok(algorithm)
+}
+
+/// Escapes a filename the way GNU coreutils does: backslashes, newlines and
+/// carriage returns become `\\`, `\n` and `\r`. Returns `None` when the name
+/// needs no escaping; otherwise the output line must start with a backslash.
+pub fn escape_filename(name: &str) -> Option<String> {
+    if !name.contains(['\\', '\n', '\r']) {
+        return None;
+    }
+
+    let mut escaped = String::with_capacity(name.len() + 2);
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    Some(escaped)
+}
+
+/// Reverses [`escape_filename`]; returns `None` on an invalid escape sequence.
+pub fn unescape_filename(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+fn parse_tag_line(line: &str) -> Option<ManifestEntry> {
+    let (tag, path, digest) = line.split_once(" (")
+        .and_then(|(tag, rest)| Some((tag, rest.rsplit_once(") = ")?)))
+        .or_else(|| {
+            let (tag, rest) = line.split_once('(')?;
+            Some((tag, rest.rsplit_once(")= ")?))
+        })
+        .map(|(tag, (path, digest))| (tag, path, digest))?;
+    let algorithm = tag_algorithm(tag)?;
+
+    Some(ManifestEntry {
+        digest: digest.to_string(),
+        digest_bytes: Vec::new(),
+        path: path.to_string(),
+        binary: false,
+        algorithm: Some(algorithm),
+    })
+}
+
+/// Difference for one path between two manifests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Manifest contents keyed by path: the algorithm and lowercase digest.
+pub type Digests = BTreeMap<String, (HashAlgorithm, String)>;
+
+/// Compares two manifests by path, in path order. An entry whose algorithm
+/// changed counts as changed.
+pub fn diff(old: &Digests, new: &Digests) -> Vec<(String, Change)> {
+    let removed = old.keys()
+        .filter(|path| !new.contains_key(*path))
+        .map(|path| (path.clone(), Change::Removed));
+    let others = new.iter().filter_map(|(path, digest)| match old.get(path) {
+        None => Some((path.clone(), Change::Added)),
+        Some(old_digest) if old_digest != digest => Some((path.clone(), Change::Changed)),
+        Some(_) => None,
+    });
+
+    let mut changes: Vec<_> = removed.chain(others).collect();
+    changes.sort();
+    changes
+}
+
+/// Looks up the algorithm for a tag as written by GNU/BSD tools or by
+/// `openssl dgst`, which spells SHA-2 as `SHA2-256` and BLAKE2 with its size.
+fn tag_algorithm(tag: &str) -> Option<HashAlgorithm> {
+    HashAlgorithm::from_tag(tag).or_else(|| match tag.to_ascii_uppercase().as_str() {
+        "SHA2-224" => Some(HashAlgorithm::Sha224),
+        "SHA2-256" => Some(HashAlgorithm::Sha256),
+        "SHA2-384" => Some(HashAlgorithm::Sha384),
+        "SHA2-512" => Some(HashAlgorithm::Sha512),
+        "BLAKE2B-512" | "BLAKE2B512" => Some(HashAlgorithm::Blake2b),
+        "BLAKE2S-256" | "BLAKE2S256" => Some(HashAlgorithm::Blake2s),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_path_prefixes() {
+        assert!(!is_glob(r"\\?\C:\data\disk.img"));
+        assert!(is_glob(r"\\?\C:\data\*.img"));
+        assert!(!is_glob(r"\\server\share\disk.img"));
+        assert_eq!(glob_pattern(r"\\?\UNC\server\share\*.img"), r"\\server\share\*.img");
+        assert_eq!(glob_pattern(r"\\?\C:\data\*.img"), r"\\?\C:\data\*.img");
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "# made by sha-calc\n\nabcd  a.txt\nSHA1 (b.txt) = da39a3ee5e6b4b0d3255bfef95601890afd80709\n";
+        let entries = parse(text, false).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>(), ["a.txt", "b.txt"]);
+
+        let entries = parse("; crc\nabc.txt 352441C2\n", true).unwrap();
+        assert_eq!(entries[0].algorithm, Some(HashAlgorithm::Crc32));
+
+        match parse("abcd  a.txt\nno-separator-here\n", false) {
+            Err(Error::BadManifestLine { line: 2, error: ParseError::MissingSeparator }) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_default_line() {
+        let entry = parse_line("abcd  some file.txt").unwrap();
+        assert_eq!(entry.digest, "abcd");
+        assert_eq!(entry.path, "some file.txt");
+    }
+
+    #[test]
+    fn test_parse_binary_marker() {
+        let entry = parse_line("abcd *image.iso").unwrap();
+        assert_eq!(entry.path, "image.iso");
+        assert!(entry.binary);
+
+        let entry = parse_line("abcd  *starred").unwrap();
+        assert_eq!(entry.path, "*starred");
+        assert!(!entry.binary);
+    }
+
+    #[test]
+    fn test_parse_tag_line() {
+        let entry = parse_line("SHA256 (dir/a (1).txt) = abcd").unwrap();
+        assert_eq!(entry.digest, "abcd");
+        assert_eq!(entry.path, "dir/a (1).txt");
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Sha256));
+    }
+
+    #[test]
+    fn test_parse_tag_line_per_line_algorithm() {
+        let entry = parse_line("SHA1 (file) = abcd").unwrap();
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Sha1));
+
+        let entry = parse_line("abcd  file").unwrap();
+        assert_eq!(entry.algorithm, None);
+
+        // Unknown tags are not recognized; the line is then unusable
+        let entry = parse_line("MD5 (file) = abcd");
+        assert!(entry.and_then(|entry| resolve_algorithm(&entry, None, &[])).is_err());
+    }
+
+    #[test]
+    fn test_parse_openssl_line() {
+        let entry = parse_line("SHA2-256(dir/a (1).txt)= abcd").unwrap();
+        assert_eq!(entry.path, "dir/a (1).txt");
+        assert_eq!(entry.digest, "abcd");
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Sha256));
+
+        let entry = parse_line("SHA512(file)= abcd").unwrap();
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Sha512));
+
+        let entry = parse_line("BLAKE2b512(file)= abcd").unwrap();
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Blake2b));
+    }
+
+    #[test]
+    fn test_escape_filename() {
+        assert_eq!(escape_filename("plain name.txt"), None);
+        assert_eq!(escape_filename("a\\b\nc\rd").as_deref(), Some("a\\\\b\\nc\\rd"));
+    }
+
+    #[test]
+    fn test_parse_escaped_lines() {
+        let entry = parse_line("\\abcd  two\\nlines\\\\x").unwrap();
+        assert_eq!(entry.path, "two\nlines\\x");
+
+        let entry = parse_line("\\SHA256 (a\\nb) = abcd").unwrap();
+        assert_eq!(entry.path, "a\nb");
+
+        // Unescaped lines are taken literally
+        let entry = parse_line("abcd  dir\\file").unwrap();
+        assert_eq!(entry.path, "dir\\file");
+
+        assert_eq!(parse_line("\\abcd  bad\\escape"), Err(ParseError::InvalidEscape));
+    }
+
+    #[test]
+    fn test_parse_tolerant_separators() {
+        for line in ["abcd file", "abcd\tfile", "abcd  file\r", "abcd \tfile"] {
+            let entry = parse_line(line).unwrap();
+            assert_eq!(entry.path, "file", "line: {:?}", line);
+            assert!(!entry.binary);
+        }
+
+        let entry = parse_line("abcd\t*file").unwrap();
+        assert_eq!(entry.path, "file");
+        assert!(entry.binary);
+
+        // coreutils' two-space form still allows names starting with `*` or a space
+        assert_eq!(parse_line("abcd  *file").unwrap().path, "*file");
+        assert_eq!(parse_line("abcd   file").unwrap().path, " file");
+
+        assert!(is_comment("# generated by build.sh"));
+        assert!(is_comment("  #abcd  file"));
+        assert!(!is_comment("abcd  #file"));
+    }
+
+    #[test]
+    fn test_parse_malformed_line() {
+        assert_eq!(parse_line("abcd"), Err(ParseError::MissingSeparator));
+        assert_eq!(parse_line("abcd "), Err(ParseError::MissingSeparator));
+        assert_eq!(parse_line("abcd *"), Err(ParseError::MissingSeparator));
+        assert_eq!(parse_line("xy!z  file"), Err(ParseError::InvalidDigest));
+    }
+
+    #[test]
+    #[cfg(all(feature = "sha2", feature = "sha3"))]
+    fn test_is_manifest_name() {
+        assert!(is_manifest_name("sub/SHA256SUMS.sha256"));
+        assert!(is_manifest_name("disc1.SFV"));
+        assert!(is_manifest_name("release.sha3-512"));
+        assert!(!is_manifest_name("release.iso"));
+        assert!(!is_manifest_name("SHA256SUMS"));
+    }
+
+    #[test]
+    fn test_parse_sfv_line() {
+        let entry = parse_sfv_line("My Album - 01.flac 0D4A1185").unwrap();
+        assert_eq!(entry.path, "My Album - 01.flac");
+        assert_eq!(entry.digest, "0D4A1185");
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Crc32));
+
+        assert_eq!(parse_sfv_line("file.rar"), Err(ParseError::MissingSeparator));
+        assert_eq!(parse_sfv_line("file.rar 0D4A11"), Err(ParseError::WrongLength { algorithm: HashAlgorithm::Crc32, len: 3 }));
+        assert_eq!(parse_sfv_line("file.rar XYZ41185"), Err(ParseError::InvalidDigest));
+        assert!(is_sfv("release/Disc1.SFV"));
+        assert!(!is_sfv("SHA256SUMS"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let digests = |entries: &[(&str, HashAlgorithm, &str)]| -> Digests {
+            entries.iter()
+                .map(|&(path, alg, digest)| (path.to_string(), (alg, digest.to_string())))
+                .collect()
+        };
+        let old = digests(&[
+            ("same", HashAlgorithm::Sha256, "aa"),
+            ("edited", HashAlgorithm::Sha256, "bb"),
+            ("rehashed", HashAlgorithm::Sha1, "cc"),
+            ("deleted", HashAlgorithm::Sha256, "dd"),
+        ]);
+        let new = digests(&[
+            ("same", HashAlgorithm::Sha256, "aa"),
+            ("edited", HashAlgorithm::Sha256, "ee"),
+            ("rehashed", HashAlgorithm::Sha256, "cc"),
+            ("created", HashAlgorithm::Sha256, "ff"),
+        ]);
+
+        assert_eq!(diff(&old, &new), vec![
+            ("created".to_string(), Change::Added),
+            ("deleted".to_string(), Change::Removed),
+            ("edited".to_string(), Change::Changed),
+            ("rehashed".to_string(), Change::Changed),
+        ]);
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_parse_base64_and_sri_digests() {
+        // SHA-256 and SHA-384 of "hello world"
+        let sha256 = hex::decode("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9").unwrap();
+        let entry = parse_line("uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=  file").unwrap();
+        assert_eq!(entry.digest_bytes, sha256);
+        assert_eq!(entry.algorithm, None);
+        assert_eq!(resolve_algorithm(&entry, None, &[]), Ok(HashAlgorithm::Sha256));
+
+        let entry = parse_line("sha384-/b2OdaZ/KfcBpOBAOF4uI5hjA+oQI5IRr5B/y7g1eLPkF8txzmRu/QgZ3YwIjeG9  app.js").unwrap();
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Sha384));
+        assert_eq!(entry.digest_bytes.len(), 48);
+
+        // base64url may contain '-' without being an SRI string
+        let entry = parse_line("uU0nuZNNPgilLlLX2n2r-sSE7-N6U4DukIj3rOLvzek  file").unwrap();
+        assert_eq!(entry.digest_bytes, sha256);
+        assert_eq!(entry.algorithm, None);
+
+        assert_eq!(parse_line("md5-XrY7u+Ae7tCTyyK7j1rNww==  file"), Err(ParseError::InvalidDigest));
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_parse_digest() {
+        let entry = parse_digest(" B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9\n", "file.iso").unwrap();
+        assert_eq!(entry.digest_bytes.len(), 32);
+        assert_eq!(entry.path, "file.iso");
+        assert_eq!(resolve_algorithm(&entry, None, &[]), Ok(HashAlgorithm::Sha256));
+
+        let entry = parse_digest("sha512-MJ7MSJwS1utMxA9QyQLytNDtd+5RGnx6m808qG1M2G+YndNbxf9JlnDaNCVbRbDP2DDoH2Bdz33FVC6TrpzXbw==", "-").unwrap();
+        assert_eq!(entry.algorithm, Some(HashAlgorithm::Sha512));
+
+        assert_eq!(parse_digest("not a digest", "file"), Err(ParseError::InvalidDigest));
+    }
+
+    #[test]
+    fn test_reencode_digest() {
+        let digest = [0xb9, 0x4d, 0xff];
+        assert_eq!(reencode_digest("0011aa", &digest), "b94dff");
+        assert_eq!(reencode_digest("0011AA", &digest), "B94DFF");
+        assert_eq!(reencode_digest("sha256-AAAA", &digest), "sha256-uU3/");
+        assert_eq!(reencode_digest("AQID+w==", &[1]), "AQ==");
+        assert_eq!(reencode_digest("AQID+w", &[1]), "AQ");
+        assert_eq!(reencode_digest("AQID-w", &digest), "uU3_");
+    }
+
+    #[test]
+    #[cfg(all(feature = "sha1", feature = "sha2", feature = "blake2"))]
+    fn test_resolve_algorithm() {
+        let digest = "a".repeat(64);
+        let entry = parse_line(&format!("{}  file", digest)).unwrap();
+        assert_eq!(resolve_algorithm(&entry, None, &[]), Ok(HashAlgorithm::Sha256));
+        assert_eq!(resolve_algorithm(&entry, None, &[HashAlgorithm::Blake2s]), Ok(HashAlgorithm::Blake2s));
+        assert_eq!(
+            resolve_algorithm(&entry, Some(HashAlgorithm::Sha1), &[]),
+            Err(ParseError::WrongLength { algorithm: HashAlgorithm::Sha1, len: 32 })
+        );
+
+        let entry = parse_line("abcd  file").unwrap();
+        assert_eq!(resolve_algorithm(&entry, None, &[]), Err(ParseError::UnknownLength(2)));
+    }
+}
@@ -0,0 +1,182 @@
+//! minisign and OpenBSD signify signature verification for checksum manifests.
+//!
+//! Both tools share the key and signature layout: a two byte algorithm
+//! identifier, an 8 byte key ID and the Ed25519 key or signature, base64
+//! encoded below an `untrusted comment:` line. minisign adds a trusted
+//! comment covered by a second, global signature, and may sign the BLAKE2b-512
+//! hash of the file instead of the file itself.
+
+use anyhow::{Context, Result};
+use rustedbytes_sha::hash_bytes;
+
+use crate::ed25519;
+use crate::encoding;
+use crate::hasher::HashAlgorithm;
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment:";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+/// Signs the message itself (signify, legacy minisign).
+const ALG_PURE: [u8; 2] = *b"Ed";
+/// Signs the BLAKE2b-512 hash of the message (minisign default).
+const ALG_PREHASHED: [u8; 2] = *b"ED";
+
+pub struct PublicKey {
+    key_id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// Parses a public key file, or the bare base64 key as printed by `minisign -P`.
+    pub fn parse(text: &str) -> Result<PublicKey> {
+        // minisign prehashes with BLAKE2b
+        if !HashAlgorithm::Blake2b.is_available() {
+            anyhow::bail!("Signature verification needs the blake2 feature, which this build leaves out");
+        }
+        let line = data_lines(text).next().context("Public key is empty")?;
+        let bytes = encoding::decode_base64(line.trim())
+            .filter(|bytes| bytes.len() == 42 && bytes[..2] == ALG_PURE)
+            .context("Invalid minisign/signify public key")?;
+
+        Ok(PublicKey {
+            key_id: bytes[2..10].try_into().unwrap(),
+            key: bytes[10..].try_into().unwrap(),
+        })
+    }
+}
+
+pub struct Signature {
+    prehashed: bool,
+    key_id: [u8; 8],
+    signature: [u8; 64],
+    /// minisign's trusted comment and the global signature covering it.
+    trusted: Option<(String, [u8; 64])>,
+}
+
+impl Signature {
+    /// Parses a `.minisig` or signify `.sig` file.
+    pub fn parse(text: &str) -> Result<Signature> {
+        let mut lines = data_lines(text);
+        let bytes = lines.next()
+            .and_then(|line| encoding::decode_base64(line.trim()))
+            .filter(|bytes| bytes.len() == 74)
+            .context("Invalid minisign/signify signature")?;
+
+        let prehashed = match [bytes[0], bytes[1]] {
+            ALG_PURE => false,
+            ALG_PREHASHED => true,
+            _ => anyhow::bail!("Unsupported signature algorithm"),
+        };
+
+        let trusted = match lines.next() {
+            Some(line) => {
+                let comment = line.strip_prefix(TRUSTED_PREFIX)
+                    .context("Expected a trusted comment in the signature")?;
+                let global = lines.next()
+                    .and_then(|line| encoding::decode_base64(line.trim()))
+                    .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+                    .context("Invalid global signature")?;
+                Some((comment.to_string(), global))
+            },
+            None => None,
+        };
+
+        Ok(Signature {
+            prehashed,
+            key_id: bytes[2..10].try_into().unwrap(),
+            signature: bytes[10..].try_into().unwrap(),
+            trusted,
+        })
+    }
+
+    /// The trusted comment, if the signature was made by minisign.
+    pub fn trusted_comment(&self) -> Option<&str> {
+        self.trusted.as_ref().map(|(comment, _)| comment.as_str())
+    }
+
+    /// Verifies the signature of `message`, and of the trusted comment if present.
+    pub fn verify(&self, key: &PublicKey, message: &[u8]) -> Result<()> {
+        if self.key_id != key.key_id {
+            anyhow::bail!(
+                "Signature key ID {} does not match public key {}",
+                key_id_hex(&self.key_id), key_id_hex(&key.key_id)
+            );
+        }
+
+        let valid = if self.prehashed {
+            ed25519::verify(&key.key, hash_bytes(message, HashAlgorithm::Blake2b).as_bytes(), &self.signature)
+        } else {
+            ed25519::verify(&key.key, message, &self.signature)
+        };
+        if !valid {
+            anyhow::bail!("Signature verification failed");
+        }
+
+        if let Some((comment, global)) = &self.trusted {
+            let mut signed = self.signature.to_vec();
+            signed.extend_from_slice(comment.as_bytes());
+            if !ed25519::verify(&key.key, &signed, global) {
+                anyhow::bail!("Trusted comment signature verification failed");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Non-blank lines other than untrusted comments. Only line endings are
+/// stripped, since the trusted comment is signed verbatim.
+fn data_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with(UNTRUSTED_PREFIX))
+}
+
+/// Key IDs are displayed as big-endian hex of the little-endian stored value, like minisign.
+fn key_id_hex(key_id: &[u8; 8]) -> String {
+    key_id.iter().rev().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(all(test, feature = "blake2"))]
+mod tests {
+    use super::*;
+
+    // Key pair and signatures generated with the Ed25519 seed 00 01 02 .. 1f
+    const PUBLIC_KEY: &str = "untrusted comment: minisign public key 0807060504030201\nRWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4\n";
+    const MESSAGE: &[u8] = b"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  hello.txt\n";
+    const MINISIG: &str = "untrusted comment: signature from minisign secret key\nRUQBAgMEBQYHCH5MgZnQGmOvuwqpywgyxV6/AdW+9ivXJcC9z5TItNDyjxITLtqbh4tv8XWCwwtw/GRSzz9gnZqZEmoXrG0UaAU=\ntrusted comment: timestamp:1700000000\tfile:SHA256SUMS\nbfXkJKPcfXi2Qz8bGGUTb36ALQ3PUfCDUvTTWT9YWYCxXiYtjtDWMmmuaY2vwfjLI3Uq9DfVfLE09d0Jr6IWBQ==\n";
+    const SIGNIFY: &str = "untrusted comment: verify with test.pub\nRWQBAgMEBQYHCKvIyWA2dH0aA0jRWHoweMYI39CoUGrRWrcNZuDjo2pIrr6KDnrepOt5BNPlrTjjjhaas0KP8Ka4fozd00/2FAg=\n";
+
+    #[test]
+    fn test_minisign_prehashed_signature() {
+        let key = PublicKey::parse(PUBLIC_KEY).unwrap();
+        let signature = Signature::parse(MINISIG).unwrap();
+        assert_eq!(signature.trusted_comment(), Some("timestamp:1700000000\tfile:SHA256SUMS"));
+        signature.verify(&key, MESSAGE).unwrap();
+        assert!(signature.verify(&key, b"tampered").is_err());
+    }
+
+    #[test]
+    fn test_signify_signature() {
+        // A bare key, as printed by `minisign -P`, is accepted too
+        let key = PublicKey::parse("RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4").unwrap();
+        let signature = Signature::parse(SIGNIFY).unwrap();
+        assert_eq!(signature.trusted_comment(), None);
+        signature.verify(&key, MESSAGE).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_trusted_comment() {
+        let key = PublicKey::parse(PUBLIC_KEY).unwrap();
+        let signature = Signature::parse(&MINISIG.replace("1700000000", "1800000000")).unwrap();
+        let error = signature.verify(&key, MESSAGE).unwrap_err();
+        assert!(error.to_string().contains("Trusted comment"));
+    }
+
+    #[test]
+    fn test_key_id_mismatch() {
+        let key = PublicKey::parse("RWQICAgICAgICAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4").unwrap();
+        let signature = Signature::parse(SIGNIFY).unwrap();
+        let error = signature.verify(&key, MESSAGE).unwrap_err();
+        assert!(error.to_string().contains("does not match public key"));
+    }
+}
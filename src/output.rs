@@ -0,0 +1,269 @@
+use clap::ValueEnum;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub use rustedbytes_sha::format::{DigestStyle, Format, Printer, algorithm_name};
+use rustedbytes_sha::format::json_string;
+
+/// When to colorize terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Colorize when stdout is a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+/// ANSI colors used for check-mode statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Green,
+    Red,
+    Yellow,
+}
+
+/// Wraps `text` in the ANSI escape sequence for `color` when `enabled`.
+pub fn paint(text: &str, color: Color, enabled: bool) -> String {
+    if !enabled {
+        return text.to_string();
+    }
+
+    let code = match color {
+        Color::Green => 32,
+        Color::Red => 31,
+        Color::Yellow => 33,
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Format of the end-of-check summary selected with `--summary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SummaryFormat {
+    /// `12 OK, 1 FAILED, 0 missing, 0 unreadable`
+    Text,
+    /// A single JSON object with the same counters
+    Json,
+}
+
+/// Counters collected over a check run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckSummary {
+    pub ok: usize,
+    pub failed: usize,
+    pub missing: usize,
+    pub unreadable: usize,
+    pub improperly_formatted: usize,
+}
+
+impl CheckSummary {
+    pub fn render(&self, format: SummaryFormat) -> String {
+        match format {
+            SummaryFormat::Text => {
+                let mut text = format!(
+                    "{} OK, {} FAILED, {} missing, {} unreadable",
+                    self.ok, self.failed, self.missing, self.unreadable
+                );
+                if self.improperly_formatted > 0 {
+                    text.push_str(&format!(", {} improperly formatted", self.improperly_formatted));
+                }
+                text
+            },
+            SummaryFormat::Json => format!(
+                "{{\"ok\": {}, \"failed\": {}, \"missing\": {}, \"unreadable\": {}, \"improperly_formatted\": {}}}",
+                self.ok, self.failed, self.missing, self.unreadable, self.improperly_formatted
+            ),
+        }
+    }
+}
+
+/// Format of the progress records selected with `--progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// One JSON object per line with event, files_done, files_total,
+    /// bytes_done, bytes_total and current
+    Json,
+}
+
+/// Minimum time between two progress records; the final record is always written.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Progress of a check run, reported as JSON Lines for wrappers and GUIs.
+/// Totals grow as manifests are read.
+pub struct Progress<W: Write> {
+    out: W,
+    files_done: u64,
+    files_total: u64,
+    bytes_done: u64,
+    bytes_total: u64,
+    last_record: Option<Instant>,
+}
+
+impl<W: Write> Progress<W> {
+    pub fn new(out: W) -> Self {
+        Progress { out, files_done: 0, files_total: 0, bytes_done: 0, bytes_total: 0, last_record: None }
+    }
+
+    /// Adds files about to be checked or hashed, e.g. the entries of a manifest.
+    pub fn add_total(&mut self, files: u64, bytes: u64) {
+        self.files_total += files;
+        self.bytes_total += bytes;
+    }
+
+    /// Notes that `path` is being checked, writing a record unless one was
+    /// written less than [`PROGRESS_INTERVAL`] ago.
+    pub fn start_file(&mut self, path: &str) -> io::Result<()> {
+        self.update(path)
+    }
+
+    /// Notes that `bytes` more bytes of `path` were read, so that large files
+    /// and block devices report progress while they are hashed.
+    pub fn read(&mut self, path: &str, bytes: u64) -> io::Result<()> {
+        self.bytes_done += bytes;
+        self.update(path)
+    }
+
+    /// Notes that the current file has been checked.
+    pub fn finish_file(&mut self) {
+        self.files_done += 1;
+    }
+
+    fn update(&mut self, path: &str) -> io::Result<()> {
+        if self.last_record.is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL) {
+            return Ok(());
+        }
+        self.last_record = Some(Instant::now());
+        self.write_record("progress", Some(path))
+    }
+
+    /// Writes the final record.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.write_record("done", None)
+    }
+
+    fn write_record(&mut self, event: &str, current: Option<&str>) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "{{\"event\":{},\"files_done\":{},\"files_total\":{},\"bytes_done\":{},\"bytes_total\":{},\"current\":{}}}",
+            json_string(event), self.files_done, self.files_total, self.bytes_done, self.bytes_total,
+            current.map_or_else(|| "null".to_string(), json_string)
+        )?;
+        self.out.flush()
+    }
+}
+
+/// Formats a timestamp as an ISO 8601 UTC date-time (`2024-01-31T12:00:00Z`).
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    };
+
+    let (days, secs_of_day) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day,
+        secs_of_day / 3_600, secs_of_day % 3_600 / 60, secs_of_day % 60
+    )
+}
+
+/// Formats a byte count with binary units (`512 bytes`, `1.5 MiB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["bytes", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} bytes", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_summary() {
+        let mut summary = CheckSummary { ok: 1234, failed: 3, missing: 2, unreadable: 1, improperly_formatted: 0 };
+        assert_eq!(summary.render(SummaryFormat::Text), "1234 OK, 3 FAILED, 2 missing, 1 unreadable");
+
+        summary.improperly_formatted = 4;
+        assert_eq!(summary.render(SummaryFormat::Text), "1234 OK, 3 FAILED, 2 missing, 1 unreadable, 4 improperly formatted");
+        assert_eq!(
+            summary.render(SummaryFormat::Json),
+            r#"{"ok": 1234, "failed": 3, "missing": 2, "unreadable": 1, "improperly_formatted": 4}"#
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 bytes");
+        assert_eq!(format_size(1023), "1023 bytes");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 << 30), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("OK", Color::Green, true), "\x1b[32mOK\x1b[0m");
+        assert_eq!(paint("FAILED", Color::Red, true), "\x1b[31mFAILED\x1b[0m");
+        assert_eq!(paint("MISSING", Color::Yellow, false), "MISSING");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        use std::time::Duration;
+
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)), "2023-11-14T22:13:20Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH - Duration::from_secs(1)), "1969-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_progress_records() {
+        let mut progress = Progress::new(Vec::new());
+        progress.add_total(2, 30);
+        progress.start_file("a \"b\".txt").unwrap();
+        progress.read("a \"b\".txt", 10).unwrap();
+        progress.finish_file();
+        // Throttled: too soon after the previous record
+        progress.start_file("c.txt").unwrap();
+        progress.read("c.txt", 20).unwrap();
+        progress.finish_file();
+        progress.finish().unwrap();
+
+        let rendered = String::from_utf8(progress.out).unwrap();
+        assert_eq!(
+            rendered,
+            "{\"event\":\"progress\",\"files_done\":0,\"files_total\":2,\"bytes_done\":0,\"bytes_total\":30,\"current\":\"a \\\"b\\\".txt\"}\n\
+             {\"event\":\"done\",\"files_done\":2,\"files_total\":2,\"bytes_done\":30,\"bytes_total\":30,\"current\":null}\n"
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! Minimal QR code encoder (byte mode, error correction level M, versions 1–10)
+//! for printing digests to the terminal.
+
+/// Highest supported version; 213 bytes at level M, enough for any digest encoding.
+const MAX_VERSION: usize = 10;
+
+/// Error correction codewords per block at level M, indexed by version - 1.
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION] = [10, 16, 26, 18, 24, 16, 18, 22, 22, 26];
+
+/// Number of error correction blocks at level M, indexed by version - 1.
+const NUM_BLOCKS: [usize; MAX_VERSION] = [1, 1, 1, 2, 2, 4, 4, 4, 5, 5];
+
+/// Format information bits identifying level M.
+const LEVEL_M_BITS: u32 = 0b00;
+
+/// Width of the light border around the symbol, in modules.
+const QUIET_ZONE: usize = 4;
+
+pub struct QrCode {
+    size: usize,
+    modules: Vec<Vec<bool>>,
+    is_function: Vec<Vec<bool>>,
+}
+
+impl QrCode {
+    /// Encodes `data` in byte mode using the smallest version that fits, or
+    /// returns `None` if it does not fit in a version 10 symbol.
+    pub fn encode(data: &[u8]) -> Option<QrCode> {
+        let version = (1..=MAX_VERSION).find(|&v| data_bit_length(data.len(), v) <= num_data_codewords(v) * 8)?;
+
+        let mut bits = BitBuffer::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, char_count_bits(version));
+        for &byte in data {
+            bits.push(byte.into(), 8);
+        }
+
+        let capacity = num_data_codewords(version) * 8;
+        bits.push(0, (capacity - bits.len()).min(4));
+        bits.push(0, (8 - bits.len() % 8) % 8);
+        for &pad in [0xEC, 0x11].iter().cycle() {
+            if bits.len() >= capacity {
+                break;
+            }
+            bits.push(pad, 8);
+        }
+
+        let size = version * 4 + 17;
+        let mut qr = QrCode {
+            size,
+            modules: vec![vec![false; size]; size],
+            is_function: vec![vec![false; size]; size],
+        };
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&add_ecc_and_interleave(&bits.into_bytes(), version));
+
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                qr.apply_mask(mask);
+                qr.draw_format_bits(mask);
+                let penalty = qr.penalty_score();
+                qr.apply_mask(mask);
+                penalty
+            })
+            .unwrap_or(0);
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+
+        Some(qr)
+    }
+
+    /// Renders the symbol with Unicode half blocks, two module rows per line,
+    /// surrounded by a quiet zone. Light modules are drawn as blocks, so the
+    /// code reads correctly on the usual light-on-dark terminal.
+    pub fn render(&self) -> String {
+        let total = self.size + 2 * QUIET_ZONE;
+        let light = |x: usize, y: usize| {
+            x < QUIET_ZONE || y < QUIET_ZONE
+                || x >= self.size + QUIET_ZONE || y >= self.size + QUIET_ZONE
+                || !self.modules[y - QUIET_ZONE][x - QUIET_ZONE]
+        };
+
+        let mut text = String::new();
+        for y in (0..total).step_by(2) {
+            for x in 0..total {
+                let bottom = y + 1 < total && light(x, y + 1);
+                text.push(match (light(x, y), bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn set_function_module(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y][x] = dark;
+        self.is_function[y][x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        for i in 0..self.size {
+            self.set_function_module(6, i, i % 2 == 0);
+            self.set_function_module(i, 6, i % 2 == 0);
+        }
+
+        let far = self.size - 4;
+        for (x, y) in [(3, 3), (far, 3), (3, far)] {
+            self.draw_finder_pattern(x, y);
+        }
+
+        let positions = alignment_pattern_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Skip the three corners occupied by finder patterns
+                if !((i == 0 || i == last) && (j == 0 || j == last) && !(i == last && j == last)) {
+                    self.draw_alignment_pattern(x, y);
+                }
+            }
+        }
+
+        // Reserve the format areas; the real bits are drawn once a mask is chosen
+        self.draw_format_bits(0);
+        self.draw_version(version);
+    }
+
+    fn draw_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -4..=4_isize {
+            for dx in -4..=4_isize {
+                let (xx, yy) = (x as isize + dx, y as isize + dy);
+                if (0..self.size as isize).contains(&xx) && (0..self.size as isize).contains(&yy) {
+                    let dist = dx.abs().max(dy.abs());
+                    self.set_function_module(xx as usize, yy as usize, dist != 2 && dist != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, x: usize, y: usize) {
+        for dy in -2..=2_isize {
+            for dx in -2..=2_isize {
+                let dist = dx.abs().max(dy.abs());
+                self.set_function_module(x.wrapping_add_signed(dx), y.wrapping_add_signed(dy), dist != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let bits = format_bits(mask);
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+        let size = self.size;
+
+        for i in 0..6 {
+            self.set_function_module(8, i, bit(i));
+        }
+        self.set_function_module(8, 7, bit(6));
+        self.set_function_module(8, 8, bit(7));
+        self.set_function_module(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function_module(14 - i, 8, bit(i));
+        }
+
+        for i in 0..8 {
+            self.set_function_module(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function_module(8, size - 15 + i, bit(i));
+        }
+        self.set_function_module(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self, version: usize) {
+        if version < 7 {
+            return;
+        }
+
+        let mut rem = version as u32;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = (version as u32) << 12 | rem;
+
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function_module(a, b, dark);
+            self.set_function_module(b, a, dark);
+        }
+    }
+
+    /// Places the codewords in the zigzag pattern, two columns at a time from the right.
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let mut i = 0;
+        let mut right = self.size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..self.size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { self.size - 1 - vert } else { vert };
+                    if !self.is_function[y][x] && i < data.len() * 8 {
+                        self.modules[y][x] = (data[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// XORs the data modules with the given mask pattern; applying it twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !self.is_function[y][x] {
+                    self.modules[y][x] ^= true;
+                }
+            }
+        }
+    }
+
+    /// Scores the symbol with the four penalty rules of ISO/IEC 18004 §7.8.3.
+    fn penalty_score(&self) -> usize {
+        let size = self.size;
+        let at = |x: usize, y: usize, transpose: bool| {
+            if transpose { self.modules[x][y] } else { self.modules[y][x] }
+        };
+
+        let mut score = 0;
+        for transpose in [false, true] {
+            for y in 0..size {
+                // Runs of five or more modules of the same color
+                let mut run = 1;
+                for x in 1..size {
+                    if at(x, y, transpose) == at(x - 1, y, transpose) {
+                        run += 1;
+                        if run == 5 {
+                            score += 3;
+                        } else if run > 5 {
+                            score += 1;
+                        }
+                    } else {
+                        run = 1;
+                    }
+                }
+
+                // Finder-like 1:1:3:1:1 patterns with four light modules on either side
+                let dark = |x: isize| (0..size as isize).contains(&x) && at(x as usize, y, transpose);
+                for x in -4..size as isize {
+                    let core = [true, false, true, true, true, false, true]
+                        .iter()
+                        .enumerate()
+                        .all(|(i, &d)| dark(x + i as isize) == d);
+                    if core {
+                        let before = (1..=4).all(|i| !dark(x - i));
+                        let after = (7..11).all(|i| !dark(x + i));
+                        if before || after {
+                            score += 40;
+                        }
+                    }
+                }
+            }
+        }
+
+        // 2x2 blocks of the same color
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.modules[y][x];
+                if color == self.modules[y][x + 1] && color == self.modules[y + 1][x] && color == self.modules[y + 1][x + 1] {
+                    score += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light modules
+        let dark = self.modules.iter().flatten().filter(|&&m| m).count();
+        let total = size * size;
+        let deviation: usize = (dark * 20).abs_diff(total * 10);
+        score + deviation.div_ceil(total).saturating_sub(1) * 10
+    }
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bits: Vec<bool>,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, count: usize) {
+        self.bits.extend((0..count).rev().map(|i| (value >> i) & 1 != 0));
+    }
+
+    fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bits
+            .chunks(8)
+            .map(|chunk| chunk.iter().fold(0, |byte, &bit| byte << 1 | u8::from(bit)))
+            .collect()
+    }
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version < 10 { 8 } else { 16 }
+}
+
+fn data_bit_length(len: usize, version: usize) -> usize {
+    4 + char_count_bits(version) + len * 8
+}
+
+/// Modules available for data and error correction after the function patterns.
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize) -> usize {
+    num_raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version - 1] * NUM_BLOCKS[version - 1]
+}
+
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+
+    let num_align = version / 7 + 2;
+    let step = (version * 4 + num_align * 2 + 1) / (num_align * 2 - 2) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..num_align - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// The 15-bit BCH-protected format information for level M and `mask`.
+fn format_bits(mask: u32) -> u32 {
+    let data = LEVEL_M_BITS << 3 | mask;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    (data << 10 | rem) ^ 0x5412
+}
+
+/// Splits the data into blocks, appends Reed-Solomon codewords to each and
+/// interleaves the result.
+fn add_ecc_and_interleave(data: &[u8], version: usize) -> Vec<u8> {
+    let num_blocks = NUM_BLOCKS[version - 1];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version - 1];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut k = 0;
+    for i in 0..num_blocks {
+        let data_len = short_block_len - ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[k..k + data_len].to_vec();
+        k += data_len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend_from_slice(&ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            // Short blocks carry a placeholder byte where long blocks have their last data byte
+            if i != short_block_len - ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+/// Coefficients of the generator polynomial of the given degree, highest
+/// power first with the leading 1 omitted.
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor) {
+            *x ^= gf_multiply(y, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z = 0u8;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bits() {
+        // Level M, masks 0 and 5 (ISO/IEC 18004 Table C.1)
+        assert_eq!(format_bits(0), 0b101010000010010);
+        assert_eq!(format_bits(5), 0b100000011001110);
+    }
+
+    #[test]
+    fn test_data_codewords() {
+        assert_eq!(num_data_codewords(1), 16);
+        assert_eq!(num_data_codewords(5), 86);
+        assert_eq!(num_data_codewords(7), 124);
+        assert_eq!(num_data_codewords(10), 216);
+    }
+
+    #[test]
+    fn test_reed_solomon_codeword_divisible_by_generator() {
+        let data = b"hello world";
+        let divisor = reed_solomon_divisor(10);
+        let mut codeword = data.to_vec();
+        codeword.extend(reed_solomon_remainder(data, &divisor));
+
+        // The generator's roots are 2^0..2^9, so the codeword must vanish at each of them
+        let mut root = 1u8;
+        for _ in 0..10 {
+            let value = codeword.iter().fold(0u8, |acc, &c| gf_multiply(acc, root) ^ c);
+            assert_eq!(value, 0);
+            root = gf_multiply(root, 0x02);
+        }
+    }
+
+    #[test]
+    fn test_version_selection() {
+        // A SHA-256 hex digest needs version 5; SHA-512 needs version 8
+        assert_eq!(QrCode::encode(&[b'a'; 64]).unwrap().size, 37);
+        assert_eq!(QrCode::encode(&[b'a'; 128]).unwrap().size, 49);
+        assert!(QrCode::encode(&[b'a'; 300]).is_none());
+    }
+
+    #[test]
+    fn test_finder_patterns() {
+        let qr = QrCode::encode(b"b94d27b9934d3e08").unwrap();
+        let last = qr.size - 1;
+        for (x, y) in [(0, 0), (last - 6, 0), (0, last - 6)] {
+            assert!(qr.modules[y][x] && qr.modules[y + 6][x + 6]);
+            assert!(!qr.modules[y + 1][x + 1]);
+            assert!(qr.modules[y + 3][x + 3]);
+        }
+    }
+
+    #[test]
+    fn test_render_dimensions() {
+        let qr = QrCode::encode(b"abc").unwrap();
+        let text = qr.render();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), (21 + 8_usize).div_ceil(2));
+        assert!(lines.iter().all(|line| line.chars().count() == 29));
+        assert!(lines[0].chars().all(|c| c == '█'));
+    }
+}
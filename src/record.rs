@@ -0,0 +1,263 @@
+//! Per-file results, as produced by [`crate::hash_file_record`] and
+//! consumed by the output formats of `sha-calc`, and (with the `serde`
+//! feature) serialization of them and of algorithm names.
+
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::digest::Digest;
+use crate::error::Error;
+use crate::hasher::HashAlgorithm;
+
+/// The outcome of hashing one file: its digest, or the error that prevented
+/// hashing, along with what is known about the file. Digests stay raw here;
+/// each output format encodes them as it needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashRecord {
+    pub path: String,
+    pub algorithm: HashAlgorithm,
+    pub digest: Option<Digest>,
+    /// Number of bytes hashed.
+    pub size: Option<u64>,
+    /// Time spent reading and hashing.
+    pub duration: Option<Duration>,
+    /// Modification time, for inputs that have one.
+    pub mtime: Option<SystemTime>,
+    pub error: Option<String>,
+}
+
+impl HashRecord {
+    /// A successful result; `duration` and `mtime` are left for the caller.
+    pub fn new(path: impl Into<String>, digest: Digest, size: u64) -> HashRecord {
+        HashRecord {
+            path: path.into(),
+            algorithm: digest.algorithm(),
+            digest: Some(digest),
+            size: Some(size),
+            duration: None,
+            mtime: None,
+            error: None,
+        }
+    }
+
+    /// An input that could not be hashed.
+    pub fn failed(path: impl Into<String>, algorithm: HashAlgorithm, error: impl Into<String>) -> HashRecord {
+        HashRecord {
+            path: path.into(),
+            algorithm,
+            digest: None,
+            size: None,
+            duration: None,
+            mtime: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Hashes the file at `path` into a record with its size, modification time
+/// and how long hashing took. Failing to open or read the file is an error
+/// rather than a failed record, so it can be matched on.
+pub fn hash_file_record<P: AsRef<Path>>(path: P, algorithm: HashAlgorithm) -> Result<HashRecord, Error> {
+    let path = path.as_ref();
+    let start = Instant::now();
+    let mut size = 0;
+    let digest = crate::hash_file_with_progress(path, algorithm, |done, _| size = done)?;
+
+    let mut record = HashRecord::new(path.to_string_lossy(), digest, size);
+    record.duration = Some(start.elapsed());
+    record.mtime = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    Ok(record)
+}
+
+#[cfg(feature = "serde")]
+mod impls {
+    use std::fmt;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+    use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+    use super::HashRecord;
+    use crate::digest::Digest;
+    use crate::hasher::HashAlgorithm;
+
+    /// Algorithms are written under their command-line names (`sha3-256`).
+    impl Serialize for HashAlgorithm {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for HashAlgorithm {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HashAlgorithm, D::Error> {
+            struct AlgorithmVisitor;
+
+            impl Visitor<'_> for AlgorithmVisitor {
+                type Value = HashAlgorithm;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("a hash algorithm name such as \"sha256\"")
+                }
+
+                fn visit_str<E: de::Error>(self, value: &str) -> Result<HashAlgorithm, E> {
+                    value.parse().map_err(E::custom)
+                }
+            }
+
+            deserializer.deserialize_str(AlgorithmVisitor)
+        }
+    }
+
+    /// The fields of `sha-calc --json`, then the timing ones.
+    const FIELDS: &[&str] = &["algorithm", "digest", "path", "size", "error", "mtime", "duration"];
+
+    /// The digest is written in hex, the mtime as Unix seconds and the
+    /// duration as (fractional) seconds.
+    impl Serialize for HashRecord {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut record = serializer.serialize_struct("HashRecord", FIELDS.len())?;
+            record.serialize_field("algorithm", &self.algorithm)?;
+            record.serialize_field("digest", &self.digest.as_ref().map(Digest::to_hex))?;
+            record.serialize_field("path", &self.path)?;
+            record.serialize_field("size", &self.size)?;
+            record.serialize_field("error", &self.error)?;
+            record.serialize_field("mtime", &self.mtime.map(unix_seconds))?;
+            record.serialize_field("duration", &self.duration.map(|duration| duration.as_secs_f64()))?;
+            record.end()
+        }
+    }
+
+    fn unix_seconds(time: SystemTime) -> i64 {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        }
+    }
+
+    /// Missing optional fields read as `None`; unknown fields are ignored so
+    /// that newer output stays readable. Digests must be hex.
+    impl<'de> Deserialize<'de> for HashRecord {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HashRecord, D::Error> {
+            struct RecordVisitor;
+
+            impl<'de> Visitor<'de> for RecordVisitor {
+                type Value = HashRecord;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("a hash record")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<HashRecord, A::Error> {
+                    let (mut algorithm, mut digest, mut path, mut size, mut error) = (None, None::<String>, None, None, None);
+                    let (mut mtime, mut duration) = (None::<i64>, None::<f64>);
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            "algorithm" => algorithm = Some(map.next_value()?),
+                            "digest" => digest = map.next_value()?,
+                            "path" => path = Some(map.next_value()?),
+                            "size" => size = map.next_value()?,
+                            "error" => error = map.next_value()?,
+                            "mtime" => mtime = map.next_value()?,
+                            "duration" => duration = map.next_value()?,
+                            _ => {
+                                map.next_value::<IgnoredAny>()?;
+                            }
+                        }
+                    }
+
+                    let algorithm: HashAlgorithm = algorithm.ok_or_else(|| de::Error::missing_field("algorithm"))?;
+                    let digest = digest
+                        .map(|hex| {
+                            Digest::from_hex(algorithm, &hex)
+                                .ok_or_else(|| de::Error::custom(format!("invalid {} digest '{}'", algorithm.name(), hex)))
+                        })
+                        .transpose()?;
+                    let mtime = mtime.map(|secs| match u64::try_from(secs) {
+                        Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+                        Err(_) => UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()),
+                    });
+                    let duration = duration
+                        .map(|secs| Duration::try_from_secs_f64(secs).map_err(de::Error::custom))
+                        .transpose()?;
+                    Ok(HashRecord {
+                        path: path.ok_or_else(|| de::Error::missing_field("path"))?,
+                        algorithm,
+                        digest,
+                        size,
+                        duration,
+                        mtime,
+                        error,
+                    })
+                }
+            }
+
+            deserializer.deserialize_struct("HashRecord", FIELDS, RecordVisitor)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::collections::BTreeMap;
+
+        use serde::de::IntoDeserializer;
+        use serde::de::value::{Error, MapDeserializer};
+
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "sha3")]
+        fn test_algorithm_names() {
+            let alg = HashAlgorithm::deserialize("sha3-256".into_deserializer());
+            assert_eq!(alg, Ok::<_, Error>(HashAlgorithm::Sha3_256));
+            assert!(HashAlgorithm::deserialize(IntoDeserializer::<Error>::into_deserializer("md5")).is_err());
+        }
+
+        #[test]
+        fn test_record_from_map() {
+            let fields = BTreeMap::from([("algorithm", "crc32"), ("path", "abc.txt"), ("owner", "ignored")]);
+            let record = HashRecord::deserialize(MapDeserializer::<_, Error>::new(fields.into_iter())).unwrap();
+            assert_eq!(record, HashRecord {
+                path: "abc.txt".to_string(),
+                algorithm: HashAlgorithm::Crc32,
+                digest: None,
+                size: None,
+                duration: None,
+                mtime: None,
+                error: None,
+            });
+
+            let fields = BTreeMap::from([("algorithm", "crc32")]);
+            assert!(HashRecord::deserialize(MapDeserializer::<_, Error>::new(fields.into_iter())).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_bytes;
+
+    #[test]
+    fn test_constructors() {
+        let digest = hash_bytes(b"abc", HashAlgorithm::Crc32);
+        let record = HashRecord::new("abc.txt", digest.clone(), 3);
+        assert_eq!((record.algorithm, record.digest, record.size), (HashAlgorithm::Crc32, Some(digest), Some(3)));
+
+        let record = HashRecord::failed("gone.txt", HashAlgorithm::Crc32, "No such file or directory");
+        assert_eq!((record.digest, record.size), (None, None));
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_hash_file_record() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"hello world").unwrap();
+        let record = hash_file_record(file.path(), HashAlgorithm::Sha256).unwrap();
+        assert_eq!(record.digest, Some(hash_bytes(b"hello world", HashAlgorithm::Sha256)));
+        assert_eq!(record.size, Some(11));
+        assert!(record.duration.is_some() && record.mtime.is_some());
+        assert_eq!(record.path, file.path().to_string_lossy());
+
+        assert!(matches!(hash_file_record("/nonexistent/file", HashAlgorithm::Sha256), Err(Error::OpenFailed { .. })));
+    }
+}
@@ -0,0 +1,105 @@
+//! Downloading remote manifests, signatures and inputs with the system `curl`.
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+
+/// How many times an interrupted download is resumed before giving up.
+const MAX_RESUMES: u32 = 5;
+
+/// Whether `source` is a URL rather than a local path.
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+/// Downloads `url`, which must use HTTPS (redirects included).
+pub fn fetch(url: &str) -> Result<Vec<u8>> {
+    let output = curl(url, 0)?
+        .output()
+        .context("Failed to run curl, which is required to download remote manifests")?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to download {}: {}", url, message.trim());
+    }
+    Ok(output.stdout)
+}
+
+/// Downloads `url` without storing it, passing the body to `consume` piece by
+/// piece, and returns its size. A transfer that breaks after making progress
+/// is resumed where it stopped with a range request; curl refuses servers
+/// that ignore the range, so no byte is passed twice.
+pub fn stream(url: &str, consume: &mut dyn FnMut(&[u8])) -> Result<u64> {
+    let mut offset = 0u64;
+    let mut resumes = 0;
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let mut child = curl(url, offset)?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run curl, which is required to download remote files")?;
+
+        let started_at = offset;
+        let mut body = child.stdout.take().unwrap();
+        loop {
+            let n = body.read(&mut buffer).context("Failed to read from curl")?;
+            if n == 0 {
+                break;
+            }
+            consume(&buffer[..n]);
+            offset += n as u64;
+        }
+
+        let mut message = String::new();
+        child.stderr.take().unwrap().read_to_string(&mut message).ok();
+        let status = child.wait().context("Failed to run curl")?;
+        if status.success() {
+            return Ok(offset);
+        }
+        if offset == started_at || resumes == MAX_RESUMES {
+            anyhow::bail!("Failed to download {}: {}", url, message.trim());
+        }
+        resumes += 1;
+    }
+}
+
+/// A curl invocation for `url`, which must use HTTPS (redirects included),
+/// starting at byte `offset`.
+fn curl(url: &str, offset: u64) -> Result<Command> {
+    if !url.starts_with("https://") {
+        anyhow::bail!("Refusing to download over plain HTTP: {}", url);
+    }
+
+    let mut command = Command::new("curl");
+    command.args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--proto", "=https", "--proto-redir", "=https"]);
+    if offset > 0 {
+        command.args(["--continue-at", &offset.to_string()]);
+    }
+    command.arg("--")
+        .arg(url);
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/SHA256SUMS"));
+        assert!(is_url("http://example.com/SHA256SUMS"));
+        assert!(!is_url("SHA256SUMS"));
+        assert!(!is_url("/srv/https://mirror"));
+    }
+
+    #[test]
+    fn test_plain_http_is_refused() {
+        let error = fetch("http://example.com/SHA256SUMS").unwrap_err();
+        assert!(error.to_string().contains("plain HTTP"));
+        let error = stream("http://example.com/file.iso", &mut |_| {}).unwrap_err();
+        assert!(error.to_string().contains("plain HTTP"));
+    }
+}
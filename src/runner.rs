@@ -0,0 +1,366 @@
+//! Hashing or verifying a batch of paths in one call, as one `sha-calc`
+//! run would, with results as data.
+
+use std::io;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::format::{DigestStyle, Format, Printer};
+use crate::hasher::HashAlgorithm;
+use crate::manifest;
+use crate::record::{HashRecord, hash_file_record};
+use crate::verify::{VerificationReport, VerifyOptions, verify_manifest_with};
+use crate::walk::{self, FileType, Hidden, WalkOptions};
+
+/// The common options of `sha-calc` (`-a`, `-r`, `-c`, `--include`,
+/// `--exclude`, the directory walk filters and the structured output
+/// formats) over [`hash_file_record`] and [`verify_manifest_with`], for
+/// automation that would otherwise run the binary and parse its output.
+///
+/// ```
+/// use rustedbytes_sha::{Format, HashAlgorithm, Runner};
+///
+/// let output = Runner::new()
+///     .algorithm(HashAlgorithm::Crc32)
+///     .output(Format::Json)
+///     .run(["Cargo.toml"])?;
+/// assert!(output.is_success());
+/// assert_eq!(output.records[0].path, "Cargo.toml");
+/// assert!(output.text.starts_with("[\n  {\"algorithm\":\"crc32\""));
+/// # Ok::<(), rustedbytes_sha::Error>(())
+/// ```
+///
+/// Paths are selected, walked and ordered by the same code as in the binary.
+/// Unlike the binary, a runner never reads standard input (paths are
+/// always files, `-` included), prints nothing and always returns; options
+/// that prompt, watch files or write to a file descriptor are not offered.
+#[derive(Debug, Clone, Default)]
+pub struct Runner {
+    algorithm: Option<HashAlgorithm>,
+    recursive: bool,
+    check: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    walk: WalkOptions,
+    output: Option<Format>,
+}
+
+impl Runner {
+    /// A command with no options: digests of the files given, with the
+    /// default algorithm (SHA-256 when compiled in).
+    pub fn new() -> Runner {
+        Runner::default()
+    }
+
+    /// `-a`: the algorithm to hash with; in check mode, the algorithm for
+    /// digests of its length.
+    pub fn algorithm(mut self, algorithm: HashAlgorithm) -> Runner {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    /// `-r`: hash every regular file below directories given as paths.
+    pub fn recursive(mut self, recursive: bool) -> Runner {
+        self.recursive = recursive;
+        self
+    }
+
+    /// `-c`: the paths are manifests whose entries are verified.
+    pub fn check(mut self, check: bool) -> Runner {
+        self.check = check;
+        self
+    }
+
+    /// `--include`: only hash or check paths matching one of the patterns
+    /// given; below a directory, paths are relative to it.
+    pub fn include(mut self, pattern: &str) -> Runner {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// `--exclude`: skip paths matching this pattern, and directories whose
+    /// path with a trailing `/` matches it.
+    pub fn exclude(mut self, pattern: &str) -> Runner {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// `--hidden` and `--no-hidden`: which hidden files a walk lists.
+    pub fn hidden(mut self, hidden: Hidden) -> Runner {
+        self.walk.hidden = hidden;
+        self
+    }
+
+    /// `--max-depth`: only hash files at most `depth` levels below the
+    /// directories given.
+    pub fn max_depth(mut self, depth: usize) -> Runner {
+        self.walk.max_depth = Some(depth);
+        self
+    }
+
+    /// `--ext`: when walking directories, only hash files with one of the
+    /// extensions given.
+    pub fn ext(mut self, extension: &str) -> Runner {
+        self.walk.extensions.push(extension.to_string());
+        self
+    }
+
+    /// `--type`: when walking directories, only hash regular files or only
+    /// links to them.
+    pub fn file_type(mut self, file_type: FileType) -> Runner {
+        self.walk.file_type = Some(file_type);
+        self
+    }
+
+    /// `--min-size`: skip files smaller than `bytes`.
+    pub fn min_size(mut self, bytes: u64) -> Runner {
+        self.walk.min_size = Some(bytes);
+        self
+    }
+
+    /// `--max-size`: skip files larger than `bytes`.
+    pub fn max_size(mut self, bytes: u64) -> Runner {
+        self.walk.max_size = Some(bytes);
+        self
+    }
+
+    /// `--gitignore`: when walking directories, skip files ignored by
+    /// `.gitignore` files.
+    pub fn gitignore(mut self, gitignore: bool) -> Runner {
+        self.walk.gitignore = gitignore;
+        self
+    }
+
+    /// `--json`, `--csv` and the other structured formats: also render the
+    /// records as `sha-calc` writes them, into [`RunOutput::text`]. As with
+    /// the binary, which rejects these options with `-c`, check mode only
+    /// returns reports.
+    pub fn output(mut self, format: Format) -> Runner {
+        self.output = Some(format);
+        self
+    }
+
+    /// Hashes `paths`, or verifies them as manifests in check mode. Paths
+    /// with glob characters are expanded. Files that cannot be hashed get a
+    /// record with an error, and problems with listed files are reported
+    /// per entry; an error is returned for an invalid pattern, a directory
+    /// that cannot be listed or a manifest that cannot be read.
+    pub fn run<I, P>(&self, paths: I) -> Result<RunOutput, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let options = VerifyOptions {
+            algorithm: self.algorithm,
+            include: patterns(&self.include)?,
+            exclude: patterns(&self.exclude)?,
+            ..VerifyOptions::default()
+        };
+        let mut output = RunOutput::default();
+
+        if self.check {
+            for path in paths {
+                output.reports.push(verify_manifest_with(path, &options)?);
+            }
+            return Ok(output);
+        }
+
+        let walk = WalkOptions { prune: options.exclude.clone(), ..self.walk.clone() };
+        let mut inputs = Vec::new();
+        for path in paths {
+            let path = path.as_ref().to_string_lossy().into_owned();
+            if manifest::is_glob(&path) {
+                let matches = manifest::expand_glob(&path)?;
+                inputs.extend(matches.iter().map(|path| path.display().to_string()));
+            } else {
+                inputs.push(path);
+            }
+        }
+
+        let mut files = Vec::new();
+        for input in inputs {
+            let root = Path::new(&input);
+            if self.recursive && root.is_dir() {
+                let walked = walk::walk_files(root, &walk)
+                    .map_err(|source| Error::OpenFailed { path: root.to_path_buf(), source })?;
+                for file in walked {
+                    if options.is_selected(&file.strip_prefix(root).unwrap_or(&file).to_string_lossy()) {
+                        files.push(file.display().to_string());
+                    }
+                }
+            } else if options.is_selected(&input) && has_selected_size(root, &walk) {
+                files.push(input);
+            }
+        }
+        walk::sort_paths(&mut files);
+
+        let algorithm = self.algorithm.unwrap_or_default();
+        for file in files {
+            let record = hash_file_record(&file, algorithm)
+                .unwrap_or_else(|e| HashRecord::failed(file, algorithm, e.to_string()));
+            output.records.push(record);
+        }
+
+        if let Some(format) = self.output {
+            output.text = render(&output.records, format).expect("writing to memory cannot fail");
+        }
+        Ok(output)
+    }
+}
+
+/// The results of a [`Runner`] command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RunOutput {
+    /// One record per file hashed, in order; files that could not be hashed
+    /// have an error instead of a digest.
+    pub records: Vec<HashRecord>,
+    /// In check mode, one report per manifest, in order.
+    pub reports: Vec<VerificationReport>,
+    /// With [`Runner::output`], the records in that format, as `sha-calc`
+    /// would print them; empty otherwise.
+    pub text: String,
+}
+
+impl RunOutput {
+    /// Whether every file was hashed, and every manifest verified.
+    pub fn is_success(&self) -> bool {
+        self.records.iter().all(|record| record.error.is_none())
+            && self.reports.iter().all(VerificationReport::is_success)
+    }
+}
+
+fn patterns(patterns: &[String]) -> Result<Vec<glob::Pattern>, Error> {
+    patterns
+        .iter()
+        .map(|pattern| glob::Pattern::new(pattern).map_err(|error| Error::BadPattern { pattern: pattern.clone(), error }))
+        .collect()
+}
+
+/// `records` as [`Printer`] writes them in `format`.
+fn render(records: &[HashRecord], format: Format) -> io::Result<String> {
+    let mut text = Vec::new();
+    let mut printer = Printer::new(&mut text, format, DigestStyle::default())?;
+    for record in records {
+        printer.write_entry(record)?;
+    }
+    printer.finish()?;
+    Ok(String::from_utf8(text).expect("paths and errors of records are UTF-8"))
+}
+
+/// Whether a path given to [`Runner::run`] is within the size limits; files
+/// whose size cannot be read, directories and devices are kept, so that
+/// errors are still reported.
+fn has_selected_size(path: &Path, walk: &WalkOptions) -> bool {
+    std::fs::metadata(path).map_or(true, |meta| !meta.is_file() || walk.is_size_selected(meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_bytes;
+
+    #[test]
+    fn test_hash_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "hello world").unwrap();
+
+        let output = Runner::new().algorithm(HashAlgorithm::Crc32).run([&path]).unwrap();
+        assert_eq!(output.records.len(), 1);
+        assert_eq!(output.records[0].digest, Some(hash_bytes(b"hello world", HashAlgorithm::Crc32)));
+        assert!(output.is_success());
+
+        // "-" is a file name, not standard input; records are sorted by path
+        let output = Runner::new().run([path.as_path(), Path::new("/nonexistent/file"), Path::new("-")]).unwrap();
+        let paths: Vec<_> = output.records.iter().map(|record| record.path.as_str()).collect();
+        assert_eq!(paths, ["-", "/nonexistent/file", &*path.to_string_lossy()]);
+        assert_eq!(output.records[2].algorithm, HashAlgorithm::default());
+        assert!(output.records[0].error.is_some() && output.records[1].error.is_some());
+        assert!(!output.is_success());
+    }
+
+    #[test]
+    fn test_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs/api")).unwrap();
+        for name in ["b.txt", "a.log", "docs/api/c.txt", "docs/d.txt"] {
+            std::fs::write(dir.path().join(name), name).unwrap();
+        }
+
+        let output = Runner::new().recursive(true).exclude("*.log").exclude("docs/api/*").run([dir.path()]).unwrap();
+        let names: Vec<_> = output.records.iter()
+            .map(|record| Path::new(&record.path).strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["b.txt", "docs/d.txt"]);
+
+        // Without -r a directory cannot be hashed
+        let output = Runner::new().run([dir.path()]).unwrap();
+        assert!(output.records[0].error.is_some());
+    }
+
+    #[test]
+    fn test_walk_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        for (name, size) in [("x.iso", 1), (".y.iso", 1), ("z.txt", 1), ("big.iso", 100), ("a/w.iso", 1), ("a/b/v.iso", 1)] {
+            std::fs::write(dir.path().join(name), vec![0; size]).unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("x.iso"), dir.path().join("link.iso")).unwrap();
+
+        let names = |runner: Runner| -> Vec<String> {
+            runner.recursive(true).run([dir.path()]).unwrap().records.iter()
+                .map(|record| Path::new(&record.path).strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect()
+        };
+        let listed = names(Runner::new().hidden(Hidden::Skip).ext("ISO").max_depth(2).max_size(10));
+        if cfg!(unix) {
+            assert_eq!(listed, ["a/w.iso", "link.iso", "x.iso"]);
+            assert_eq!(names(Runner::new().file_type(FileType::Link)), ["link.iso"]);
+        } else {
+            assert_eq!(listed, ["a/w.iso", "x.iso"]);
+        }
+        assert_eq!(names(Runner::new().include("*.iso").exclude("a/**").min_size(10)), ["big.iso"]);
+    }
+
+    #[test]
+    fn test_output_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "hello world").unwrap();
+
+        let output = Runner::new().algorithm(HashAlgorithm::Crc32).output(Format::Csv).run([&path]).unwrap();
+        assert_eq!(output.text, format!("algorithm,digest,path,size,error\ncrc32,0d4a1185,{},11,\n", path.display()));
+        let output = Runner::new().algorithm(HashAlgorithm::Crc32).run([&path]).unwrap();
+        assert!(output.text.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_check_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let (good, bad) = (dir.path().join("good.txt"), dir.path().join("bad.txt"));
+        std::fs::write(&good, "hello world").unwrap();
+        std::fs::write(&bad, "tampered").unwrap();
+        let digest = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        let manifest = dir.path().join("SHA256SUMS");
+        std::fs::write(&manifest, format!("{0}  {1}\n{0}  {2}\n", digest, good.display(), bad.display())).unwrap();
+
+        let output = Runner::new().check(true).run([&manifest]).unwrap();
+        assert_eq!(output.reports[0].failures().count(), 1);
+        assert!(!output.is_success());
+
+        let output = Runner::new().check(true).exclude("*/bad.txt").run([&manifest]).unwrap();
+        assert!(output.is_success());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(Runner::new().include("[").run(["a"]), Err(Error::BadPattern { .. })));
+        assert!(matches!(
+            Runner::new().check(true).run(["/nonexistent/SHA256SUMS"]),
+            Err(Error::OpenFailed { .. })
+        ));
+    }
+}
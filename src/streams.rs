@@ -0,0 +1,92 @@
+//! Data attached to a file besides its contents, for `--streams`: extended
+//! attribute values on Linux and macOS, alternate data streams on NTFS.
+
+use std::io;
+use std::path::Path;
+
+/// The name and contents of each extended attribute or alternate data
+/// stream of `path`, sorted by name. Alternate data streams are named
+/// without their `:$DATA` type suffix.
+#[cfg(not(windows))]
+pub fn read_all(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut names = crate::xattr::list(path)?;
+    names.sort();
+
+    let mut streams = Vec::new();
+    for name in names {
+        // An attribute removed since it was listed is skipped
+        if let Some(value) = crate::xattr::get(path, &name)? {
+            streams.push((name, value));
+        }
+    }
+    Ok(streams)
+}
+
+#[cfg(windows)]
+pub fn read_all(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut streams = Vec::new();
+    for name in stream_names(path)? {
+        let mut stream_path = path.as_os_str().to_os_string();
+        stream_path.push(format!(":{}", name));
+        streams.push((name, std::fs::read(&stream_path)?));
+    }
+    streams.sort();
+    Ok(streams)
+}
+
+/// Names of the named `$DATA` streams of `path`, via `FindFirstStreamW`.
+#[cfg(windows)]
+fn stream_names(path: &Path) -> io::Result<Vec<String>> {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+
+    const ERROR_HANDLE_EOF: i32 = 38;
+    /// `FindStreamInfoStandard`
+    const INFO_LEVEL_STANDARD: i32 = 0;
+
+    /// `WIN32_FIND_STREAM_DATA`: `MAX_PATH + 36` UTF-16 units for the name
+    #[repr(C)]
+    struct FindStreamData {
+        _size: i64,
+        name: [u16; 296],
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn FindFirstStreamW(path: *const u16, level: i32, data: *mut c_void, flags: u32) -> *mut c_void;
+        fn FindNextStreamW(handle: *mut c_void, data: *mut c_void) -> i32;
+        fn FindClose(handle: *mut c_void) -> i32;
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut data = FindStreamData { _size: 0, name: [0; 296] };
+    let data_ptr = (&mut data as *mut FindStreamData).cast::<c_void>();
+
+    // SAFETY: the path is NUL-terminated and `data` is a WIN32_FIND_STREAM_DATA
+    let handle = unsafe { FindFirstStreamW(wide.as_ptr(), INFO_LEVEL_STANDARD, data_ptr, 0) };
+    if handle as isize == -1 {
+        let error = io::Error::last_os_error();
+        return if error.raw_os_error() == Some(ERROR_HANDLE_EOF) { Ok(Vec::new()) } else { Err(error) };
+    }
+
+    let mut names = Vec::new();
+    loop {
+        // Names look like `:name:$DATA`; the unnamed main stream is `::$DATA`
+        let len = data.name.iter().position(|&c| c == 0).unwrap_or(data.name.len());
+        let name = String::from_utf16_lossy(&data.name[..len]);
+        if let Some(name) = name.strip_prefix(':')
+            .and_then(|name| name.strip_suffix(":$DATA"))
+            .filter(|name| !name.is_empty())
+        {
+            names.push(name.to_string());
+        }
+
+        // SAFETY: `handle` is open and `data` is a WIN32_FIND_STREAM_DATA
+        if unsafe { FindNextStreamW(handle, data_ptr) } == 0 {
+            let error = io::Error::last_os_error();
+            // SAFETY: `handle` was returned by FindFirstStreamW
+            unsafe { FindClose(handle) };
+            return if error.raw_os_error() == Some(ERROR_HANDLE_EOF) { Ok(names) } else { Err(error) };
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Reader for tar archives (ustar, with GNU long names and pax extended
+//! headers), for `--archive`. Archives are read front to back as a stream:
+//! member contents are hashed or skipped as they go by, never stored.
+
+use std::io::{self, Read};
+
+use anyhow::{Context, Result};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Largest GNU long name or pax header read, against corrupt size fields.
+const MAX_EXTENSION_SIZE: u64 = 1024 * 1024;
+
+/// A regular file stored in an archive.
+#[derive(Debug)]
+pub struct Member {
+    /// Path inside the archive, as stored (`./` prefixes included).
+    pub path: String,
+    pub size: u64,
+    /// Modification time, in seconds since the Unix epoch.
+    pub mtime: u64,
+}
+
+impl Member {
+    /// Whether the member is stored at `path`, compared without leading `./`
+    /// on either side.
+    pub fn is(&self, path: &str) -> bool {
+        self.path.trim_start_matches("./") == path.trim_start_matches("./")
+    }
+}
+
+/// The regular files of a tar archive, in archive order. Directories, links
+/// and special files are skipped. After [`Archive::next_member`], reading
+/// from the archive reads the contents of that member.
+pub struct Archive<R> {
+    inner: R,
+    /// Bytes consumed from `inner`, for error messages
+    offset: u64,
+    /// Contents of the current member not yet read
+    remaining: u64,
+    /// Padding to the block boundary after the current member
+    padding: u64,
+}
+
+impl<R: Read> Archive<R> {
+    pub fn new(inner: R) -> Archive<R> {
+        Archive { inner, offset: 0, remaining: 0, padding: 0 }
+    }
+
+    /// Advances to the next regular file, skipping whatever is left of the
+    /// current one. `None` at the end of the archive.
+    pub fn next_member(&mut self) -> Result<Option<Member>> {
+        // Path set by a preceding GNU long name or pax header
+        let mut long_path: Option<String> = None;
+
+        loop {
+            self.skip_rest()?;
+            let pos = self.offset;
+            let mut header = [0u8; BLOCK_SIZE];
+            let len = read_block(&mut self.inner, &mut header).context("Failed to read from input")?;
+            self.offset += len as u64;
+            if len == 0 && long_path.is_some() {
+                anyhow::bail!("Truncated tar archive");
+            }
+            if len == 0 {
+                return Ok(None);
+            }
+            if len < BLOCK_SIZE {
+                anyhow::bail!("{}", if pos == 0 { "Not a tar archive" } else { "Truncated tar archive" });
+            }
+            if header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            if !checksum_matches(&header) {
+                anyhow::bail!("Not a tar archive, or corrupt header at offset {}", pos);
+            }
+
+            let (size, padded) = parse_number(&header[124..136])
+                .and_then(|size| Some((size, size.checked_next_multiple_of(BLOCK_SIZE as u64)?)))
+                .with_context(|| format!("Invalid size in tar header at offset {}", pos))?;
+            self.remaining = size;
+            self.padding = padded - size;
+
+            match header[156] {
+                b'L' => long_path = Some(String::from_utf8_lossy(field(&self.read_extension()?)).into_owned()),
+                b'x' => long_path = pax_path(&self.read_extension()?).or(long_path),
+                b'0' | b'\0' | b'7' => {
+                    let path = long_path.take().unwrap_or_else(|| header_path(&header));
+                    let mtime = parse_number(&header[136..148]).unwrap_or(0);
+                    return Ok(Some(Member { path, size, mtime }));
+                },
+                _ => long_path = None,
+            }
+        }
+    }
+
+    /// The contents of a long name or pax header entry.
+    fn read_extension(&mut self) -> Result<Vec<u8>> {
+        if self.remaining > MAX_EXTENSION_SIZE {
+            anyhow::bail!("Extended tar header of {} bytes at offset {} is too large", self.remaining, self.offset);
+        }
+        let mut data = Vec::new();
+        self.read_to_end(&mut data).context("Failed to read from input")?;
+        Ok(data)
+    }
+
+    /// Skips the unread contents of the current member and its padding.
+    fn skip_rest(&mut self) -> Result<()> {
+        let wanted = self.remaining + self.padding;
+        let skipped = io::copy(&mut (&mut self.inner).take(wanted), &mut io::sink())
+            .context("Failed to read from input")?;
+        self.offset += skipped;
+        if skipped < wanted {
+            anyhow::bail!("Truncated tar archive");
+        }
+        self.remaining = 0;
+        self.padding = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Archive<R> {
+    /// Reads the contents of the current member.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let read = self.inner.read(&mut buf[..len])?;
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated tar archive"));
+        }
+        self.remaining -= read as u64;
+        self.offset += read as u64;
+        Ok(read)
+    }
+}
+
+/// Fills `block` unless the input ends first, returning the bytes read.
+fn read_block(reader: &mut impl Read, block: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < block.len() {
+        match reader.read(&mut block[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+/// The header checksum: the byte sum of the header with the checksum field
+/// read as spaces.
+fn checksum_matches(header: &[u8]) -> bool {
+    let sum: u64 = header.iter().enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { u64::from(b' ') } else { u64::from(b) })
+        .sum();
+    parse_number(&header[148..156]) == Some(sum)
+}
+
+/// `name`, preceded by the ustar `prefix` field if present.
+fn header_path(header: &[u8]) -> String {
+    let name = String::from_utf8_lossy(field(&header[..100]));
+    let prefix = field(&header[345..500]);
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        format!("{}/{}", String::from_utf8_lossy(prefix), name)
+    } else {
+        name.into_owned()
+    }
+}
+
+/// A NUL-terminated header field.
+fn field(bytes: &[u8]) -> &[u8] {
+    bytes.iter().position(|&b| b == 0).map_or(bytes, |end| &bytes[..end])
+}
+
+/// Parses a numeric field: octal text, or GNU base-256 when the high bit of
+/// the first byte is set.
+fn parse_number(bytes: &[u8]) -> Option<u64> {
+    if bytes[0] & 0x80 != 0 {
+        return bytes[1..].iter().try_fold(u64::from(bytes[0] & 0x7f), |n, &b| {
+            n.checked_mul(256).map(|n| n | u64::from(b))
+        });
+    }
+    let text = std::str::from_utf8(field(bytes)).ok()?.trim_matches(' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// The `path` record of a pax extended header (`<len> path=<value>\n`).
+fn pax_path(mut records: &[u8]) -> Option<String> {
+    let mut path = None;
+    while !records.is_empty() {
+        let space = records.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&records[..space]).ok()?.parse().ok()?;
+        let record = records.get(space + 1..len)?.strip_suffix(b"\n")?;
+        if let Some(value) = record.strip_prefix(b"path=") {
+            path = Some(String::from_utf8_lossy(value).into_owned());
+        }
+        records = &records[len..];
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a header block; `name` longer than 100 bytes is truncated.
+    fn header(name: &str, kind: u8, size: usize) -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        let name = &name.as_bytes()[..name.len().min(100)];
+        block[..name.len()].copy_from_slice(name);
+        block[100..107].copy_from_slice(b"0000644");
+        block[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        block[136..147].copy_from_slice(b"14524770400");
+        block[156] = kind;
+        block[257..263].copy_from_slice(b"ustar\0");
+        block[263..265].copy_from_slice(b"00");
+        seal(&mut block);
+        block
+    }
+
+    /// Recomputes the checksum of a header block.
+    fn seal(block: &mut [u8]) {
+        block[148..156].fill(b' ');
+        let sum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+        block[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+    }
+
+    fn entry(archive: &mut Vec<u8>, name: &str, kind: u8, data: &[u8]) {
+        archive.extend(header(name, kind, data.len()));
+        archive.extend(data);
+        archive.resize(archive.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+    }
+
+    /// The members of `archive` with their contents.
+    fn members(archive: &[u8]) -> Result<Vec<(Member, Vec<u8>)>> {
+        let mut reader = Archive::new(archive);
+        let mut members = Vec::new();
+        while let Some(member) = reader.next_member()? {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            members.push((member, data));
+        }
+        Ok(members)
+    }
+
+    #[test]
+    fn test_members() {
+        let long_name = format!("{}/file.txt", "d".repeat(120));
+        let pax = format!("{} path=pax/name.txt\n", 21);
+        let mut archive = Vec::new();
+        entry(&mut archive, "dir/", b'5', b"");
+        entry(&mut archive, "dir/a.txt", b'0', b"hello world");
+        entry(&mut archive, "dir/link", b'2', b"");
+        entry(&mut archive, "././@LongLink", b'L', format!("{}\0", long_name).as_bytes());
+        entry(&mut archive, "truncated", b'0', b"");
+        entry(&mut archive, "PaxHeaders/x", b'x', pax.as_bytes());
+        entry(&mut archive, "short", b'0', &[7; 600]);
+        archive.extend([0u8; 2 * BLOCK_SIZE]);
+
+        let members = members(&archive).unwrap();
+        let paths: Vec<&str> = members.iter().map(|(m, _)| m.path.as_str()).collect();
+        assert_eq!(paths, ["dir/a.txt", long_name.as_str(), "pax/name.txt"]);
+        assert_eq!(members[0].1, b"hello world");
+        assert_eq!(members[0].0.mtime, 1_700_000_000);
+        assert_eq!(members[2].0.size, 600);
+        assert_eq!(members[2].1.len(), 600);
+    }
+
+    #[test]
+    fn test_unread_contents_are_skipped() {
+        let mut archive = Vec::new();
+        entry(&mut archive, "./dir/a.txt", b'0', &[1; 1000]);
+        entry(&mut archive, "dir/b.txt", b'0', b"b");
+
+        let mut reader = Archive::new(archive.as_slice());
+        let first = reader.next_member().unwrap().unwrap();
+        assert!(first.is("dir/a.txt"));
+        let mut start = [0u8; 10];
+        reader.read_exact(&mut start).unwrap();
+        let second = reader.next_member().unwrap().unwrap();
+        assert!(second.is("./dir/b.txt"));
+        assert!(!second.is("dir"));
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"b");
+        assert!(reader.next_member().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_archives() {
+        assert!(members(&[b'x'; BLOCK_SIZE]).is_err());
+        assert!(members(b"short text").unwrap_err().to_string().contains("Not a tar archive"));
+        assert!(members(b"").unwrap().is_empty());
+
+        let mut archive = Vec::new();
+        entry(&mut archive, "a.txt", b'0', &[1; 100]);
+        assert!(members(&archive[..BLOCK_SIZE + 50]).unwrap_err().to_string().contains("Truncated"));
+        let mut reader = Archive::new(&archive[..BLOCK_SIZE + 50]);
+        reader.next_member().unwrap();
+        assert!(reader.next_member().unwrap_err().to_string().contains("Truncated"));
+
+        let mut huge = header("././@LongLink", b'L', 0);
+        huge[124..136].copy_from_slice(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0x20, 0, 0]);
+        seal(&mut huge);
+        assert!(members(&huge).unwrap_err().to_string().contains("too large"));
+    }
+
+    #[test]
+    fn test_malformed_headers() {
+        // Sizes that overflow when padded to a block, or do not fit 64 bits
+        for size in [[0x80, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], [0xff; 12]] {
+            let mut block = header("a.txt", b'0', 0);
+            block[124..136].copy_from_slice(&size);
+            seal(&mut block);
+            assert!(members(&block).unwrap_err().to_string().contains("Invalid size"));
+        }
+        let mut block = header("a.txt", b'0', 0);
+        block[124..136].copy_from_slice(b"12345678901\0");
+        seal(&mut block);
+        assert!(members(&block).unwrap_err().to_string().contains("Invalid size"));
+
+        // Pax records with lengths that are zero, too long, too short or missing
+        for records in ["0 path=x\n", "99 path=x\n", "3 path=x\n", "path=x\n", "5 path=x\n"] {
+            let mut archive = Vec::new();
+            entry(&mut archive, "PaxHeaders/x", b'x', records.as_bytes());
+            entry(&mut archive, "a.txt", b'0', b"a");
+            let members = members(&archive).unwrap();
+            assert_eq!(members[0].0.path, "a.txt", "{:?}", records);
+        }
+
+        // A header whose checksum does not match, after a valid member
+        let mut archive = Vec::new();
+        entry(&mut archive, "a.txt", b'0', b"a");
+        entry(&mut archive, "b.txt", b'0', b"b");
+        archive[BLOCK_SIZE * 2 + 5] ^= 1;
+        assert!(members(&archive).unwrap_err().to_string().contains("corrupt header at offset 1024"));
+    }
+
+    #[test]
+    fn test_truncated_archives() {
+        let mut archive = Vec::new();
+        entry(&mut archive, "././@LongLink", b'L', b"long/name.txt\0");
+        entry(&mut archive, "a.txt", b'0', &[1; 700]);
+        entry(&mut archive, "PaxHeaders/x", b'x', b"21 path=pax/name.txt\n");
+        entry(&mut archive, "b.txt", b'0', b"b");
+
+        // Cut anywhere but at the end of a member, the archive is an error
+        let ends = [BLOCK_SIZE * 5, archive.len()];
+        for len in 1..archive.len() {
+            let result = members(&archive[..len]);
+            assert_eq!(result.is_ok(), ends.contains(&len), "{}", len);
+        }
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number(b"0000644\0"), Some(0o644));
+        assert_eq!(parse_number(b"   12 \0"), Some(0o12));
+        assert_eq!(parse_number(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]), Some(256));
+        assert_eq!(parse_number(b"abc\0"), None);
+    }
+}
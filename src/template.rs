@@ -0,0 +1,189 @@
+//! The `--format` output template language.
+//!
+//! Templates are plain text with `{placeholder}` fields; `{{` and `}}` produce
+//! literal braces and `\t`, `\n`, `\0` and `\\` are interpreted as escapes.
+
+use std::path::Path;
+
+use rustedbytes_sha::HashRecord;
+
+use crate::output::{self, DigestStyle};
+
+/// Fields that can be referenced from a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Digest,
+    Path,
+    Name,
+    Size,
+    Mtime,
+    Algorithm,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 6] = [
+        ("digest", Field::Digest),
+        ("path", Field::Path),
+        ("name", Field::Name),
+        ("size", Field::Size),
+        ("mtime", Field::Mtime),
+        ("algorithm", Field::Algorithm),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    Field(Field),
+}
+
+/// Layout used by `--long`: the path comes last so it may contain separators.
+pub const LONG_TEMPLATE: &str = "{digest}  {size}  {mtime}  {algorithm}  {path}";
+
+/// A parsed output template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    tokens: Vec<Token>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unterminated placeholder '{{{}'", name)),
+                        }
+                    }
+                    let field = Field::ALL.iter()
+                        .find(|(field_name, _)| *field_name == name)
+                        .map(|(_, field)| *field)
+                        .ok_or_else(|| format!(
+                            "unknown placeholder '{{{}}}' (expected one of: {})",
+                            name,
+                            Field::ALL.iter().map(|(field_name, _)| *field_name).collect::<Vec<_>>().join(", ")
+                        ))?;
+
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(Token::Field(field));
+                },
+                '}' => return Err("unmatched '}' (use '}}' for a literal brace)".to_string()),
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('0') => literal.push('\0'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    },
+                    None => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        Ok(Template { tokens })
+    }
+
+    /// Renders the template for a successfully hashed entry, writing its
+    /// digest in `style`; fields that are not available (e.g. the mtime of
+    /// stdin) render as `-`.
+    pub fn render(&self, entry: &HashRecord, style: &DigestStyle) -> String {
+        let mut rendered = String::new();
+        for token in &self.tokens {
+            match token {
+                Token::Literal(text) => rendered.push_str(text),
+                Token::Field(field) => {
+                    let value = match field {
+                        Field::Digest => entry.digest.as_ref().map(|digest| style.format(digest)),
+                        Field::Path => Some(entry.path.clone()),
+                        Field::Name => Path::new(&entry.path)
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned()),
+                        Field::Size => entry.size.map(|size| size.to_string()),
+                        Field::Mtime => entry.mtime.map(output::format_timestamp),
+                        Field::Algorithm => Some(output::algorithm_name(entry.algorithm)),
+                    };
+                    rendered.push_str(value.as_deref().unwrap_or("-"));
+                },
+            }
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::HashAlgorithm;
+    use rustedbytes_sha::Digest;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn entry() -> HashRecord {
+        HashRecord {
+            path: "dir/file.txt".to_string(),
+            algorithm: HashAlgorithm::Crc32,
+            digest: Digest::from_hex(HashAlgorithm::Crc32, "0000abcd"),
+            size: Some(42),
+            duration: None,
+            mtime: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_render_all_fields() {
+        let template = Template::parse("{algorithm}:{digest}  {path} ({name}) {size} {mtime}").unwrap();
+        assert_eq!(template.render(&entry(), &DigestStyle::default()), "crc32:0000abcd  dir/file.txt (file.txt) 42 2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_escapes_and_braces() {
+        let template = Template::parse("{{{digest}}}\\t{path}\\\\").unwrap();
+        assert_eq!(template.render(&entry(), &DigestStyle::default()), "{0000abcd}\tdir/file.txt\\");
+    }
+
+    #[test]
+    fn test_missing_fields_render_as_dash() {
+        let mut entry = entry();
+        entry.mtime = None;
+        let template = Template::parse("{mtime}").unwrap();
+        assert_eq!(template.render(&entry, &DigestStyle::default()), "-");
+    }
+
+    #[test]
+    fn test_long_template() {
+        let template = Template::parse(LONG_TEMPLATE).unwrap();
+        assert_eq!(template.render(&entry(), &DigestStyle::default()), "0000abcd  42  2023-11-14T22:13:20Z  crc32  dir/file.txt");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{bogus}").unwrap_err().contains("unknown placeholder '{bogus}'"));
+        assert!(Template::parse("{digest").unwrap_err().contains("unterminated"));
+        assert!(Template::parse("digest}").unwrap_err().contains("unmatched"));
+    }
+}
@@ -0,0 +1,419 @@
+//! Verifying the files a checksum manifest lists, as `sha-calc -c` does.
+
+use std::convert::Infallible;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::digest::Digest;
+use crate::error::Error;
+use crate::hasher::HashAlgorithm;
+use crate::manifest::{self, ManifestEntry, ParseError};
+
+/// How [`verify_manifest_with`] reads a manifest.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct VerifyOptions {
+    /// Like `-a`: the algorithm for lines whose digest length fits it; the
+    /// others are still detected line by line.
+    pub algorithm: Option<HashAlgorithm>,
+    /// Like `--prefer`: which algorithm a digest length ambiguous between
+    /// several stands for.
+    pub preferred: Vec<HashAlgorithm>,
+    /// The directory relative paths are resolved against. By default, that
+    /// is the manifest's own directory for SFV files and the current
+    /// directory otherwise, as with `sha-calc -c`.
+    pub base_dir: Option<PathBuf>,
+    /// Like `--glob-entries`: a listed path containing `*`, `?` or `[`
+    /// stands for every regular file it matches, possibly none.
+    pub glob_entries: bool,
+    /// Like `--require-glob-match`: a glob entry matching no file is missing.
+    pub require_match: bool,
+    /// Like `--include`: if not empty, only listed paths matching one of
+    /// these patterns are checked.
+    pub include: Vec<glob::Pattern>,
+    /// Like `--exclude`: listed paths matching any of these are skipped.
+    pub exclude: Vec<glob::Pattern>,
+}
+
+impl VerifyOptions {
+    /// Whether a listed path passes the include and exclude patterns.
+    pub fn is_selected(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(path)))
+            && !self.exclude.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+/// The outcome of checking one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryStatus {
+    Ok,
+    /// The file's digest differs from the listed one.
+    Failed { actual: Digest },
+    Missing,
+    /// The file could not be read; the message says why.
+    Unreadable(String),
+}
+
+/// One file checked against a manifest entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryReport {
+    /// Line of the entry in the manifest, counting from 1.
+    pub line: usize,
+    /// The path as listed.
+    pub path: String,
+    /// Where the file was read from.
+    pub location: String,
+    pub expected: Digest,
+    pub status: EntryStatus,
+}
+
+impl EntryReport {
+    pub fn is_ok(&self) -> bool {
+        self.status == EntryStatus::Ok
+    }
+}
+
+/// The result of verifying a manifest: a report for every file checked, in
+/// manifest order, and the lines that could not be parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+    pub entries: Vec<EntryReport>,
+    /// Improperly formatted lines (counting from 1) and why; like coreutils,
+    /// they are skipped rather than failing the manifest.
+    pub improper_lines: Vec<(usize, ParseError)>,
+    /// Number of properly formatted lines; none means the file is not a
+    /// manifest at all.
+    pub formatted_lines: usize,
+}
+
+impl VerificationReport {
+    /// Whether the manifest has usable lines and every file listed matched.
+    pub fn is_success(&self) -> bool {
+        self.formatted_lines > 0 && self.entries.iter().all(EntryReport::is_ok)
+    }
+
+    /// The files that did not match, were missing or could not be read.
+    pub fn failures(&self) -> impl Iterator<Item = &EntryReport> {
+        self.entries.iter().filter(|entry| !entry.is_ok())
+    }
+}
+
+/// Parses the manifest at `path` (any format `sha-calc -c` reads: GNU and
+/// BSD lines, SRI digests, and SFV files by their `.sfv` extension) and
+/// checks every file it lists.
+///
+/// ```no_run
+/// let report = rustedbytes_sha::verify_manifest("SHA256SUMS")?;
+/// for entry in report.failures() {
+///     eprintln!("{}: {:?}", entry.path, entry.status);
+/// }
+/// # Ok::<(), rustedbytes_sha::Error>(())
+/// ```
+///
+/// Only reading the manifest itself is an error; problems with the files
+/// it lists are reported per entry.
+pub fn verify_manifest<P: AsRef<Path>>(path: P) -> Result<VerificationReport, Error> {
+    verify_manifest_with(path, &VerifyOptions::default())
+}
+
+/// Like [`verify_manifest`], with the choices of [`VerifyOptions`].
+pub fn verify_manifest_with<P: AsRef<Path>>(path: P, options: &VerifyOptions) -> Result<VerificationReport, Error> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|source| Error::OpenFailed { path: path.to_path_buf(), source })?;
+    let text = String::from_utf8(data).map_err(|e| Error::ReadFailed {
+        path: Some(path.to_path_buf()),
+        source: io::Error::new(io::ErrorKind::InvalidData, e),
+    })?;
+
+    let sfv = manifest::is_sfv(&path.to_string_lossy());
+    let base_dir = match &options.base_dir {
+        Some(dir) => Some(dir.clone()),
+        None if sfv => path.parent().map(Path::to_path_buf),
+        None => None,
+    };
+    let options = VerifyOptions { base_dir, ..options.clone() };
+    match verify_text_with(&text, sfv, &options, &mut LocalFiles) {
+        Ok(report) => Ok(report),
+        Err(never) => match never {},
+    }
+}
+
+/// What [`verify_text_with`] needs from its caller: `sha-calc -c` reads
+/// remote files, reports progress and prints each result as soon as it is
+/// known.
+pub trait VerifyHooks {
+    /// The error [`VerifyHooks::checked`] stops verification with.
+    type Error;
+
+    /// The digest of the file listed as `path`, read from `location`; by
+    /// default with [`crate::hash_file`]. A `NotFound` error makes the
+    /// entry [`EntryStatus::Missing`], any other [`EntryStatus::Unreadable`].
+    fn digest(&mut self, path: &str, location: &str, algorithm: HashAlgorithm) -> io::Result<Digest> {
+        let _ = path;
+        crate::hash_file(location, algorithm).map_err(|e| {
+            let kind = e.io_error().map_or(io::ErrorKind::Other, io::Error::kind);
+            io::Error::new(kind, e.to_string())
+        })
+    }
+
+    /// Called with each result, before the next file is read.
+    fn checked(&mut self, line: &str, entry: &ManifestEntry, report: &EntryReport) -> Result<(), Self::Error>;
+}
+
+/// Files on disk, collected into the report only.
+struct LocalFiles;
+
+impl VerifyHooks for LocalFiles {
+    type Error = Infallible;
+
+    fn checked(&mut self, _line: &str, _entry: &ManifestEntry, _report: &EntryReport) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Like [`verify_manifest_with`], for a manifest already read (from stdin or
+/// a download, say), `.sfv` style if `sfv` is set; relative paths are
+/// resolved against [`VerifyOptions::base_dir`] only. Blank and comment
+/// lines are skipped.
+pub fn verify_text_with<H: VerifyHooks>(
+    text: &str,
+    sfv: bool,
+    options: &VerifyOptions,
+    hooks: &mut H,
+) -> Result<VerificationReport, H::Error> {
+    let mut report = VerificationReport::default();
+    for (index, line) in text.lines().enumerate() {
+        let comment = if sfv { line.starts_with(';') } else { manifest::is_comment(line) };
+        if line.trim().is_empty() || comment {
+            continue;
+        }
+
+        let (entry, algorithm) = match parse_entry(line, sfv, options) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                report.improper_lines.push((index + 1, e));
+                continue;
+            },
+        };
+        report.formatted_lines += 1;
+
+        let expected = Digest::from_finalized(algorithm, entry.digest_bytes.clone());
+        let result = |path: &str, location: &str, status| EntryReport {
+            line: index + 1,
+            path: path.to_string(),
+            location: location.to_string(),
+            expected: expected.clone(),
+            status,
+        };
+
+        let files = if entry.path == "-" {
+            Err("cannot check stdin".to_string())
+        } else {
+            entry_files(&entry.path, options).map_err(|e| e.to_string())
+        };
+        let files = match files {
+            Ok(files) => files,
+            Err(message) => {
+                let result = result(&entry.path, &entry.path, EntryStatus::Unreadable(message));
+                hooks.checked(line, &entry, &result)?;
+                report.entries.push(result);
+                continue;
+            },
+        };
+
+        if files.is_empty() && options.require_match {
+            let result = result(&entry.path, &entry.path, EntryStatus::Missing);
+            hooks.checked(line, &entry, &result)?;
+            report.entries.push(result);
+        }
+
+        for (path, location) in &files {
+            if !options.is_selected(path) {
+                continue;
+            }
+            let status = match hooks.digest(path, location, algorithm) {
+                Ok(digest) if digest.as_bytes() == entry.digest_bytes => EntryStatus::Ok,
+                Ok(digest) => EntryStatus::Failed { actual: digest },
+                Err(e) if e.kind() == io::ErrorKind::NotFound => EntryStatus::Missing,
+                Err(e) => EntryStatus::Unreadable(e.to_string()),
+            };
+            let result = result(path, location, status);
+            hooks.checked(line, &entry, &result)?;
+            report.entries.push(result);
+        }
+    }
+    Ok(report)
+}
+
+/// The files a manifest entry listing `path` stands for, as (listed path,
+/// location) pairs, before the include and exclude patterns apply: a
+/// relative path is resolved against [`VerifyOptions::base_dir`] (URLs are
+/// left as they are), and with [`VerifyOptions::glob_entries`] a glob
+/// pattern stands for every regular file it matches.
+pub fn entry_files(path: &str, options: &VerifyOptions) -> Result<Vec<(String, String)>, Error> {
+    let url = path.contains("://");
+    let location = match &options.base_dir {
+        Some(dir) if !url => dir.join(path).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    };
+
+    if !(options.glob_entries && !url && manifest::is_glob(path)) {
+        return Ok(vec![(path.to_string(), location)]);
+    }
+
+    let mut files = Vec::new();
+    for file in manifest::expand_glob(&location)? {
+        if file.is_dir() {
+            continue;
+        }
+        let listed = options.base_dir.as_deref()
+            .and_then(|dir| file.strip_prefix(dir).ok())
+            .unwrap_or(&file);
+        files.push((listed.display().to_string(), file.display().to_string()));
+    }
+    Ok(files)
+}
+
+/// Parses a line of a regular or SFV manifest and picks the algorithm to
+/// verify it with.
+pub fn parse_entry(line: &str, sfv: bool, options: &VerifyOptions) -> Result<(ManifestEntry, HashAlgorithm), ParseError> {
+    if sfv {
+        return manifest::parse_sfv_line(line).map(|entry| (entry, HashAlgorithm::Crc32));
+    }
+    let entry = manifest::parse_line(line)?;
+    // The explicit algorithm applies to the lines whose digest length fits
+    // it, so one manifest can mix algorithms
+    let explicit = options.algorithm.filter(|algorithm| algorithm.digest_size() == entry.digest_bytes.len());
+    let algorithm = manifest::resolve_algorithm(&entry, explicit, &options.preferred)?;
+    Ok((entry, algorithm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_bytes;
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_verify_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let (good, bad) = (dir.path().join("good.txt"), dir.path().join("bad.txt"));
+        std::fs::write(&good, "hello world").unwrap();
+        std::fs::write(&bad, "tampered").unwrap();
+        let digest = hash_bytes(b"hello world", HashAlgorithm::Sha256);
+        let missing = dir.path().join("missing.txt");
+        let manifest = dir.path().join("SHA256SUMS");
+        let text = format!(
+            "# comment\n{0}  {1}\n{0}  {2}\nabcd  short.txt\n{0}  {3}\n",
+            digest.to_hex(), good.display(), bad.display(), missing.display()
+        );
+        std::fs::write(&manifest, text).unwrap();
+
+        let report = verify_manifest(&manifest).unwrap();
+        let statuses: Vec<_> = report.entries.iter().map(|entry| (entry.line, entry.status.clone())).collect();
+        assert_eq!(statuses, [
+            (2, EntryStatus::Ok),
+            (3, EntryStatus::Failed { actual: hash_bytes(b"tampered", HashAlgorithm::Sha256) }),
+            (5, EntryStatus::Missing),
+        ]);
+        assert_eq!(report.entries[0].expected, digest);
+        assert_eq!(report.entries[1].path, bad.to_string_lossy());
+        assert_eq!(report.improper_lines, [(4, ParseError::UnknownLength(2))]);
+        assert_eq!(report.formatted_lines, 3);
+        assert_eq!(report.failures().count(), 2);
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_sfv_relative_to_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "hello world").unwrap();
+        let manifest = dir.path().join("files.sfv");
+        std::fs::write(&manifest, "; comment\na.txt 0D4A1185\n").unwrap();
+
+        let report = verify_manifest(&manifest).unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.entries[0].location, dir.path().join("a.txt").to_string_lossy());
+        assert_eq!(report.entries[0].expected.algorithm(), HashAlgorithm::Crc32);
+    }
+
+    #[test]
+    #[cfg(all(feature = "sha2", feature = "sha3"))]
+    fn test_options() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "hello world").unwrap();
+        let digest = hash_bytes(b"hello world", HashAlgorithm::Sha3_256);
+        let manifest = dir.path().join("SUMS");
+        std::fs::write(&manifest, format!("{}  a.txt\n", digest.to_hex())).unwrap();
+
+        let mut options = VerifyOptions { base_dir: Some(dir.path().to_path_buf()), ..VerifyOptions::default() };
+        let report = verify_manifest_with(&manifest, &options).unwrap();
+        assert!(matches!(report.entries[0].status, EntryStatus::Failed { .. }));
+
+        options.preferred = vec![HashAlgorithm::Sha3_256];
+        assert!(verify_manifest_with(&manifest, &options).unwrap().is_success());
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_glob_entries_and_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["app.1.log", "app.2.log", "app.3.log"] {
+            std::fs::write(dir.path().join(name), "hello world").unwrap();
+        }
+        let digest = hash_bytes(b"hello world", HashAlgorithm::Sha256).to_hex();
+        let text = format!("{0}  app.*.log\n{0}  old.*.log\n", digest);
+
+        let mut options = VerifyOptions {
+            base_dir: Some(dir.path().to_path_buf()),
+            glob_entries: true,
+            exclude: vec![glob::Pattern::new("*.3.log").unwrap()],
+            ..VerifyOptions::default()
+        };
+        let report = verify_text_with(&text, false, &options, &mut LocalFiles).unwrap();
+        let paths: Vec<_> = report.entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, ["app.1.log", "app.2.log"]);
+        assert!(report.is_success());
+
+        options.require_match = true;
+        let report = verify_text_with(&text, false, &options, &mut LocalFiles).unwrap();
+        assert_eq!(report.entries[2].path, "old.*.log");
+        assert_eq!(report.entries[2].status, EntryStatus::Missing);
+    }
+
+    #[test]
+    fn test_hooks() {
+        /// Every file reads as "hello world"; stops at the first failure.
+        struct Fake(Vec<String>);
+
+        impl VerifyHooks for Fake {
+            type Error = String;
+
+            fn digest(&mut self, _path: &str, _location: &str, algorithm: HashAlgorithm) -> io::Result<Digest> {
+                Ok(hash_bytes(b"hello world", algorithm))
+            }
+
+            fn checked(&mut self, _line: &str, _entry: &ManifestEntry, report: &EntryReport) -> Result<(), String> {
+                self.0.push(report.path.clone());
+                if report.is_ok() { Ok(()) } else { Err(report.path.clone()) }
+            }
+        }
+
+        let digest = hash_bytes(b"hello world", HashAlgorithm::Crc32).to_hex();
+        let text = format!("a {0}\nb {1}\nc {0}\n", digest, "0".repeat(8));
+        let mut hooks = Fake(Vec::new());
+        assert_eq!(verify_text_with(&text, true, &VerifyOptions::default(), &mut hooks), Err("b".to_string()));
+        assert_eq!(hooks.0, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_not_a_manifest() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"hello\n").unwrap();
+        let report = verify_manifest(file.path()).unwrap();
+        assert_eq!((report.formatted_lines, report.improper_lines.len()), (0, 1));
+        assert!(!report.is_success());
+
+        assert!(matches!(verify_manifest("/nonexistent/SHA256SUMS"), Err(Error::OpenFailed { .. })));
+    }
+}
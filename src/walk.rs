@@ -0,0 +1,299 @@
+//! Recursive directory traversal for `--recursive`, with the filters of
+//! the command line, as used by `sha-calc` and [`Runner`](crate::Runner).
+
+use clap::ValueEnum;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::gitignore::Gitignore;
+
+/// Kind of file selected with `--type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileType {
+    /// Regular files
+    #[value(name = "f")]
+    File,
+    /// Symbolic links to regular files
+    #[value(name = "l")]
+    Link,
+}
+
+impl FileType {
+    /// Whether `path`, a regular file or a link to one, is of this type.
+    pub fn matches(self, path: &Path) -> bool {
+        let is_link = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink());
+        is_link == (self == FileType::Link)
+    }
+}
+
+/// Whether the extension of `path` is one of `extensions` (without the dot,
+/// compared case-insensitively).
+pub fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy();
+        extensions.iter().any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+    })
+}
+
+/// Which hidden files and directories a walk lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Hidden {
+    /// All of them (`--hidden`).
+    Include,
+    /// All but Windows protected system files (the default).
+    #[default]
+    SkipSystem,
+    /// None: no dotfiles, and on Windows no files with the hidden attribute
+    /// (`--no-hidden`).
+    Skip,
+}
+
+/// The filters of a directory walk, as set by `--hidden`, `--max-depth`,
+/// `--ext`, `--type`, `--min-size`, `--max-size` and `--gitignore`. The
+/// default lists every file but protected system files.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct WalkOptions {
+    pub hidden: Hidden,
+    /// Only files at most this many levels below the root (1: the files
+    /// directly inside it).
+    pub max_depth: Option<usize>,
+    /// Only files with one of these extensions (see [`has_extension`]).
+    pub extensions: Vec<String>,
+    pub file_type: Option<FileType>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Skip files ignored by `.gitignore` files, and `.git` directories.
+    pub gitignore: bool,
+    /// Directories whose path relative to the root, with a trailing `/`,
+    /// matches one of these are not entered: the `--exclude` patterns, so
+    /// that `node_modules/**` prunes the whole directory.
+    pub prune: Vec<glob::Pattern>,
+}
+
+impl WalkOptions {
+    /// Whether a file of `size` bytes is within `min_size` and `max_size`.
+    pub fn is_size_selected(&self, size: u64) -> bool {
+        self.min_size.is_none_or(|min| size >= min) && self.max_size.is_none_or(|max| size <= max)
+    }
+
+    /// Whether a walk leaves out `path` as hidden.
+    fn skips_hidden(&self, path: &Path) -> bool {
+        match self.hidden {
+            Hidden::Include => false,
+            Hidden::SkipSystem => is_protected_system_file(path),
+            Hidden::Skip => is_hidden(path),
+        }
+    }
+
+    /// Whether the directory at `dir`, relative to the root, must not be entered.
+    fn is_pruned(&self, dir: &Path) -> bool {
+        let dir = format!("{}/", dir.to_string_lossy());
+        self.prune.iter().any(|pattern| pattern.matches(&dir))
+    }
+}
+
+/// Lists the regular files below `root` that pass the filters of `options`,
+/// as [`walk`] does; filtered out directories are not entered.
+pub fn walk_files(root: &Path, options: &WalkOptions) -> io::Result<Vec<PathBuf>> {
+    let ignore = options.gitignore.then(|| Gitignore::new(root));
+    let limit_size = options.min_size.is_some() || options.max_size.is_some();
+    walk(root, &|relative, is_dir| {
+        // Files in a directory at depth N are at depth N + 1
+        let depth = relative.components().count() + usize::from(is_dir);
+        (is_dir && options.is_pruned(relative))
+            || options.skips_hidden(&root.join(relative))
+            || (!is_dir && !options.extensions.is_empty() && !has_extension(relative, &options.extensions))
+            || (!is_dir && options.file_type.is_some_and(|file_type| !file_type.matches(&root.join(relative))))
+            || (!is_dir && limit_size && fs::metadata(root.join(relative))
+                .is_ok_and(|meta| !options.is_size_selected(meta.len())))
+            || options.max_depth.is_some_and(|max_depth| depth > max_depth)
+            || ignore.as_ref().is_some_and(|ignore| ignore.is_ignored(relative, is_dir))
+    })
+}
+
+/// Sorts paths by their bytes (UTF-8, so by code point), never by locale
+/// collation: `B` before `a`, `a-b` before `a/b` before `a0`. Windows
+/// separators order like `/`, so a tree lists the same way on every system.
+pub fn sort_paths(paths: &mut [String]) {
+    if cfg!(windows) {
+        paths.sort_by_cached_key(|path| path.replace('\\', "/"));
+    } else {
+        paths.sort();
+    }
+}
+
+/// Lists the regular files below `root`, depth first in name order.
+/// Symbolic links to files are included; links to directories are not
+/// followed, so link cycles cannot cause endless recursion. Entries for
+/// which `skip` returns true, given their path relative to `root` and whether
+/// they are directories, are left out; skipped directories are not entered.
+pub fn walk(root: &Path, skip: &dyn Fn(&Path, bool) -> bool) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    walk_into(root, root, skip, &mut files)?;
+    Ok(files)
+}
+
+fn walk_into(root: &Path, dir: &Path, skip: &dyn Fn(&Path, bool) -> bool, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", dir.display(), e)))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if file_type.is_dir() {
+            if !skip(relative, true) {
+                walk_into(root, &path, skip, files)?;
+            }
+        } else if (file_type.is_file() || (file_type.is_symlink() && path.is_file())) && !skip(relative, false) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` is hidden: a dotfile, or on Windows a file or directory
+/// with the hidden attribute.
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) || has_attributes(path, FILE_ATTRIBUTE_HIDDEN)
+}
+
+/// Whether `path` is a protected operating system file, which Windows
+/// Explorer hides by default (`desktop.ini`, `System Volume Information`):
+/// hidden and system attributes both set. Never true on other platforms.
+pub fn is_protected_system_file(path: &Path) -> bool {
+    has_attributes(path, FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM)
+}
+
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+#[cfg(windows)]
+fn has_attributes(path: &Path, attributes: u32) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.file_attributes() & attributes == attributes)
+}
+
+#[cfg(not(windows))]
+fn has_attributes(_path: &Path, _attributes: u32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_lists_files_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("b/c")).unwrap();
+        fs::create_dir(dir.path().join("empty")).unwrap();
+        for name in ["z.txt", "a.txt", "b/c/deep.txt", "b/y.txt"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+
+        let files: Vec<PathBuf> = walk(dir.path(), &|_, _| false).unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        let expected: Vec<PathBuf> = ["a.txt", "b/c/deep.txt", "b/y.txt", "z.txt"].iter().map(PathBuf::from).collect();
+        assert_eq!(files, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_does_not_follow_directory_links() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("file.txt"), "data").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("file.txt"), dir.path().join("link.txt")).unwrap();
+
+        let files = walk(dir.path(), &|_, _| false).unwrap();
+        assert_eq!(files, vec![dir.path().join("file.txt"), dir.path().join("link.txt")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_survives_symlink_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a")).unwrap();
+        fs::create_dir_all(dir.path().join("b")).unwrap();
+        fs::write(dir.path().join("a/file.txt"), "data").unwrap();
+        // a/to_b -> b and b/to_a -> a, a link to itself and a dangling link
+        std::os::unix::fs::symlink("../b", dir.path().join("a/to_b")).unwrap();
+        std::os::unix::fs::symlink("../a", dir.path().join("b/to_a")).unwrap();
+        std::os::unix::fs::symlink("self", dir.path().join("self")).unwrap();
+        std::os::unix::fs::symlink("nowhere", dir.path().join("dangling")).unwrap();
+
+        let files = walk(dir.path(), &|_, _| false).unwrap();
+        assert_eq!(files, vec![dir.path().join("a/file.txt")]);
+    }
+
+    #[test]
+    fn test_walk_files_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/deep")).unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        for (name, size) in [("a.iso", 10), ("b.ISO", 100), ("c.txt", 10), (".hidden.iso", 10), ("src/d.iso", 10),
+                             ("src/deep/e.iso", 10), ("target/f.iso", 10)] {
+            fs::write(dir.path().join(name), vec![0; size]).unwrap();
+        }
+        let listed = |options: &WalkOptions| -> Vec<String> {
+            walk_files(dir.path(), options).unwrap().iter()
+                .map(|path| path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect()
+        };
+
+        let mut options = WalkOptions {
+            hidden: Hidden::Skip,
+            extensions: vec!["iso".to_string()],
+            max_depth: Some(2),
+            max_size: Some(50),
+            prune: vec![glob::Pattern::new("target/**").unwrap()],
+            ..WalkOptions::default()
+        };
+        assert_eq!(listed(&options), ["a.iso", "src/d.iso"]);
+
+        options.hidden = Hidden::Include;
+        assert_eq!(listed(&options), [".hidden.iso", "a.iso", "src/d.iso"]);
+    }
+
+    #[test]
+    fn test_sort_paths() {
+        let mut paths: Vec<String> = ["a0", "b", "a/b", "\u{e9}", "B", "a-b", "a/B", "a"].map(String::from).to_vec();
+        sort_paths(&mut paths);
+        assert_eq!(paths, ["B", "a", "a-b", "a/B", "a/b", "a0", "b", "\u{e9}"]);
+    }
+
+    #[test]
+    fn test_has_extension() {
+        let extensions = ["iso".to_string(), ".QCOW2".to_string()];
+        assert!(has_extension(Path::new("images/disk.ISO"), &extensions));
+        assert!(has_extension(Path::new("vm.qcow2"), &extensions));
+        assert!(!has_extension(Path::new("disk.img"), &extensions));
+        assert!(!has_extension(Path::new("iso"), &extensions));
+    }
+
+    #[test]
+    fn test_dotfiles_are_hidden() {
+        assert!(is_hidden(Path::new("project/.git")));
+        assert!(is_hidden(Path::new(".env")));
+        assert!(!is_hidden(Path::new("dir.d/file.txt")));
+        assert!(!is_protected_system_file(Path::new(".env")));
+    }
+
+    #[test]
+    fn test_walk_prunes_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("src/main.js"), "").unwrap();
+        fs::write(dir.path().join("src/node_modules/pkg/index.js"), "").unwrap();
+
+        let files = walk(dir.path(), &|rel, is_dir| is_dir && rel.ends_with("node_modules")).unwrap();
+        assert_eq!(files, vec![dir.path().join("src/main.js")]);
+    }
+}
@@ -0,0 +1,176 @@
+//! WebAssembly interface to the library, exported with the `wasm` feature
+//! from a module built for `wasm32-unknown-unknown`; `js/sha_calc.js` wraps
+//! it as `hashBytes(algorithm, data, encoding)`.
+//!
+//! The exports only pass numbers, so no binding generator is needed: the
+//! caller copies its strings and bytes into buffers from
+//! [`sha_calc_alloc`], and [`sha_calc_hash`] writes the encoded digest to an
+//! output buffer and returns its length, or a negative `SHA_CALC_ERR_*`
+//! code.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use core::{ptr, slice, str};
+
+use crate::encoding::{self, Encoding};
+use crate::hasher::HashAlgorithm;
+use crate::hash_bytes;
+
+/// The algorithm name is unknown, or its support was not compiled in.
+pub const SHA_CALC_ERR_ALGORITHM: i32 = -1;
+/// A required pointer is null, or the encoding name is unknown.
+pub const SHA_CALC_ERR_ARGUMENT: i32 = -2;
+/// `out_len` is smaller than the encoded digest.
+pub const SHA_CALC_ERR_BUFFER: i32 = -3;
+
+/// Longest encoded digest of any algorithm and encoding (a 64-byte digest
+/// as proquints), rounded up: an output buffer this large always suffices.
+pub const MAX_TEXT_LEN: usize = 256;
+
+/// A zeroed buffer of `len` bytes in the module's memory, to be released
+/// with [`sha_calc_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn sha_calc_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()).cast()
+}
+
+/// Releases a buffer from [`sha_calc_alloc`].
+///
+/// # Safety
+///
+/// `buffer` and `len` must come from one call to [`sha_calc_alloc`], and
+/// the buffer must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha_calc_free(buffer: *mut u8, len: usize) {
+    // SAFETY: the caller guarantees the buffer came from sha_calc_alloc(len)
+    drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)) });
+}
+
+/// Hashes `data_len` bytes at `data` with the algorithm named `algorithm`
+/// (as for `sha-calc -a`) and writes the digest, encoded as named by
+/// `encoding` (`hex`, `base64`, `base32`, `zbase32` or `proquint`), to
+/// `out`. Names are UTF-8 and not NUL-terminated.
+///
+/// # Safety
+///
+/// Each pointer must be valid for its length; `data` may be null if
+/// `data_len` is 0.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha_calc_hash(
+    algorithm: *const u8,
+    algorithm_len: usize,
+    encoding: *const u8,
+    encoding_len: usize,
+    data: *const u8,
+    data_len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    let inputs = unsafe { (bytes(algorithm, algorithm_len), bytes(encoding, encoding_len), bytes(data, data_len)) };
+    let (Some(algorithm), Some(encoding), Some(data)) = inputs else {
+        return SHA_CALC_ERR_ARGUMENT;
+    };
+    let Some(algorithm) = str::from_utf8(algorithm).ok().and_then(|name| name.parse::<HashAlgorithm>().ok()) else {
+        return SHA_CALC_ERR_ALGORITHM;
+    };
+    let Some(encoding) = str::from_utf8(encoding).ok().and_then(parse_encoding) else {
+        return SHA_CALC_ERR_ARGUMENT;
+    };
+
+    let text = encoding::encode(hash_bytes(data, algorithm).as_bytes(), encoding);
+    if out.is_null() {
+        return SHA_CALC_ERR_ARGUMENT;
+    }
+    if out_len < text.len() {
+        return SHA_CALC_ERR_BUFFER;
+    }
+    // SAFETY: `out` holds at least `text.len()` bytes and cannot overlap a
+    // string this function owns
+    unsafe { out.copy_from_nonoverlapping(text.as_ptr(), text.len()) };
+    text.len() as i32
+}
+
+/// # Safety
+///
+/// `data` must be null or valid for `len` bytes.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        // SAFETY: the caller guarantees `data` is valid for `len` bytes
+        Some(unsafe { slice::from_raw_parts(data, len) })
+    }
+}
+
+/// The encoding named as for `sha-calc --encoding`, ignoring case.
+fn parse_encoding(name: &str) -> Option<Encoding> {
+    [
+        ("hex", Encoding::Hex),
+        ("base64", Encoding::Base64),
+        ("base32", Encoding::Base32),
+        ("zbase32", Encoding::Zbase32),
+        ("proquint", Encoding::Proquint),
+    ]
+    .into_iter()
+    .find(|(known, _)| known.eq_ignore_ascii_case(name))
+    .map(|(_, encoding)| encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(algorithm: &str, encoding: &str, data: &[u8], out_len: usize) -> Result<String, i32> {
+        let out = sha_calc_alloc(out_len);
+        let len = unsafe {
+            sha_calc_hash(algorithm.as_ptr(), algorithm.len(), encoding.as_ptr(), encoding.len(), data.as_ptr(), data.len(), out, out_len)
+        };
+        let text = (len >= 0).then(|| String::from_utf8(unsafe { slice::from_raw_parts(out, len as usize) }.to_vec()).unwrap());
+        unsafe { sha_calc_free(out, out_len) };
+        text.ok_or(len)
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash("sha256", "hex", b"hello world", MAX_TEXT_LEN).unwrap(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+        assert_eq!(hash("SHA256", "Base64", b"hello world", MAX_TEXT_LEN).unwrap(), "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=");
+        assert_eq!(hash("crc32", "hex", b"", 8).unwrap(), "00000000");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(hash("md5", "hex", b"", MAX_TEXT_LEN), Err(SHA_CALC_ERR_ALGORITHM));
+        assert_eq!(hash("sha256", "base58", b"", MAX_TEXT_LEN), Err(SHA_CALC_ERR_ARGUMENT));
+        assert_eq!(hash("sha256", "hex", b"", 63), Err(SHA_CALC_ERR_BUFFER));
+        let len = unsafe { sha_calc_hash(c"sha1".as_ptr().cast(), 4, c"hex".as_ptr().cast(), 3, ptr::null(), 1, ptr::null_mut(), 0) };
+        assert_eq!(len, SHA_CALC_ERR_ARGUMENT);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_every_digest_fits_the_output_buffer() {
+        use clap::ValueEnum;
+
+        for algorithm in HashAlgorithm::value_variants() {
+            for encoding in Encoding::value_variants() {
+                let text = encoding::encode(&vec![0xff; algorithm.digest_size()], *encoding);
+                assert!(text.len() <= MAX_TEXT_LEN, "{:?} {:?}", algorithm, encoding);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrapper_matches() {
+        let wrapper = include_str!("../js/sha_calc.js");
+        for code in [SHA_CALC_ERR_ALGORITHM, SHA_CALC_ERR_ARGUMENT, SHA_CALC_ERR_BUFFER] {
+            assert!(wrapper.contains(&format!("[{}]:", code)), "{}", code);
+        }
+        assert!(wrapper.contains(&format!("const MAX_TEXT_LEN = {};", MAX_TEXT_LEN)));
+        for export in ["sha_calc_alloc(", "sha_calc_free(", "sha_calc_hash("] {
+            assert!(wrapper.contains(export), "{}", export);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! A writer that hashes what passes through it.
+
+use std::io::{self, Write};
+
+use crate::digest::Digest;
+use crate::hasher::{HashAlgorithm, MultiHasher, StreamingHasher};
+
+/// Wraps a writer and hashes every byte written through it, so a file or
+/// response body can be checksummed while it is written instead of read
+/// back afterwards.
+///
+#[cfg_attr(feature = "sha2", doc = "```")]
+#[cfg_attr(not(feature = "sha2"), doc = "```ignore")]
+/// use std::io::Write;
+/// use rustedbytes_sha::{HashAlgorithm, HashingWriter};
+///
+/// let mut writer = HashingWriter::new(Vec::new(), HashAlgorithm::Sha256);
+/// writer.write_all(b"hello world").unwrap();
+/// let (data, digest) = writer.finalize();
+/// assert_eq!(data, b"hello world");
+/// assert_eq!(digest.to_hex(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+/// ```
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: StreamingHasher,
+    written: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W, algorithm: HashAlgorithm) -> HashingWriter<W> {
+        HashingWriter::with_hasher(inner, StreamingHasher::new(algorithm))
+    }
+
+    /// Like [`HashingWriter::new`], but continues from `hasher`, for instance
+    /// one already fed a header that is not written out.
+    pub fn with_hasher(inner: W, hasher: StreamingHasher) -> HashingWriter<W> {
+        HashingWriter { inner, hasher, written: 0 }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The wrapped writer. Bytes written to it directly are not hashed.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Number of bytes written through so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Returns the wrapped writer, unflushed, and the digest of everything
+    /// it accepted.
+    pub fn finalize(self) -> (W, Digest) {
+        (self.inner, self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Only what the inner writer accepted counts; the rest is retried
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A [`MultiHasher`] is itself a sink, for copying a reader into all of its
+/// hashers at once with `io::copy`.
+impl Write for MultiHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts at most 3 bytes per call.
+    struct Trickle(Vec<u8>);
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_partial_writes_are_hashed_once() {
+        let mut writer = HashingWriter::new(Trickle(Vec::new()), HashAlgorithm::Crc32);
+        writer.write_all(b"hello ").unwrap();
+        writer.write_all(b"world").unwrap();
+        assert_eq!(writer.bytes_written(), 11);
+
+        let (inner, digest) = writer.finalize();
+        assert_eq!(inner.0, b"hello world");
+        assert_eq!(digest.to_hex(), "0d4a1185");
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn test_multi_hasher_sink() {
+        let mut hasher = MultiHasher::new(&[HashAlgorithm::Sha1, HashAlgorithm::Crc32]);
+        io::copy(&mut &b"hello world"[..], &mut hasher).unwrap();
+        let digests = hasher.finalize();
+        assert_eq!(digests[0].to_hex(), "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed");
+        assert_eq!(digests[1].to_hex(), "0d4a1185");
+    }
+
+    #[test]
+    fn test_nothing_written() {
+        let (_, digest) = HashingWriter::new(io::sink(), HashAlgorithm::Crc32).finalize();
+        assert_eq!(digest.as_bytes(), [0, 0, 0, 0]);
+    }
+}
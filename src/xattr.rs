@@ -0,0 +1,121 @@
+//! Minimal extended-attribute access for the shatag/cshatag convention
+//! (`user.shatag.<algorithm>` holding the hex digest, `user.shatag.ts` the
+//! mtime it was computed at) and for hashing attribute values with `--streams`.
+
+use std::io;
+use std::path::Path;
+
+/// Reads the extended attribute `name` of `path`, or `None` if it is not set.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn get(path: &Path, name: &str) -> io::Result<Option<Vec<u8>>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let c_name = CString::new(name)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut buffer = vec![0u8; 256];
+    loop {
+        let len = get_raw(&c_path, &c_name, &mut buffer);
+        if len >= 0 {
+            buffer.truncate(len as usize);
+            return Ok(Some(buffer));
+        }
+
+        let error = io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::ERANGE) => buffer.resize(buffer.len() * 2, 0),
+            #[cfg(target_os = "linux")]
+            Some(libc::ENODATA) => return Ok(None),
+            #[cfg(target_os = "macos")]
+            Some(libc::ENOATTR) => return Ok(None),
+            _ => return Err(error),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn get_raw(path: &std::ffi::CStr, name: &std::ffi::CStr, buffer: &mut [u8]) -> isize {
+    // SAFETY: both strings are NUL-terminated and the buffer length is passed along
+    unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len()) }
+}
+
+#[cfg(target_os = "macos")]
+fn get_raw(path: &std::ffi::CStr, name: &std::ffi::CStr, buffer: &mut [u8]) -> isize {
+    // SAFETY: both strings are NUL-terminated and the buffer length is passed along
+    unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len(), 0, 0) }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn get(_path: &Path, _name: &str) -> io::Result<Option<Vec<u8>>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "extended attributes are not supported on this platform"))
+}
+
+/// Lists the names of the extended attributes of `path`.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn list(path: &Path) -> io::Result<Vec<String>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut buffer = vec![0u8; 1024];
+    loop {
+        let len = list_raw(&c_path, &mut buffer);
+        if len >= 0 {
+            buffer.truncate(len as usize);
+            break;
+        }
+
+        let error = io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::ERANGE) => buffer.resize(buffer.len() * 2, 0),
+            _ => return Err(error),
+        }
+    }
+
+    // NUL-terminated names, one after the other
+    Ok(buffer.split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect())
+}
+
+#[cfg(target_os = "linux")]
+fn list_raw(path: &std::ffi::CStr, buffer: &mut [u8]) -> isize {
+    // SAFETY: the path is NUL-terminated and the buffer length is passed along
+    unsafe { libc::listxattr(path.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len()) }
+}
+
+#[cfg(target_os = "macos")]
+fn list_raw(path: &std::ffi::CStr, buffer: &mut [u8]) -> isize {
+    // SAFETY: the path is NUL-terminated and the buffer length is passed along
+    unsafe { libc::listxattr(path.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len(), 0) }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn list(_path: &Path) -> io::Result<Vec<String>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "extended attributes are not supported on this platform"))
+}
+
+/// Parses a `user.shatag.ts` value (`seconds.nanoseconds`) into its parts.
+pub fn parse_timestamp(value: &[u8]) -> Option<(i64, u32)> {
+    let text = std::str::from_utf8(value).ok()?.trim_end_matches('\0').trim();
+    let (secs, nanos) = text.split_once('.').unwrap_or((text, "0"));
+    Some((secs.parse().ok()?, nanos.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp(b"1700000000.123456789"), Some((1700000000, 123456789)));
+        assert_eq!(parse_timestamp(b"1700000000"), Some((1700000000, 0)));
+        assert_eq!(parse_timestamp(b"soon"), None);
+    }
+}
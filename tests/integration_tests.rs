@@ -1,3 +1,7 @@
+// The sha-calc binary needs the std feature; tests that hash with an
+// algorithm behind a feature are gated on it
+#![cfg(feature = "std")]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::fs;
@@ -6,6 +10,7 @@ use tempfile::NamedTempFile;
 
 
 #[test]
+#[cfg(feature = "sha2")]
 fn test_stdin_input() {
     let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     cmd.write_stdin("hello world")
@@ -15,6 +20,7 @@ fn test_stdin_input() {
 }
 
 #[test]
+#[cfg(feature = "sha2")]
 fn test_single_file() {
     let mut file = NamedTempFile::new().unwrap();
     writeln!(file, "test content").unwrap();
@@ -44,6 +50,7 @@ fn test_multiple_files() {
 }
 
 #[test]
+#[cfg(feature = "sha1")]
 fn test_sha1_algorithm() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     cmd.args(["-a", "sha1"])
@@ -54,6 +61,7 @@ fn test_sha1_algorithm() {
 }
 
 #[test]
+#[cfg(feature = "sha2")]
 fn test_sha512_algorithm() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     cmd.args(["-a", "sha512"])
@@ -65,6 +73,73 @@ fn test_sha512_algorithm() {
 }
 
 #[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_multiple_algorithms() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-a", "sha256,crc32,sha1"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  -\n",
+            "0d4a1185  -\n",
+            "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed  -\n",
+        ));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_multiple_algorithms_per_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("a.txt");
+    let second = dir.path().join("b.txt");
+    fs::write(&first, "hello world").unwrap();
+    fs::write(&second, "").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--tag", "-a", "sha256", "-a", "crc32"])
+        .args([&first, &second])
+        .assert()
+        .success()
+        .stdout(format!(
+            "SHA256 ({a}) = b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n\
+             CRC32 ({a}) = 0d4a1185\n\
+             SHA256 ({b}) = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n\
+             CRC32 ({b}) = 00000000\n",
+            a = first.display(),
+            b = second.display(),
+        ));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_multiple_algorithms_json() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--json", "-a", "sha256,crc32"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "[\n",
+            "  {\"algorithm\":\"sha256\",\"digest\":\"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\",\"path\":\"-\",\"size\":11,\"error\":null},\n",
+            "  {\"algorithm\":\"crc32\",\"digest\":\"0d4a1185\",\"path\":\"-\",\"size\":11,\"error\":null}\n",
+            "]\n",
+        ));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_multiple_algorithms_need_plain_hashing() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "-a", "sha256,crc32"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--check works with a single algorithm"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
 fn test_quiet_mode() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     cmd.args(["-q"])
@@ -75,6 +150,7 @@ fn test_quiet_mode() {
 }
 
 #[test]
+#[cfg(feature = "sha2")]
 fn test_empty_input() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     cmd.write_stdin("")
@@ -112,6 +188,7 @@ fn test_glob_pattern() {
 }
 
 #[test]
+#[cfg(feature = "sha2")]
 fn test_check_mode() {
     let mut content_file = NamedTempFile::new().unwrap();
     let mut hash_file = NamedTempFile::new().unwrap();
@@ -136,6 +213,7 @@ fn test_check_mode() {
 }
 
 #[test]
+#[cfg(feature = "sha2")]
 fn test_check_mode_failure() {
     let mut content_file = NamedTempFile::new().unwrap();
     let mut hash_file = NamedTempFile::new().unwrap();
@@ -154,11 +232,12 @@ fn test_check_mode_failure() {
 }
 
 #[test]
+#[cfg(all(feature = "sha1", feature = "sha2", feature = "sha3", feature = "blake2"))]
 fn test_all_sha_algorithms() {
     let algorithms = vec![
         "sha1", "sha224", "sha256", "sha384", "sha512",
         "sha3-224", "sha3-256", "sha3-384", "sha3-512",
-        "blake2b", "blake2s"
+        "blake2b", "blake2s", "crc32"
     ];
     
     for algorithm in algorithms {
@@ -213,4 +292,2202 @@ fn test_version_flag() {
         .assert()
         .success()
         .stdout(predicate::str::contains(env!("CARGO_PKG_VERSION")));
-}
\ No newline at end of file
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_raw_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.arg("--raw")
+        .write_stdin("hello world")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(hex::encode(&output.stdout), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_upper_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--upper")
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9  -\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_tag_output_and_check() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    writeln!(content_file, "test content").unwrap();
+    let path = content_file.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.args(["--tag", path])
+        .output()
+        .unwrap();
+
+    let tag_line = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(tag_line, format!("SHA256 ({}) = a1fff0ffefb9eace7230c24e50731f0a91c62f9cefdfe77121c2f607125dffae\n", path));
+    write!(hash_file, "{}", tag_line).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_json_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--json")
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("[\n  {\"algorithm\":\"sha256\",\"digest\":\"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\",\"path\":\"-\",\"size\":11,\"error\":null}\n]\n");
+}
+
+#[test]
+fn test_json_output_reports_errors() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--json", "nonexistent_file.txt"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"path\":\"nonexistent_file.txt\""))
+        .stdout(predicate::str::contains("\"error\":\"Failed to open file: nonexistent_file.txt"));
+}
+
+#[test]
+fn test_jsonl_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file1_path = temp_dir.path().join("a.txt");
+    let file2_path = temp_dir.path().join("b.txt");
+
+    fs::write(&file1_path, "content1").unwrap();
+    fs::write(&file2_path, "content2").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.args(["--jsonl", file1_path.to_str().unwrap(), file2_path.to_str().unwrap()])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with('{') && lines[0].ends_with('}'));
+    assert!(lines[0].contains("a.txt"));
+    assert!(lines[1].contains("b.txt"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_csv_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("a,b.txt");
+    fs::write(&file_path, "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--csv", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!(
+            "algorithm,digest,path,size,error\nsha256,b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9,\"{}\",11,\n",
+            file_path.to_str().unwrap()
+        ));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_tsv_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--tsv")
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("algorithm\tdigest\tpath\tsize\terror\nsha256\tb94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\t-\t11\t\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_yaml_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--yaml")
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("- algorithm: sha256\n  digest: \"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\"\n  path: \"-\"\n  size: 11\n  error: null\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_xml_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--xml")
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checksums>\n"))
+        .stdout(predicate::str::contains("<digest>b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9</digest>"))
+        .stdout(predicate::str::ends_with("</checksums>\n"));
+}
+
+#[test]
+fn test_sfv_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("data.bin");
+    fs::write(&file_path, "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--sfv", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("; Generated by"))
+        .stdout(predicate::str::ends_with(format!("{} 0D4A1185\n", file_path.to_str().unwrap())));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_oci_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--oci", "-q"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--prefix-algo", "-a", "sha512"])
+        .write_stdin("test")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("sha512:ee26b0dd4af7e749"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_ni_uri_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--ni-uri", "-q"])
+        .write_stdin("Hello World!")
+        .assert()
+        .success()
+        .stdout("ni:///sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk\n");
+}
+
+#[test]
+#[cfg(feature = "sha1")]
+fn test_ni_uri_unsupported_algorithm() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--ni-uri", "-a", "sha1"])
+        .write_stdin("Hello World!")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no RFC 6920"));
+}
+
+#[test]
+#[cfg(feature = "sha1")]
+fn test_magnet_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("hello world.txt");
+    fs::write(&file_path, "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--magnet", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("^magnet:\\?xt=urn:btih:[0-9a-f]{40}&dn=hello%20world.txt&xl=11\n$").unwrap());
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_format_template() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--format", "{algorithm}\\t{size}\\t{digest}\\t{path}\\t{mtime}"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("sha256\t11\tb94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\t-\t-\n");
+}
+
+#[test]
+fn test_format_template_unknown_placeholder() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--format", "{hash}"])
+        .write_stdin("hello world")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown placeholder '{hash}'"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_zero_terminated_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file1_path = temp_dir.path().join("a.txt");
+    let file2_path = temp_dir.path().join("b.txt");
+
+    fs::write(&file1_path, "content1").unwrap();
+    fs::write(&file2_path, "content2").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.args(["-z", "-q", file1_path.to_str().unwrap(), file2_path.to_str().unwrap()])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let records: Vec<&[u8]> = output.stdout.split(|&b| b == 0).collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].len(), 64);
+    assert_eq!(records[1].len(), 64);
+    assert!(records[2].is_empty());
+    assert!(!output.stdout.contains(&b'\n'));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_mode_color() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    writeln!(content_file, "test content").unwrap();
+    writeln!(hash_file, "a1fff0ffefb9eace7230c24e50731f0a91c62f9cefdfe77121c2f607125dffae  {}",
+             content_file.path().to_str().unwrap()).unwrap();
+    writeln!(hash_file, "0000000000000000000000000000000000000000000000000000000000000000  {}",
+             content_file.path().to_str().unwrap()).unwrap();
+    writeln!(hash_file, "0000000000000000000000000000000000000000000000000000000000000000  nonexistent_file.txt").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "--color=always", hash_file.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\x1b[32mOK\x1b[0m"))
+        .stdout(predicate::str::contains("\x1b[31mFAILED\x1b[0m"))
+        .stdout(predicate::str::contains("nonexistent_file.txt: \x1b[33mMISSING\x1b[0m"));
+
+    // Piped output is not colorized by default
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\x1b[").not())
+        .stdout(predicate::str::contains("nonexistent_file.txt: MISSING"));
+}
+
+#[test]
+fn test_output_file_and_append() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file1_path = temp_dir.path().join("a.txt");
+    let file2_path = temp_dir.path().join("b.txt");
+    let manifest_path = temp_dir.path().join("manifest.sha256");
+
+    fs::write(&file1_path, "content1").unwrap();
+    fs::write(&file2_path, "content2").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-o", manifest_path.to_str().unwrap(), file1_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-o", manifest_path.to_str().unwrap(), "--append", file2_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    let lines: Vec<&str> = manifest.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("a.txt"));
+    assert!(lines[1].ends_with("b.txt"));
+}
+
+#[test]
+fn test_output_file_untouched_on_failure() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("a.txt");
+    let manifest_path = temp_dir.path().join("manifest.sha256");
+
+    fs::write(&file_path, "content1").unwrap();
+    fs::write(&manifest_path, "previous manifest\n").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-o", manifest_path.to_str().unwrap(), file_path.to_str().unwrap(), "nonexistent_file.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to open file"));
+
+    assert_eq!(fs::read_to_string(&manifest_path).unwrap(), "previous manifest\n");
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_sidecar_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("release.iso");
+    fs::write(&file_path, "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--sidecar", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("release.iso"));
+
+    let sidecar = fs::read_to_string(temp_dir.path().join("release.iso.sha256")).unwrap();
+    assert_eq!(sidecar, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  release.iso\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--sidecar", "-a", "sha512", file_path.to_str().unwrap()])
+        .assert()
+        .success();
+    assert!(temp_dir.path().join("release.iso.sha512").exists());
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_long_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("my file.txt");
+    fs::write(&file_path, "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--long", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(format!(
+            "^b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  11  \\d{{4}}-\\d{{2}}-\\d{{2}}T\\d{{2}}:\\d{{2}}:\\d{{2}}Z  sha256  {}\n$",
+            regex_escape(file_path.to_str().unwrap())
+        )).unwrap());
+}
+
+#[cfg(feature = "sha2")]
+fn regex_escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| if c.is_alphanumeric() || c == ' ' { vec![c] } else { vec!['\\', c] })
+        .collect()
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_encoding_option() {
+    let cases = [
+        ("base64", "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek="),
+        ("base32", "XFGSPOMTJU7ARJJOKLL5U7NL7LCIJ37DPJJYB3UQRD32ZYXPZXUQ===="),
+        ("zbase32", "zfg1xqcujw9ytjjqkmm7w9pm9mnej59dxjjab5wotd543azx3zwo"),
+        ("proquint", "rojat-fivun-natat-gumam-pihov-jaril-tonut-pozup-sifah-vuzog-lonig-magov-nafam-zivos-varoz-sulon"),
+    ];
+
+    for (encoding, expected) in cases {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["-q", "--encoding", encoding])
+            .write_stdin("hello world")
+            .assert()
+            .success()
+            .stdout(format!("{}\n", expected));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+#[cfg(feature = "sha2")]
+fn test_escaped_filename_round_trip() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("two\nlines\\name.txt");
+    let hash_file = temp_dir.path().join("manifest.sha256");
+    fs::write(&file_path, "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.arg(file_path.to_str().unwrap())
+        .output()
+        .unwrap();
+
+    let line = String::from_utf8(output.stdout).unwrap();
+    assert!(line.starts_with("\\b94d27b9"));
+    assert!(line.ends_with("two\\nlines\\\\name.txt\n"));
+    assert_eq!(line.lines().count(), 1);
+    fs::write(&hash_file, &line).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK"));
+}
+
+#[test]
+#[cfg(feature = "sha1")]
+fn test_grouped_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-q", "--group", "4", "-a", "sha1"])
+        .write_stdin("hello")
+        .assert()
+        .success()
+        .stdout("aaf4 c61d dcc5 e8a2 dabe de0f 3b48 2cd9 aea9 434d\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-q", "--upper", "--group", "2::", "-a", "sha1"])
+        .write_stdin("hello")
+        .assert()
+        .success()
+        .stdout("AA:F4:C6:1D:DC:C5:E8:A2:DA:BE:DE:0F:3B:48:2C:D9:AE:A9:43:4D\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_binary_marker_round_trip() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    writeln!(content_file, "test content").unwrap();
+    let path = content_file.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.args(["--binary", path])
+        .output()
+        .unwrap();
+
+    let line = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(line, format!("a1fff0ffefb9eace7230c24e50731f0a91c62f9cefdfe77121c2f607125dffae *{}\n", path));
+    write!(hash_file, "{}", line).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!("{}: OK\n", path));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--text", path])
+        .assert()
+        .success()
+        .stdout(format!("a1fff0ffefb9eace7230c24e50731f0a91c62f9cefdfe77121c2f607125dffae  {}\n", path));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_qr_output() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.args(["--qr", "-q"])
+        .write_stdin("hello world")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"));
+
+    // Version 5 symbol (37 modules) plus a 4-module quiet zone, two rows per line
+    let qr: Vec<&str> = lines.collect();
+    assert_eq!(qr.len(), 23);
+    assert!(qr.iter().all(|line| line.chars().count() == 45));
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_check_detects_algorithm_from_digest_length() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    write!(content_file, "hello world").unwrap();
+    let path = content_file.path().to_str().unwrap();
+
+    writeln!(hash_file, "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed  {}", path).unwrap();
+    writeln!(hash_file, "309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f  {}", path).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!("{0}: OK\n{0}: OK\n", path));
+}
+
+#[test]
+#[cfg(all(feature = "sha2", feature = "sha3"))]
+fn test_check_prefer_resolves_ambiguous_length() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    write!(content_file, "hello world").unwrap();
+    let path = content_file.path().to_str().unwrap();
+    writeln!(hash_file, "644bcc7e564373040999aac89e7622f3ca71fba1d972fd94a31c3bfbf24e3938  {}", path).unwrap();
+
+    // 64 hex digits defaults to SHA-256
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("FAILED"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "--prefer", "sha3-256", hash_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!("{}: OK\n", path));
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2", feature = "sha3"))]
+fn test_check_tagged_lines_select_algorithm() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    write!(content_file, "hello world").unwrap();
+    let path = content_file.path().to_str().unwrap();
+
+    // shasum --tag and openssl dgst lines, each with its own algorithm
+    writeln!(hash_file, "SHA1 ({}) = 2aae6c35c94fcfb415dbe95f408b9ce91ee846ed", path).unwrap();
+    writeln!(hash_file, "SHA2-256({})= b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9", path).unwrap();
+    writeln!(hash_file, "SHA3-256 ({}) = 644bcc7e564373040999aac89e7622f3ca71fba1d972fd94a31c3bfbf24e3938", path).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "-a", "sha512", hash_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!("{0}: OK\n{0}: OK\n{0}: OK\n", path));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_malformed_lines_and_strict() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    let mut hash_file = NamedTempFile::new().unwrap();
+
+    write!(content_file, "hello world").unwrap();
+    let path = content_file.path().to_str().unwrap();
+    writeln!(hash_file, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  {}", path).unwrap();
+    writeln!(hash_file, "not a checksum line").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!("{}: OK\n", path))
+        .stderr(predicate::str::contains("WARNING: 1 line is improperly formatted"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "--strict", hash_file.path().to_str().unwrap()])
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn test_check_no_properly_formatted_lines() {
+    let mut hash_file = NamedTempFile::new().unwrap();
+    writeln!(hash_file, "garbage").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", hash_file.path().to_str().unwrap()])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("no properly formatted checksum lines found"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_warn_reports_each_malformed_line() {
+    let mut hash_file = NamedTempFile::new().unwrap();
+    writeln!(hash_file, "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  /dev/null").unwrap();
+    writeln!(hash_file, "zz!z  file").unwrap();
+    writeln!(hash_file, "abcd").unwrap();
+
+    let manifest = hash_file.path().to_str().unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "--warn", manifest])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!("{}: 2: improperly formatted line: digest is not valid hexadecimal or base64", manifest)))
+        .stderr(predicate::str::contains(format!("{}: 3: improperly formatted line: missing separator", manifest)))
+        .stderr(predicate::str::contains("WARNING: 2 lines are improperly formatted"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_manifest_from_stdin() {
+    let mut content_file = NamedTempFile::new().unwrap();
+    write!(content_file, "hello world").unwrap();
+    let path = content_file.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "-"])
+        .write_stdin(format!("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  {}\n", path))
+        .assert()
+        .success()
+        .stdout(format!("{}: OK\n", path));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "-"])
+        .write_stdin("garbage\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("standard input: no properly formatted checksum lines found"));
+}
+
+#[test]
+fn test_check_sfv_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("track 01.flac"), "hello world").unwrap();
+    std::fs::write(dir.path().join("track 02.flac"), "123456789").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--sfv", "-o", "album.sfv", "track 01.flac", "track 02.flac"])
+        .assert()
+        .success();
+
+    // Entries are resolved relative to the SFV file, not the working directory
+    let sfv = dir.path().join("album.sfv");
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", sfv.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("track 01.flac: OK\ntrack 02.flac: OK\n");
+
+    std::fs::write(dir.path().join("track 02.flac"), "corrupted").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", sfv.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("track 02.flac: FAILED"));
+}
+
+#[test]
+#[cfg(all(feature = "sha2", feature = "blake2"))]
+fn test_check_minisign_signed_manifest() {
+    // Fixtures signed with the Ed25519 seed 00 01 02 .. 1f
+    const PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const MINISIG: &str = "untrusted comment: signature from minisign secret key\nRUQBAgMEBQYHCH5MgZnQGmOvuwqpywgyxV6/AdW+9ivXJcC9z5TItNDyjxITLtqbh4tv8XWCwwtw/GRSzz9gnZqZEmoXrG0UaAU=\ntrusted comment: timestamp:1700000000\tfile:SHA256SUMS\nbfXkJKPcfXi2Qz8bGGUTb36ALQ3PUfCDUvTTWT9YWYCxXiYtjtDWMmmuaY2vwfjLI3Uq9DfVfLE09d0Jr6IWBQ==\n";
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("hello.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS"), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  hello.txt\n").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS.minisig"), MINISIG).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--minisign-pubkey", PUBLIC_KEY, "SHA256SUMS"])
+        .assert()
+        .success()
+        .stdout("hello.txt: OK\n")
+        .stderr(predicate::str::contains("signature verified (trusted comment: timestamp:1700000000"));
+
+    // A manifest altered after signing is rejected before any file is checked
+    std::fs::write(dir.path().join("SHA256SUMS"), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  hello.txt\nb94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  other.txt\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--minisign-pubkey", PUBLIC_KEY, "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("signature not verified"));
+}
+
+#[test]
+fn test_check_remote_manifest_download_failure() {
+    // Nothing listens on port 1, so the download fails without network access
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "https://127.0.0.1:1/SHA256SUMS"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read hash file: https://127.0.0.1:1/SHA256SUMS"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-c", "http://example.com/SHA256SUMS"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("plain HTTP"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_failed_output() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("good.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("bad.txt"), "corrupted").unwrap();
+
+    let manifest = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  good.txt\n\
+                    b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  bad.txt\n\
+                    SHA256 (gone.txt) = b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n";
+    std::fs::write(dir.path().join("SHA256SUMS"), manifest).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--failed-output", "failed.txt", "SHA256SUMS"])
+        .assert()
+        .failure();
+
+    let failed = std::fs::read_to_string(dir.path().join("failed.txt")).unwrap();
+    assert_eq!(failed, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  bad.txt\n\
+                        SHA256 (gone.txt) = b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n");
+
+    // The collected lines are themselves a manifest for a follow-up run
+    std::fs::write(dir.path().join("bad.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("gone.txt"), "hello world").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "failed.txt"])
+        .assert()
+        .success()
+        .stdout("bad.txt: OK\ngone.txt: OK\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("good.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("bad.txt"), "corrupted").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS"), "\
+        b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  good.txt\n\
+        b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  bad.txt\n\
+        b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  gone.txt\n").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--summary", "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::ends_with("1 OK, 1 FAILED, 1 missing, 0 unreadable\n"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "-q", "--summary=json", "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::ends_with("{\"ok\": 1, \"failed\": 1, \"missing\": 1, \"unreadable\": 0, \"improperly_formatted\": 0}\n"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[cfg(feature = "sha2")]
+fn test_hash_xattr_streams() {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("photo.jpg");
+    std::fs::write(&file, "hello world").unwrap();
+
+    let path = CString::new(file.as_os_str().as_bytes()).unwrap();
+    for (name, value) in [("user.xdg.origin.url", "hi"), ("user.comment", "")] {
+        let name = CString::new(name).unwrap();
+        if unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0) } != 0 {
+            // The filesystem holding temporary files has no user xattr support
+            return;
+        }
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["--streams", "photo.jpg"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let user_lines: Vec<&str> = stdout.lines().filter(|line| !line.contains(":security.")).collect();
+    assert_eq!(user_lines, [
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  photo.jpg",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  photo.jpg:user.comment",
+        "8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4  photo.jpg:user.xdg.origin.url",
+    ]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_xattr_shatag() {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    fn set_xattr(path: &std::path::Path, name: &str, value: &str) -> bool {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let name = CString::new(name).unwrap();
+        unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0) == 0 }
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let good = dir.path().join("good.txt");
+    let corrupt = dir.path().join("corrupt.txt");
+    let modified = dir.path().join("modified.txt");
+    let untagged = dir.path().join("untagged.txt");
+    for path in [&good, &corrupt, &modified, &untagged] {
+        std::fs::write(path, "hello world").unwrap();
+    }
+
+    let digest = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    if !set_xattr(&good, "user.shatag.sha256", digest) {
+        // The filesystem holding temporary files has no user xattr support
+        return;
+    }
+
+    let mtime = |path: &std::path::Path| {
+        let time = std::fs::metadata(path).unwrap().modified().unwrap()
+            .duration_since(std::time::UNIX_EPOCH).unwrap();
+        format!("{:010}.{:09}", time.as_secs(), time.subsec_nanos())
+    };
+    assert!(set_xattr(&good, "user.shatag.ts", &mtime(&good)));
+    assert!(set_xattr(&corrupt, "user.shatag.sha256", &digest.replace('b', "c")));
+    assert!(set_xattr(&corrupt, "user.shatag.ts", &mtime(&corrupt)));
+    assert!(set_xattr(&modified, "user.shatag.sha256", &digest.replace('b', "c")));
+    assert!(set_xattr(&modified, "user.shatag.ts", "0000000001.000000000"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["--check-xattr", "*.txt"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "corrupt.txt: FAILED\ngood.txt: OK\nmodified.txt: OUTDATED\nuntagged.txt: NEW\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_update_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(dir.path().join(name), name).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--update", "SHA256SUMS", "a.txt", "b.txt", "c.txt"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("0 updated, 3 added, 0 removed"));
+
+    // b.txt is rewritten, c.txt deleted, d.txt new; a.txt is corrupted behind
+    // the tool's back with its size and mtime unchanged, so it must not be re-hashed
+    let a_time = std::fs::metadata(dir.path().join("a.txt")).unwrap().modified().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "rot!!").unwrap();
+    std::fs::File::options().write(true).open(dir.path().join("a.txt")).unwrap().set_modified(a_time).unwrap();
+    std::fs::write(dir.path().join("b.txt"), "new content").unwrap();
+    std::fs::remove_file(dir.path().join("c.txt")).unwrap();
+    std::fs::write(dir.path().join("d.txt"), "d.txt").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--update", "SHA256SUMS", "d.txt"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 updated, 1 added, 1 removed"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stdout("a.txt: FAILED\nb.txt: OK\nd.txt: OK\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_update_manifest_from_other_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(dir.path().join("sub/a.txt"), "a").unwrap();
+
+    for expected in ["0 updated, 1 added, 0 removed", "0 updated, 0 added, 0 removed"] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.current_dir(dir.path())
+            .args(["--update", "sub/SHA256SUMS", "sub/a.txt"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(expected));
+    }
+    let manifest = std::fs::read_to_string(dir.path().join("sub/SHA256SUMS")).unwrap();
+    assert!(manifest.ends_with("  a.txt\n"), "{}", manifest);
+    assert!(dir.path().join("sub/SHA256SUMS.state").is_file());
+
+    // Touching a file without changing it re-hashes it, but updates nothing
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    std::fs::File::options().write(true).open(dir.path().join("sub/a.txt")).unwrap().set_modified(later).unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path().join("sub"))
+        .args(["--update", "SHA256SUMS"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("0 updated, 0 added, 0 removed"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--update", "sub/SHA256SUMS", "a.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not inside the directory of the manifest"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_diff_manifests() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    let b = "a1fff0ffefb9eace7230c24e50731f0a91c62f9cefdfe77121c2f607125dffae";
+    std::fs::write(dir.path().join("v1.sha256"), format!("{a}  same.txt\n{a}  edited.txt\n{a}  dropped.txt\n")).unwrap();
+    std::fs::write(dir.path().join("v2.sha256"), format!("{a}  same.txt\nSHA256 (edited.txt) = {b}\n{b}  new.txt\n")).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--diff", "v1.sha256", "v2.sha256"])
+        .assert()
+        .code(1)
+        .stdout("dropped.txt: REMOVED\nedited.txt: CHANGED\nnew.txt: ADDED\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--diff", "v1.sha256", "v1.sha256"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_base64_and_sri_digests() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.js"), "hello world").unwrap();
+    std::fs::write(
+        dir.path().join("integrity.txt"),
+        "sha384-/b2OdaZ/KfcBpOBAOF4uI5hjA+oQI5IRr5B/y7g1eLPkF8txzmRu/QgZ3YwIjeG9  app.js\n\
+         uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=  app.js\n\
+         uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek  app.js\n",
+    ).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--check", "integrity.txt"])
+        .assert()
+        .success()
+        .stdout("app.js: OK\napp.js: OK\napp.js: OK\n");
+
+    std::fs::write(dir.path().join("app.js"), "tampered").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--check", "integrity.txt"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("app.js: FAILED"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_glob_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::write(dir.path().join("app.1.log.gz"), "hello world").unwrap();
+    std::fs::write(dir.path().join("app.2.log.gz"), "hello world").unwrap();
+    std::fs::write(dir.path().join("manifest"), format!("{hello}  app.*.log.gz\n{hello}  old.*.log.gz\n")).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--check", "--glob-entries", "manifest"])
+        .assert()
+        .success()
+        .stdout("app.1.log.gz: OK\napp.2.log.gz: OK\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--check", "--glob-entries", "--require-glob-match", "manifest"])
+        .assert()
+        .code(4)
+        .stdout("app.1.log.gz: OK\napp.2.log.gz: OK\nold.*.log.gz: MISSING\n");
+
+    // Without --glob-entries the pattern is a literal file name
+    std::fs::write(dir.path().join("app.2.log.gz"), "rotated").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--check", "manifest"])
+        .assert()
+        .code(4)
+        .stdout("app.*.log.gz: MISSING\nold.*.log.gz: MISSING\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--check", "--glob-entries", "manifest"])
+        .assert()
+        .code(1)
+        .stdout("app.1.log.gz: OK\napp.2.log.gz: FAILED\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_quiet_and_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::write(dir.path().join("good.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("bad.txt"), "tampered").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS"), format!("{hello}  good.txt\n{hello}  bad.txt\nnot a checksum line\n")).unwrap();
+
+    // --quiet hides only the OK lines, like sha256sum
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--quiet", "SHA256SUMS"])
+        .assert()
+        .code(1)
+        .stdout("bad.txt: FAILED\n")
+        .stderr(predicate::str::contains("1 line is improperly formatted"));
+
+    // --status prints nothing at all
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--status", "SHA256SUMS"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("");
+
+    std::fs::write(dir.path().join("bad.txt"), "hello world").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--status", "SHA256SUMS"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_exit_codes_by_failure_class() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::write(dir.path().join("good.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("bad.txt"), "tampered").unwrap();
+    std::fs::write(dir.path().join("missing.sha256"), format!("{hello}  good.txt\n{hello}  gone.txt\n")).unwrap();
+    std::fs::write(dir.path().join("all.sha256"), format!("{hello}  gone.txt\n{hello}  bad.txt\n")).unwrap();
+
+    // Missing files: 4, a mismatch takes precedence: 1
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path()).args(["-c", "missing.sha256"]).assert().code(4);
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path()).args(["-c", "all.sha256"]).assert().code(1);
+
+    // Hashing mode: a missing input is 4, an unreadable one (a directory) 2
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path()).args(["good.txt", "gone.txt"]).assert().code(4);
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path()).args(["--json", "."]).assert().code(2);
+
+    // An unreadable manifest is 4 when absent
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path()).args(["-c", "nope.sha256"]).assert().code(4);
+
+    // A bad command line is 64, distinct from an I/O error
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--no-such-option"]).assert().code(64);
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--help"]).assert().success();
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_tolerant_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+    std::fs::write(
+        dir.path().join("SHA256SUMS"),
+        format!("# Generated on Windows\r\n{hello} a.txt\r\n{hello}\tb.txt\r\n{hello} *c.txt\r\n  # indented comment\r\n{hello}  d.txt\r\n"),
+    ).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--strict", "SHA256SUMS"])
+        .assert()
+        .success()
+        .stdout("a.txt: OK\nb.txt: OK\nc.txt: OK\nd.txt: OK\n")
+        .stderr("");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_recursive_manifests() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::create_dir(dir.path().join("docs")).unwrap();
+    std::fs::write(dir.path().join("docs/readme.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("docs/guide.txt"), "hello world").unwrap();
+    let nested = format!("{hello}  readme.txt\n{hello}  guide.txt\n");
+    std::fs::write(dir.path().join("docs/docs.sha256"), &nested).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path()).arg("docs/docs.sha256").output().unwrap();
+    std::fs::write(dir.path().join("BUNDLE.sha256"), &output.stdout).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--recursive-manifests", "BUNDLE.sha256"])
+        .assert()
+        .success()
+        .stdout("docs/docs.sha256: OK\ndocs/readme.txt: OK\ndocs/guide.txt: OK\n");
+
+    // Without the flag only the nested manifest itself is checked
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "BUNDLE.sha256"])
+        .assert()
+        .success()
+        .stdout("docs/docs.sha256: OK\n");
+
+    // A tampered nested manifest is not trusted, so its entries are not checked
+    std::fs::write(dir.path().join("docs/docs.sha256"), format!("{hello}  readme.txt\n")).unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--recursive-manifests", "BUNDLE.sha256"])
+        .assert()
+        .code(1)
+        .stdout("docs/docs.sha256: FAILED\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_watch_prints_transitions() {
+    use std::io::BufRead;
+
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::write(dir.path().join("app.bin"), "hello world").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS"), format!("{hello}  app.bin\n")).unwrap();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME")))
+        .current_dir(dir.path())
+        .args(["-c", "--watch", "SHA256SUMS"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let next_line = || receiver.recv_timeout(std::time::Duration::from_secs(10)).ok();
+
+    assert_eq!(next_line().as_deref(), Some("app.bin: OK"));
+    std::fs::write(dir.path().join("app.bin"), "rebuilt").unwrap();
+    assert_eq!(next_line().as_deref(), Some("app.bin: FAILED"));
+    std::fs::remove_file(dir.path().join("app.bin")).unwrap();
+    assert_eq!(next_line().as_deref(), Some("app.bin: MISSING"));
+    std::fs::write(dir.path().join("app.bin"), "hello world").unwrap();
+    assert_eq!(next_line().as_deref(), Some("app.bin: OK"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_watch_prints_changed_digests() {
+    use std::io::BufRead;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("out")).unwrap();
+    std::fs::write(dir.path().join("out/app.bin"), "hello world").unwrap();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME")))
+        .current_dir(dir.path())
+        .args(["--watch", "out"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let next_line = || receiver.recv_timeout(std::time::Duration::from_secs(10)).ok();
+
+    assert_eq!(
+        next_line().as_deref(),
+        Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  out/app.bin")
+    );
+    std::fs::write(dir.path().join("out/app.bin"), "rebuilt").unwrap();
+    assert_eq!(
+        next_line().as_deref(),
+        Some("9ae832844d70dd5a06b8c70f3f4f68bbe1c907b116d378c9654b217d4a7b6b4c  out/app.bin")
+    );
+    std::fs::write(dir.path().join("out/new.txt"), "hi").unwrap();
+    assert_eq!(
+        next_line().as_deref(),
+        Some("8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4  out/new.txt")
+    );
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_concat_list() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("parts")).unwrap();
+    std::fs::write(dir.path().join("parts/b.part"), "hello ").unwrap();
+    std::fs::write(dir.path().join("parts/a.part"), "world").unwrap();
+    std::fs::write(dir.path().join("parts/order.txt"), "# firmware\nb.part\n\na.part\n").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--concat-list", "parts/order.txt"])
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  parts/order.txt\n");
+
+    std::fs::write(dir.path().join("parts/order.txt"), "b.part\nmissing.part\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--concat-list", "parts/order.txt"])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("missing.part"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--concat-list", "parts/order.txt", "parts/a.part"])
+        .assert()
+        .failure();
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_parts() {
+    let dir = tempfile::tempdir().unwrap();
+    // "hello world" in 11 one-byte volumes: part10 must follow part9, not part1
+    for (i, byte) in "hello world".chars().enumerate() {
+        std::fs::write(dir.path().join(format!("disk.img.part{}", i + 1)), byte.to_string()).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--parts", "disk.img.part*"])
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  disk.img.part1\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--parts", "none.part*"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No parts to hash"));
+}
+
+#[test]
+fn test_compare_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.img"), "hello world").unwrap();
+    std::fs::write(dir.path().join("b.img"), "hello world").unwrap();
+    std::fs::write(dir.path().join("c.img"), "hello wORLD").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--compare", "a.img", "b.img"])
+        .assert()
+        .success()
+        .stdout("Files a.img and b.img are identical\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--compare", "a.img", "c.img"])
+        .assert()
+        .code(1)
+        .stdout("Files a.img and c.img differ\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-q", "--compare", "a.img", "-"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--compare", "a.img", "missing.img"])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("missing.img"));
+}
+
+#[test]
+fn test_compare_dirs() {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, content) in [
+        ("left/same.txt", "hello world"),
+        ("left/sub/changed.txt", "hello world"),
+        ("left/gone.txt", "old"),
+        ("right/same.txt", "hello world"),
+        ("right/sub/changed.txt", "hello wORLD"),
+        ("right/sub/new.txt", "new"),
+    ] {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--compare-dirs", "left", "right"])
+        .assert()
+        .code(1)
+        .stdout("gone.txt: LEFT ONLY\nsub/changed.txt: DIFFERENT\nsub/new.txt: RIGHT ONLY\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--compare-dirs", "left", "right", "--include", "same.txt"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_dupes() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("photos/copy")).unwrap();
+    for (name, content) in [
+        ("photos/a.jpg", "hello world"),
+        ("photos/copy/a.jpg", "hello world"),
+        ("photos/b.jpg", "hello wORLD"),
+        ("photos/c.jpg", "hi"),
+        ("photos/copy/c.jpg", "hi"),
+        ("photos/copy/c2.jpg", "hi"),
+        ("photos/empty", ""),
+        ("photos/copy/empty", ""),
+    ] {
+        std::fs::write(dir.path().join(name), content).unwrap();
+    }
+
+    // Largest waste first: 11 bytes, then 2 × 2 bytes
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--dupes", "-r", "photos"])
+        .assert()
+        .success()
+        .stdout(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  photos/a.jpg\n\
+             b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  photos/copy/a.jpg\n\
+             \n\
+             8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4  photos/c.jpg\n\
+             8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4  photos/copy/c.jpg\n\
+             8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4  photos/copy/c2.jpg\n",
+        )
+        .stderr("sha-calc: 2 groups of duplicates, 3 redundant files, 15 bytes reclaimable\n");
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_expect_single_digest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("file.iso"), "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--expect", "B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9", "file.iso"])
+        .assert()
+        .success()
+        .stdout("file.iso: OK\n");
+
+    // The algorithm follows from the digest length (SHA-1 here)
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--expect", "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed", "file.iso"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--expect", "0000000000000000000000000000000000000000", "file.iso"])
+        .assert()
+        .code(1)
+        .stdout("file.iso: FAILED\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--expect", "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9", "-q"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--expect", "abc", "file.iso"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid expected digest"));
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2", feature = "sha3"))]
+fn test_check_mixed_algorithm_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("file"), "hello world").unwrap();
+    std::fs::write(
+        dir.path().join("CHECKSUMS"),
+        "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed  file\n\
+         644bcc7e564373040999aac89e7622f3ca71fba1d972fd94a31c3bfbf24e3938  file\n\
+         SHA256 (file) = b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n\
+         sha384-/b2OdaZ/KfcBpOBAOF4uI5hjA+oQI5IRr5B/y7g1eLPkF8txzmRu/QgZ3YwIjeG9  file\n\
+         0d4a1185 file\n",
+    ).unwrap();
+
+    // -a sha3-256 only settles the 64-digit line; every other line keeps its own algorithm
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--strict", "-a", "sha3-256", "CHECKSUMS"])
+        .assert()
+        .success()
+        .stdout("file: OK\nfile: OK\nfile: OK\nfile: OK\nfile: OK\n");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_include_exclude() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::create_dir_all(dir.path().join("docs/api")).unwrap();
+    for name in ["docs/guide.txt", "docs/api/index.txt", "readme.txt", "build.log"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+    std::fs::write(dir.path().join("build.log"), "volatile").unwrap();
+    std::fs::write(
+        dir.path().join("SHA256SUMS"),
+        format!("{hello}  docs/guide.txt\n{hello}  docs/api/index.txt\n{hello}  readme.txt\n{hello}  build.log\n"),
+    ).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--include", "docs/*", "SHA256SUMS"])
+        .assert()
+        .success()
+        .stdout("docs/guide.txt: OK\ndocs/api/index.txt: OK\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--exclude", "*.log", "--exclude", "docs/api/*", "SHA256SUMS"])
+        .assert()
+        .success()
+        .stdout("docs/guide.txt: OK\nreadme.txt: OK\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--include", "[", "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid glob pattern"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_fix_updates_failed_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    let rebuilt = "9ae832844d70dd5a06b8c70f3f4f68bbe1c907b116d378c9654b217d4a7b6b4c";
+    std::fs::write(dir.path().join("a.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "rebuilt").unwrap();
+    std::fs::write(dir.path().join("c.txt"), "rebuilt").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS"), format!("{hello}  a.txt\r\n{hello}  b.txt\r\nSHA256 (c.txt) = {hello}\r\n")).unwrap();
+
+    // Declined for b.txt, accepted for c.txt
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--fix", "SHA256SUMS"])
+        .write_stdin("n\ny\n")
+        .assert()
+        .code(1)
+        .stdout("a.txt: OK\nb.txt: FAILED\nc.txt: FAILED\n")
+        .stderr(predicate::str::contains("update the digest of b.txt in SHA256SUMS? [y/N]"))
+        .stderr(predicate::str::contains("SHA256SUMS: updated 1 entry"));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("SHA256SUMS")).unwrap(),
+        format!("{hello}  a.txt\r\n{hello}  b.txt\r\nSHA256 (c.txt) = {rebuilt}\r\n")
+    );
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--fix", "--yes", "SHA256SUMS"])
+        .assert()
+        .code(1)
+        .stdout("a.txt: OK\nb.txt: FAILED\nc.txt: OK\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "SHA256SUMS"])
+        .assert()
+        .success();
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_check_progress_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::write(dir.path().join("a.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("SHA256SUMS"), format!("{hello}  a.txt\n{hello}  b.txt\n")).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--progress=json", "SHA256SUMS"])
+        .assert()
+        .success()
+        .stdout("a.txt: OK\nb.txt: OK\n")
+        .stderr(predicate::str::starts_with(
+            "{\"event\":\"progress\",\"files_done\":0,\"files_total\":2,\"bytes_done\":0,\"bytes_total\":22,\"current\":\"a.txt\"}\n",
+        ))
+        .stderr(predicate::str::ends_with(
+            "{\"event\":\"done\",\"files_done\":2,\"files_total\":2,\"bytes_done\":22,\"bytes_total\":22,\"current\":null}\n",
+        ));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "--progress", "--progress-fd", "99", "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid progress file descriptor: 99"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_recursive_directory_hashing() {
+    let dir = tempfile::tempdir().unwrap();
+    let hello = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    std::fs::create_dir_all(dir.path().join("tree/sub/deeper")).unwrap();
+    std::fs::write(dir.path().join("tree/a.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("tree/sub/deeper/b.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("tree/sub/line\nbreak.txt"), "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["-r", "tree"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout.clone()).unwrap(),
+        format!("{hello}  tree/a.txt\n{hello}  tree/sub/deeper/b.txt\n\\{hello}  tree/sub/line\\nbreak.txt\n")
+    );
+
+    // The output is a manifest that verifies as is
+    std::fs::write(dir.path().join("tree.sha256"), &output.stdout).unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "tree.sha256"])
+        .assert()
+        .success();
+
+    // Without -r a directory cannot be hashed
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path()).arg("tree").assert().failure();
+}
+
+#[test]
+fn test_recursive_include_exclude() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/node_modules/pkg")).unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/dist")).unwrap();
+    for name in ["tree/app.tar.gz", "tree/dist/app.tar.gz", "tree/notes.txt", "tree/node_modules/pkg/pkg.tar.gz"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["-r", "--include", "*.tar.gz", "--exclude", "node_modules/**", "--format", "{path}", "tree"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "tree/app.tar.gz\ntree/dist/app.tar.gz\n");
+
+    // Patterns are relative to the walked directory
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["-r", "--include", "dist/*", "--format", "{path}", "tree"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "tree/dist/app.tar.gz\n");
+}
+
+#[test]
+fn test_recursive_byte_order() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("d/a")).unwrap();
+    for name in ["d/a0.txt", "d/b.txt", "d/a/b.txt", "d/B.txt", "d/a-b.txt", "d/\u{e9}.txt"] {
+        fs::write(dir.path().join(name), name).unwrap();
+    }
+
+    // Byte order, whatever the locale: uppercase first, `-` before `/` before digits
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .env("LC_ALL", "en_US.UTF-8")
+        .args(["-r", "--format", "{path}", "d"])
+        .assert()
+        .success()
+        .stdout("d/B.txt\nd/a-b.txt\nd/a/b.txt\nd/a0.txt\nd/b.txt\nd/\u{e9}.txt\n");
+}
+
+#[test]
+fn test_recursive_max_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/a/b")).unwrap();
+    for name in ["tree/top.txt", "tree/a/mid.txt", "tree/a/b/deep.txt"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+
+    for (depth, expected) in [
+        ("0", ""),
+        ("1", "tree/top.txt\n"),
+        ("2", "tree/a/mid.txt\ntree/top.txt\n"),
+        ("9", "tree/a/b/deep.txt\ntree/a/mid.txt\ntree/top.txt\n"),
+    ] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd.current_dir(dir.path())
+            .args(["-r", "--max-depth", depth, "--format", "{path}", "tree"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected, "--max-depth {}", depth);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_stream_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let fifo = dir.path().join("pipe");
+    assert!(std::process::Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+
+    // Probing the size would open the FIFO and swallow its contents
+    let writer = std::thread::spawn({
+        let fifo = fifo.clone();
+        move || fs::write(fifo, "hello world").unwrap()
+    });
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--min-size", "1", "--progress", "pipe"])
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  pipe\n");
+    writer.join().unwrap();
+
+    let writer = std::thread::spawn({
+        let fifo = fifo.clone();
+        move || fs::write(fifo, "hello world").unwrap()
+    });
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--git-blob", "pipe"])
+        .assert()
+        .success()
+        .stdout("95d09f2b10159347eece71399a7e2e907ea3df4f  pipe\n");
+    writer.join().unwrap();
+
+    let listener = std::os::unix::net::UnixListener::bind(dir.path().join("socket")).unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"hello world").unwrap();
+    });
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--offset", "6", "socket"])
+        .assert()
+        .success()
+        .stdout("486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7  socket\n");
+    server.join().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+#[cfg(feature = "sha2")]
+fn test_one_hash_per_inode() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("backup/daily.0")).unwrap();
+    std::fs::create_dir_all(dir.path().join("backup/daily.1")).unwrap();
+    std::fs::write(dir.path().join("backup/daily.0/data.txt"), "hello world").unwrap();
+    std::fs::hard_link(dir.path().join("backup/daily.0/data.txt"), dir.path().join("backup/daily.1/data.txt")).unwrap();
+    std::fs::write(dir.path().join("backup/daily.1/new.txt"), "hi").unwrap();
+
+    // Both names are listed, but the shared content is read once
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-r", "--one-hash-per-inode", "--progress", "backup"])
+        .assert()
+        .success()
+        .stdout(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  backup/daily.0/data.txt\n\
+             b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  backup/daily.1/data.txt\n\
+             8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4  backup/daily.1/new.txt\n",
+        )
+        .stderr(predicate::str::ends_with(
+            "{\"event\":\"done\",\"files_done\":3,\"files_total\":3,\"bytes_done\":13,\"bytes_total\":13,\"current\":null}\n",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extension_and_type_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("vm/old")).unwrap();
+    for name in ["vm/disk.img", "vm/old/base.QCOW2", "vm/notes.txt", "vm/installer.iso"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+    std::os::unix::fs::symlink("installer.iso", dir.path().join("vm/latest.iso")).unwrap();
+
+    for (flags, expected) in [
+        (&["--ext", "img,qcow2"][..], "vm/disk.img\nvm/old/base.QCOW2\n"),
+        (&["--ext", "iso", "--type", "f"][..], "vm/installer.iso\n"),
+        (&["--type", "l"][..], "vm/latest.iso\n"),
+    ] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd.current_dir(dir.path())
+            .args(["-r", "--format", "{path}", "vm"])
+            .args(flags)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected, "{:?}", flags);
+    }
+}
+
+#[test]
+fn test_hidden_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("home/.cache")).unwrap();
+    for name in ["home/.bashrc", "home/.cache/blob", "home/notes.txt"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+
+    for (flags, expected) in [
+        (&[][..], "home/.bashrc\nhome/.cache/blob\nhome/notes.txt\n"),
+        (&["--no-hidden"][..], "home/notes.txt\n"),
+        (&["--no-hidden", "--hidden"][..], "home/.bashrc\nhome/.cache/blob\nhome/notes.txt\n"),
+    ] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd.current_dir(dir.path())
+            .args(["-r", "--format", "{path}", "home"])
+            .args(flags)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected, "{:?}", flags);
+    }
+}
+
+#[test]
+fn test_size_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/sub")).unwrap();
+    std::fs::write(dir.path().join("tree/empty"), "").unwrap();
+    std::fs::write(dir.path().join("tree/small.txt"), "hi").unwrap();
+    std::fs::write(dir.path().join("tree/sub/big.bin"), vec![0u8; 3000]).unwrap();
+    std::fs::write(dir.path().join("top.txt"), "hello world").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["-r", "--min-size", "1", "--max-size", "2K", "--format", "{path}", "tree", "top.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "top.txt\ntree/small.txt\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["--min-size", "0x10", "--format", "{path}", "top.txt", "tree/small.txt"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--max-size", "1.5G", "top.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size"));
+}
+
+#[test]
+fn test_recursive_gitignore() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/target/debug")).unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/.git")).unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/src")).unwrap();
+    std::fs::write(dir.path().join("tree/.gitignore"), "target/\n*.log\n").unwrap();
+    std::fs::write(dir.path().join("tree/src/.gitignore"), "!important.log\n").unwrap();
+    for name in ["tree/.git/HEAD", "tree/target/debug/app", "tree/build.log", "tree/src/main.rs", "tree/src/important.log"] {
+        std::fs::write(dir.path().join(name), "hello world").unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd.current_dir(dir.path())
+        .args(["-r", "--gitignore", "--format", "{path}", "tree"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "tree/.gitignore\ntree/src/.gitignore\ntree/src/important.log\ntree/src/main.rs\n"
+    );
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_hash_strings() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-s", "hello world", "-s", "", "--string", "line\nbreak"])
+        .assert()
+        .success()
+        .stdout(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  \"hello world\"\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  \"\"\n\
+             \\697affca60d5d0dc96f00aac4b2d833e3adc0708a4efd8b657acc4229f4449ef  \"line\\nbreak\"\n",
+        );
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["-a", "sha1", "-q", "-s", "hello world"])
+        .assert()
+        .success()
+        .stdout("2aae6c35c94fcfb415dbe95f408b9ce91ee846ed\n");
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_records() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--records")
+        .write_stdin("hello world\n\nlast")
+        .assert()
+        .success()
+        .stdout(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  -:1\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  -:2\n\
+             3547cb112ac4489af2310c0626cdba6f3097a2ad5a3b42ddd3b59c76c7a079a3  -:3\n",
+        );
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--records=nul", "-q", "-a", "sha1"])
+        .write_stdin("hello world\0line\nbreak\0")
+        .assert()
+        .success()
+        .stdout("2aae6c35c94fcfb415dbe95f408b9ce91ee846ed\n2e1a2284714247d355c6c70d3f772e3f4d075aeb\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--records")
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_hex_input() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--hex-input")
+        .write_stdin("68656c6c6f20\n776f726c64\n")
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  -\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--hex-input", "-s", "68 65 6C 6C 6F 20 77 6F 72 6C 64"])
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  \"68 65 6C 6C 6F 20 77 6F 72 6C 64\"\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--hex-input")
+        .write_stdin("not hex")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Input is not valid hexadecimal"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_base64_input() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--base64-input")
+        .write_stdin("aGVsbG8g\nd29ybGQ=\n")
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  -\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--base64-input", "-q", "-s", "aGVsbG8gd29ybGQ="])
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--base64-input", "-s", "not base64!"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("\"not base64!\": Input is not valid base64"));
+}
+
+#[test]
+fn test_remote_input_requires_https() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("http://example.com/file.iso?mirror=1")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("http://example.com/file.iso?mirror=1: Refusing to download over plain HTTP"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_archive_members() {
+    // docs/readme.txt ("hello world") and data.log ("log\n"), tar.gz
+    let archive = hex::decode("1f8b0800000000000203edd3310ec2300c8561cf9ca227284e71c9792212c11014a90d82e313d860616a25d4ff5b9ee4c596ac17cb69de4f29c46beaeba3ca12b4399abdb3f94e55771067e360deabbde64efde0a55359c16dae616aa7c8365d52cea5bb972947c1f6c450439fcb79c91d3ffbaff6d97ff5e646fabf86f6fa1d2d000000000000000000000000f85f4f4a00068c00280000").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("backup.tar.gz"), &archive).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--archive", "backup.tar.gz"])
+        .assert()
+        .success()
+        .stdout(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  docs/readme.txt\n\
+             9b75290f6a6359a2a3471022cbba4b724e45105b313ae8f6c103a2f79e82a857  data.log\n",
+        );
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--archive", "--exclude", "*.log", "--format", "{path} {size} {mtime}"])
+        .write_stdin(archive)
+        .assert()
+        .success()
+        .stdout("docs/readme.txt 11 2023-11-14T22:13:20Z\n");
+
+    fs::write(dir.path().join("plain.txt"), "not an archive").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--archive", "plain.txt"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("plain.txt: Not a tar archive"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_archive_member() {
+    // docs/readme.txt ("hello world") and data.log ("log\n"), tar.gz
+    let archive = hex::decode("1f8b0800000000000203edd3310ec2300c8561cf9ca227284e71c9792212c11014a90d82e313d860616a25d4ff5b9ee4c596ac17cb69de4f29c46beaeba3ca12b4399abdb3f94e55771067e360deabbde64efde0a55359c16dae616aa7c8365d52cea5bb972947c1f6c450439fcb79c91d3ffbaff6d97ff5e646fabf86f6fa1d2d000000000000000000000000f85f4f4a00068c00280000").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("backup.tar.gz"), &archive).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .arg("backup.tar.gz!docs/readme.txt")
+        .assert()
+        .success()
+        .stdout("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  backup.tar.gz!docs/readme.txt\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--member", "data.log", "backup.tar.gz"])
+        .assert()
+        .success()
+        .stdout("9b75290f6a6359a2a3471022cbba4b724e45105b313ae8f6c103a2f79e82a857  backup.tar.gz!data.log\n");
+
+    fs::write(dir.path().join("backup.sha256"), "9b75290f6a6359a2a3471022cbba4b724e45105b313ae8f6c103a2f79e82a857  backup.tar.gz!data.log\n").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["-c", "backup.sha256"])
+        .assert()
+        .success()
+        .stdout("backup.tar.gz!data.log: OK\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .arg("backup.tar.gz!missing.txt")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("No file missing.txt in archive backup.tar.gz"));
+
+    // Zip archives are refused outright, not parsed as corrupt tar files
+    fs::write(dir.path().join("bundle.zip"), b"PK\x03\x04\x14\0\0\0\0\0").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .arg("bundle.zip!docs/readme.txt")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Zip archives are not supported, only tar and .tar.gz"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_tee() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--tee")
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("hello world")
+        .stderr("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  -\n");
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--tee", "--tag", "-o", "stream.sha256"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("stream.sha256")).unwrap(),
+        "SHA256 (-) = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_byte_range() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("image.bin"), "hello world").unwrap();
+
+    // sha256("world")
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--offset", "6", "--length", "5", "image.bin"])
+        .assert()
+        .success()
+        .stdout("486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7  image.bin\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--offset", "0x6", "-"])
+        .write_stdin("hello world")
+        .assert()
+        .success()
+        .stdout("486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7  -\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--offset", "6", "--length", "1K", "image.bin"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Input ends 5 bytes into the requested 1024-byte range"));
+}
+
+#[cfg(unix)]
+#[test]
+#[cfg(feature = "sha2")]
+fn test_tree_digest() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let tree = dir.path().join("tree");
+    std::fs::create_dir_all(tree.join("bin")).unwrap();
+    std::fs::create_dir(tree.join("empty")).unwrap();
+    std::fs::write(tree.join("a.txt"), "hello world").unwrap();
+    std::fs::write(tree.join("bin.txt"), "").unwrap();
+    std::fs::write(tree.join("bin/run.sh"), "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(tree.join("bin/run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    // Records in byte order: a.txt, bin.txt, bin/run.sh
+    let expected = "9f201780cb8a911e5cc434d4a78580d1a8143ad492f505738ab9dd23bc7cdb29  tree\n";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--tree", "tree"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    // Timestamps and empty directories do not matter; contents do
+    std::fs::remove_dir(tree.join("empty")).unwrap();
+    std::fs::write(tree.join("a.txt"), "hello world").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--tree", "tree"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    std::fs::write(tree.join("a.txt"), "hello world!").unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--tree", "tree"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("9f201780").not());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--tree", "tree/a.txt"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Not a directory"));
+}
+
+#[test]
+#[cfg(all(feature = "sha1", feature = "sha2"))]
+fn test_git_blob() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "hello world\n").unwrap();
+    std::fs::write(dir.path().join("empty"), "").unwrap();
+
+    // `git hash-object a.txt empty`
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--git-blob", "a.txt", "empty"])
+        .assert()
+        .success()
+        .stdout("3b18e512dba79e4c8300dd08aeb37f8e728b8dad  a.txt\ne69de29bb2d1d6434b8b29ae775ad8c2e48c5391  empty\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--git-blob", "-a", "sha256", "-q"])
+        .write_stdin("hello world\n")
+        .assert()
+        .success()
+        .stdout("0bd69098bd9b9cc5934a610ab65da429b525361147faa7b5b922919e9a23143d\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(["--git-blob", "-a", "sha512", "-s", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Git object IDs are SHA-1 or SHA-256"));
+}
+
+#[test]
+#[cfg(feature = "sha2")]
+fn test_hash_progress_json() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "hello world").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "hi").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--progress", "-q", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n"))
+        .stderr(predicate::str::starts_with(
+            "{\"event\":\"progress\",\"files_done\":0,\"files_total\":2,\"bytes_done\":0,\"bytes_total\":13,\"current\":\"a.txt\"}\n",
+        ))
+        .stderr(predicate::str::ends_with(
+            "{\"event\":\"done\",\"files_done\":2,\"files_total\":2,\"bytes_done\":13,\"bytes_total\":13,\"current\":null}\n",
+        ));
+}